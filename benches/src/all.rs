@@ -1,8 +1,6 @@
-#![feature(proc_macro_hygiene, stmt_expr_attributes)]
-
 use criterion::{criterion_group, criterion_main, Criterion};
 
-use yarte::yarte;
+use yarte::TemplateBytes;
 
 criterion_group!(benches, functions);
 criterion_main!(benches);
@@ -14,6 +12,9 @@ fn functions(c: &mut Criterion) {
     // Big table
     const SIZE: usize = 100;
     c.bench_function("Big table", |b| big_table(b, SIZE));
+
+    // Literals across partials and const branches
+    c.bench_function("Coalesced literals", |b| coalesce(b, SIZE));
 }
 
 // Helpers
@@ -56,20 +57,49 @@ struct Team {
     score: u8,
 }
 
-fn teams(b: &mut criterion::Bencher) {
-    let year = 2015u16;
-    let teams = build_teams();
+#[derive(TemplateBytes)]
+#[template(path = "teams")]
+struct Teams {
+    year: u16,
+    teams: Vec<Team>,
+}
 
+fn teams(b: &mut criterion::Bencher) {
+    let t = Teams {
+        year: 2015,
+        teams: build_teams(),
+    };
     b.iter(|| {
-        let _: String = #[yarte]
-        "{{> teams }}";
+        let _ = t.call::<String>(0);
     });
 }
 
+#[derive(TemplateBytes)]
+#[template(path = "big-table")]
+struct BigTable {
+    table: Vec<Vec<usize>>,
+}
+
 fn big_table(b: &mut criterion::Bencher, size: usize) {
-    let table = build_big_table(size);
+    let t = BigTable {
+        table: build_big_table(size),
+    };
+    b.iter(|| {
+        let _ = t.call::<String>(0);
+    });
+}
+
+#[derive(TemplateBytes)]
+#[template(path = "coalesce")]
+struct Coalesce {
+    rows: Vec<(usize, &'static str)>,
+}
+
+fn coalesce(b: &mut criterion::Bencher, size: usize) {
+    let t = Coalesce {
+        rows: (0..size).map(|i| (i, "row")).collect(),
+    };
     b.iter(|| {
-        let _: String = #[yarte]
-        "{{> big-table }}";
+        let _ = t.call::<String>(0);
     });
 }
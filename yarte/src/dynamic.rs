@@ -0,0 +1,892 @@
+//! Templates parsed and rendered at runtime over a `serde_json::Value` context
+//!
+//! Uses the same parser as the derives, so a template can be previewed without
+//! a rebuild and compiled for production. Only the subset of expressions that
+//! can be evaluated over json values is available: paths, fields, indexes,
+//! literals, ranges, arithmetic, comparisons and a few methods like `len`.
+//!
+//! `match`, user helpers, partial blocks, slots, inline partials and template
+//! inheritance return [`DynError::Unsupported`]. Partials are resolved relative
+//! to the template path, the `partials` aliases and directories of `yarte.toml`
+//! aren't read. Ranges of more than [`RANGE_LIMIT`] items return
+//! [`DynError::RangeLimit`].
+//!
+//! ```
+//! # use yarte::dynamic::DynTemplate;
+//! # use serde_json::json;
+//! let t = DynTemplate::new("Hello, {{ name }}!").unwrap();
+//! assert_eq!(t.render(&json!({ "name": "<World>" })).unwrap(), "Hello, &lt;World&gt;!");
+//! ```
+use std::{
+    collections::BTreeMap,
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{PoisonError, RwLock},
+};
+
+use quote::ToTokens;
+use serde::Serialize;
+use serde_json::{Number, Value};
+use syn::{BinOp, Expr, Lit, Member, UnOp};
+
+use yarte_parser::{
+    parse, source_map, AtHelperKind, Helper, Node, Partial, SExpr, SNode, SVExpr, Ws,
+    LOOP_VAR_PREFIX,
+};
+
+use crate::{DisplayFn, Render};
+
+const RECURSION_LIMIT: usize = 128;
+/// Maximum number of items of a range, ranges are built in memory
+pub const RANGE_LIMIT: usize = 1 << 16;
+
+/// Runtime template error
+#[derive(Debug)]
+pub enum DynError {
+    /// Template source can't be parsed
+    Parse {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// Template or partial file can't be read
+    Io(io::Error),
+    /// Context can't be serialized
+    Serialize(serde_json::Error),
+    /// Identifier, field or index not found in the context
+    NotFound(String),
+    /// Expression evaluates to a value of an unexpected type
+    Type(String, &'static str),
+    /// Node or expression only available at compile time
+    Unsupported(String),
+    /// Partials nested beyond the recursion limit
+    RecursionLimit,
+    /// Range with more items than `RANGE_LIMIT`
+    RangeLimit(String),
+}
+
+impl fmt::Display for DynError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use DynError::*;
+        match self {
+            Parse {
+                message,
+                line,
+                column,
+            } => write!(f, "{message} at {line}:{column}"),
+            Io(e) => write!(f, "{e}"),
+            Serialize(e) => write!(f, "{e}"),
+            NotFound(e) => write!(f, "not found `{e}`"),
+            Type(e, expected) => write!(f, "expected {expected} in `{e}`"),
+            Unsupported(e) => write!(f, "not available at runtime: {e}"),
+            RecursionLimit => f.write_str("partial recursion limit"),
+            RangeLimit(e) => write!(f, "range of more than {RANGE_LIMIT} items `{e}`"),
+        }
+    }
+}
+
+impl error::Error for DynError {}
+
+impl From<io::Error> for DynError {
+    fn from(e: io::Error) -> Self {
+        DynError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for DynError {
+    fn from(e: serde_json::Error) -> Self {
+        DynError::Serialize(e)
+    }
+}
+
+/// Template source, parsed on each render like the partials
+struct Source {
+    path: Rc<Path>,
+    src: Box<str>,
+}
+
+impl Source {
+    fn new(path: &Path, mut src: String) -> Source {
+        src.truncate(src.trim_end().len());
+        Source {
+            path: Rc::from(path),
+            src: src.into_boxed_str(),
+        }
+    }
+
+    fn from_path(path: &Path) -> io::Result<Source> {
+        Ok(Source::new(path, fs::read_to_string(path)?))
+    }
+
+    fn nodes(&self) -> Result<Vec<SNode<'_>>, DynError> {
+        // Source map is thread local and only needed for errors of this source
+        source_map::clean();
+        parse(self.path.clone(), &self.src).map_err(|e| {
+            let start = e.span.start();
+            DynError::Parse {
+                message: e.message.to_string(),
+                line: start.line,
+                column: start.column,
+            }
+        })
+    }
+}
+
+/// Template parsed at runtime
+pub struct DynTemplate {
+    source: Source,
+    path: Option<PathBuf>,
+    escape: bool,
+}
+
+impl DynTemplate {
+    /// Parse html template, partials aren't available without a path
+    pub fn new<S: Into<String>>(src: S) -> Result<DynTemplate, DynError> {
+        DynTemplate::checked(Source::new(Path::new("dynamic"), src.into()), None)
+    }
+
+    /// Read and parse html template, partials are resolved relative to it
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<DynTemplate, DynError> {
+        let path = path.as_ref();
+        DynTemplate::checked(Source::from_path(path)?, Some(path.to_owned()))
+    }
+
+    /// Parse errors are returned at creation, before any render
+    fn checked(source: Source, path: Option<PathBuf>) -> Result<DynTemplate, DynError> {
+        source.nodes()?;
+        Ok(DynTemplate {
+            source,
+            path,
+            escape: true,
+        })
+    }
+
+    /// Render without html escape, like `TemplateText`
+    pub fn text(mut self) -> DynTemplate {
+        self.escape = false;
+        self
+    }
+
+    /// Render with a serializable context
+    pub fn render<T: Serialize + ?Sized>(&self, ctx: &T) -> Result<String, DynError> {
+        self.render_value(&serde_json::to_value(ctx)?)
+    }
+
+    /// Render with a json context
+    pub fn render_value(&self, ctx: &Value) -> Result<String, DynError> {
+        let mut i = Interpreter {
+            escape: self.escape,
+            path: self.path.clone(),
+            root: ctx,
+            scopes: vec![],
+            buf: String::new(),
+            next_ws: None,
+            skip_ws: false,
+            recursion: 0,
+        };
+        i.render(&self.source.nodes()?)?;
+        i.flush_ws((false, false));
+
+        Ok(i.buf)
+    }
+}
+
+/// Environment variable enabling the `hot-reload` mode of debug builds
+pub const RELOAD_VAR: &str = "YARTE_RELOAD";
+
+/// Environment variable with the directory of the crate sources in `hot-reload`
+/// mode, `CARGO_MANIFEST_DIR` of the build by default
+pub const RELOAD_DIR_VAR: &str = "YARTE_RELOAD_DIR";
+
+type ReloadError = fn(&Path, &DynError);
+
+static RELOAD_ERROR: RwLock<Option<ReloadError>> = RwLock::new(None);
+
+/// Sets the function called when a template can't be reloaded, before falling
+/// back to the compiled code
+///
+/// ```
+/// yarte::dynamic::on_reload_error(|path, e| eprintln!("{}: {e}", path.display()));
+/// ```
+pub fn on_reload_error(f: ReloadError) {
+    *RELOAD_ERROR.write().unwrap_or_else(PoisonError::into_inner) = Some(f);
+}
+
+/// Wrapper used by the derives in `hot-reload` mode
+///
+/// Templates with a `Serialize` context are re-read and rendered with
+/// [`DynTemplate`] when [`RELOAD_VAR`] is set, any other falls back to the
+/// compiled code.
+#[doc(hidden)]
+pub struct Reload<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait ReloadSerialize {
+    fn reload(&self, root: &str, path: &str, text: bool) -> Option<String>;
+}
+
+impl<'a, T: Serialize> ReloadSerialize for &Reload<'a, T> {
+    fn reload(&self, root: &str, path: &str, text: bool) -> Option<String> {
+        std::env::var_os(RELOAD_VAR)?;
+
+        // `path` is relative to the crate root unless it is out of the crate
+        let path = std::env::var_os(RELOAD_DIR_VAR)
+            .map_or_else(|| PathBuf::from(root), PathBuf::from)
+            .join(path);
+        let res = DynTemplate::from_path(&path).and_then(|t| {
+            if text {
+                t.text().render(self.0)
+            } else {
+                t.render(self.0)
+            }
+        });
+        match res {
+            Ok(s) => Some(s),
+            Err(e) => {
+                if let Some(f) = *RELOAD_ERROR.read().unwrap_or_else(PoisonError::into_inner) {
+                    f(&path, &e);
+                }
+                None
+            }
+        }
+    }
+}
+
+#[doc(hidden)]
+pub trait ReloadFallback {
+    fn reload(&self, _root: &str, _path: &str, _text: bool) -> Option<String> {
+        None
+    }
+}
+
+impl<'a, T> ReloadFallback for Reload<'a, T> {}
+
+struct Scope {
+    /// Value of `this`, without it in blocks like `if`
+    ctx: Option<Value>,
+    vars: BTreeMap<String, Value>,
+    /// Variables of outer scopes are not visible, like in partials with context
+    barrier: bool,
+}
+
+impl Scope {
+    fn new(ctx: Option<Value>) -> Scope {
+        Scope {
+            ctx,
+            vars: BTreeMap::new(),
+            barrier: false,
+        }
+    }
+}
+
+/// Interpreter of `SNode` with the whitespace control of the lowering
+struct Interpreter<'a> {
+    escape: bool,
+    path: Option<PathBuf>,
+    root: &'a Value,
+    scopes: Vec<Scope>,
+    buf: String,
+    next_ws: Option<String>,
+    skip_ws: bool,
+    recursion: usize,
+}
+
+fn to_string(e: &Expr) -> String {
+    e.to_token_stream().to_string()
+}
+
+impl<'a> Interpreter<'a> {
+    fn render(&mut self, nodes: &[SNode]) -> Result<(), DynError> {
+        for n in nodes {
+            match n.t() {
+                Node::Lit(l, lit, r) => self.visit_lit(l, lit.t(), r),
+                Node::Comment(_) => self.skip_ws(),
+                Node::Local(local) => {
+                    self.skip_ws();
+                    self.visit_local(local.t())?;
+                }
+                Node::Expr(ws, e) => {
+                    self.handle_ws(*ws);
+                    let value = self.eval(e.t())?;
+                    self.write(&value, e.t(), self.escape)?;
+                }
+                Node::Safe(ws, e) => {
+                    self.handle_ws(*ws);
+                    let value = self.eval(e.t())?;
+                    self.write(&value, e.t(), false)?;
+                }
+                Node::Raw(ws, l, v, r) => {
+                    self.handle_ws(ws.0);
+                    self.visit_lit(l, v.t(), r);
+                    self.handle_ws(ws.1);
+                }
+                Node::AtHelper(ws, kind, args) => {
+                    self.handle_ws(*ws);
+                    self.visit_at_helper(kind, args)?;
+                }
+                Node::Helper(h) => self.visit_helper(h)?,
+                Node::Partial(Partial(ws, path, args)) => {
+                    self.visit_partial(*ws, path.t(), args)?
+                }
+                Node::RExpr(..) => return Err(unsupported("`{{? }}` expression")),
+                Node::PartialBlock(..) | Node::Block(..) => {
+                    return Err(unsupported("partial block"))
+                }
+                Node::Inline(..) => return Err(unsupported("inline partial")),
+                Node::Extends(..) | Node::NamedBlock(..) | Node::Super(..) => {
+                    return Err(unsupported("template inheritance"))
+                }
+                Node::Fragment(ws, _, nodes) => {
+                    self.handle_ws(ws.0);
+                    self.scopes.push(Scope::new(None));
+                    self.render(nodes)?;
+                    self.scopes.pop();
+                    self.handle_ws(ws.1);
+                }
+                Node::SlotDef(..) | Node::Slot(..) => return Err(unsupported("slot")),
+                Node::Error(..) => return Err(unsupported("compile error")),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn visit_helper(&mut self, h: &Helper) -> Result<(), DynError> {
+        use Helper::*;
+        match h {
+            If((ws, cond, block), ifs, els) => {
+                let branches: Vec<(Ws, Option<&SExpr>, &[SNode])> = Some((ws.0, Some(cond), block))
+                    .into_iter()
+                    .chain(ifs.iter().map(|(ws, cond, block)| (*ws, Some(cond), block)))
+                    .chain(els.iter().map(|(ws, block)| (*ws, None, block)))
+                    .map(|(ws, cond, block)| (ws, cond, block.as_slice()))
+                    .collect();
+
+                self.handle_ws(ws.0);
+                for (i, (open, cond, nodes)) in branches.iter().enumerate() {
+                    if cond.map_or(Ok(true), |cond| self.eval_bool(cond))? {
+                        // next tag or closing tag
+                        let close = branches.get(i + 1).map_or(ws.1, |x| x.0);
+                        return self.branch(*open, nodes, close, ws.1);
+                    }
+                }
+                self.prepare_ws(ws.1);
+                Ok(())
+            }
+            Unless(ws, cond, block) => {
+                self.handle_ws(ws.0);
+                if !self.eval_bool(cond)? {
+                    self.branch(ws.0, block, ws.1, ws.1)
+                } else {
+                    self.prepare_ws(ws.1);
+                    Ok(())
+                }
+            }
+            With(ws, e, block) => {
+                self.handle_ws(ws.0);
+                let ctx = self.eval(e.t())?;
+                self.scopes.push(Scope::new(Some(ctx)));
+                self.render(block)?;
+                self.scopes.pop();
+                self.handle_ws(ws.1);
+                Ok(())
+            }
+            Each(ws, e, block, els) => self.visit_each(*ws, e, block, els),
+            Match(..) => Err(unsupported("match helper")),
+            Defined(_, name, ..) => Err(unsupported(&format!("`{name}` helper"))),
+        }
+    }
+
+    /// Render the taken branch between the `open` and `close` tags,
+    /// `end` is the closing tag of the whole helper
+    fn branch(&mut self, open: Ws, nodes: &[SNode], close: Ws, end: Ws) -> Result<(), DynError> {
+        self.prepare_ws(open);
+        self.scopes.push(Scope::new(None));
+        self.render(nodes)?;
+        self.scopes.pop();
+        self.flush_ws(close);
+        self.prepare_ws(end);
+        Ok(())
+    }
+
+    fn visit_each(
+        &mut self,
+        ws: (Ws, Ws),
+        e: &SExpr,
+        block: &[SNode],
+        els: &Option<(Ws, Vec<SNode>)>,
+    ) -> Result<(), DynError> {
+        let items: Vec<(Value, Option<(Value, Value)>)> = match self.eval(e.t())? {
+            Value::Array(items) => items
+                .into_iter()
+                .map(|x| {
+                    let kv = match &x {
+                        Value::Array(kv) if kv.len() == 2 => Some((kv[0].clone(), kv[1].clone())),
+                        _ => None,
+                    };
+                    (x, kv)
+                })
+                .collect(),
+            Value::Object(map) => map
+                .into_iter()
+                .map(|(k, v)| {
+                    let k = Value::String(k);
+                    (Value::Array(vec![k.clone(), v.clone()]), Some((k, v)))
+                })
+                .collect(),
+            _ => return Err(DynError::Type(to_string(e.t()), "iterable")),
+        };
+
+        self.handle_ws(ws.0);
+        let close = els.as_ref().map_or(ws.1, |(e_ws, _)| *e_ws);
+        let len = items.len();
+        for (index, (item, kv)) in items.into_iter().enumerate() {
+            let mut scope = Scope::new(Some(item));
+            let mut vars = vec![
+                ("index0", Value::from(index)),
+                ("index", (index + 1).into()),
+                ("first", (index == 0).into()),
+            ];
+            // `last`, `key` and `value` are only reachable as `@` loop variables
+            for (name, value) in &vars {
+                scope.vars.insert((*name).into(), value.clone());
+            }
+            vars.push(("last", (index + 1 == len).into()));
+            if let Some((key, value)) = kv {
+                vars.push(("key", key));
+                vars.push(("value", value));
+            }
+            for (name, value) in vars {
+                scope
+                    .vars
+                    .insert(format!("{}{}", LOOP_VAR_PREFIX, name), value);
+            }
+
+            self.prepare_ws(ws.0);
+            self.scopes.push(scope);
+            self.render(block)?;
+            self.scopes.pop();
+            self.flush_ws(close);
+        }
+
+        match els {
+            Some((e_ws, els)) if len == 0 => self.branch(*e_ws, els, ws.1, ws.1),
+            _ => {
+                self.prepare_ws(ws.1);
+                Ok(())
+            }
+        }
+    }
+
+    fn visit_partial(&mut self, ws: Ws, path: &str, args: &SVExpr) -> Result<(), DynError> {
+        self.recursion += 1;
+        if RECURSION_LIMIT < self.recursion {
+            return Err(DynError::RecursionLimit);
+        }
+
+        let parent = match &self.path {
+            Some(parent) => parent.clone(),
+            None => return Err(unsupported("partial without template path")),
+        };
+        let mut buf = PathBuf::from(path);
+        if buf.extension().is_none() {
+            if let Some(ext) = parent.extension() {
+                buf.set_extension(ext);
+            }
+        }
+        let mut p = parent.clone();
+        p.pop();
+        p.push(buf);
+        let source = Source::from_path(&p)?;
+        let nodes = source.nodes()?;
+
+        let mut scope = Scope::new(None);
+        for arg in args.t() {
+            match &**arg {
+                Expr::Assign(assign) => {
+                    let name = to_string(&assign.left);
+                    let value = self.eval(&assign.right)?;
+                    scope.vars.insert(name, value);
+                }
+                e => {
+                    scope.ctx = Some(self.eval(e)?);
+                    scope.barrier = true;
+                }
+            }
+        }
+
+        self.flush_ws(ws);
+        let parent = self.path.replace(p);
+        self.scopes.push(scope);
+        self.render(&nodes)?;
+        self.scopes.pop();
+        self.path = parent;
+        self.prepare_ws(ws);
+
+        self.recursion -= 1;
+        Ok(())
+    }
+
+    fn visit_at_helper(&mut self, kind: &AtHelperKind, args: &SVExpr) -> Result<(), DynError> {
+        let json = match kind {
+            AtHelperKind::Json | AtHelperKind::JsonPretty if args.t().len() == 1 => {
+                let value = self.eval(&args.t()[0])?;
+                if let AtHelperKind::Json = kind {
+                    serde_json::to_string(&value)?
+                } else {
+                    serde_json::to_string_pretty(&value)?
+                }
+            }
+            AtHelperKind::Json | AtHelperKind::JsonPretty => {
+                return Err(unsupported("json helper with many arguments"))
+            }
+            AtHelperKind::Flush => return Ok(()),
+            AtHelperKind::Defined(name) => return Err(unsupported(&format!("`@{name}` helper"))),
+        };
+
+        if self.escape {
+            for c in json.chars() {
+                match c {
+                    '<' => self.buf.push_str("\\u003c"),
+                    '>' => self.buf.push_str("\\u003e"),
+                    '&' => self.buf.push_str("\\u0026"),
+                    c => self.buf.push(c),
+                }
+            }
+        } else {
+            self.buf.push_str(&json);
+        }
+
+        Ok(())
+    }
+
+    fn visit_local(&mut self, local: &syn::Local) -> Result<(), DynError> {
+        let name = match &local.pat {
+            syn::Pat::Ident(ident) => ident.ident.to_string(),
+            syn::Pat::Type(syn::PatType { pat, .. }) => match &**pat {
+                syn::Pat::Ident(ident) => ident.ident.to_string(),
+                _ => return Err(unsupported("let with pattern")),
+            },
+            _ => return Err(unsupported("let with pattern")),
+        };
+        let value = match &local.init {
+            Some((_, e)) => self.eval(e)?,
+            None => Value::Null,
+        };
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.vars.insert(name, value);
+            }
+            None => {
+                let mut scope = Scope::new(None);
+                scope.vars.insert(name, value);
+                self.scopes.push(scope);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, value: &Value, e: &Expr, escape: bool) -> Result<(), DynError> {
+        use std::fmt::Write;
+        match value {
+            Value::String(s) if escape => write!(self.buf, "{}", DisplayFn::new(|f| s.render(f)))
+                .map_err(|_| unsupported("render"))?,
+            Value::String(s) => self.buf.push_str(s),
+            Value::Number(n) => write!(self.buf, "{n}").map_err(|_| unsupported("render"))?,
+            Value::Bool(b) => write!(self.buf, "{b}").map_err(|_| unsupported("render"))?,
+            _ => return Err(DynError::Type(to_string(e), "string, number or bool")),
+        }
+
+        Ok(())
+    }
+
+    fn eval_bool(&self, e: &SExpr) -> Result<bool, DynError> {
+        match self.eval(e.t())? {
+            Value::Bool(b) => Ok(b),
+            _ => Err(DynError::Type(to_string(e.t()), "bool")),
+        }
+    }
+
+    fn eval(&self, e: &Expr) -> Result<Value, DynError> {
+        match e {
+            Expr::Path(syn::ExprPath {
+                qself: None, path, ..
+            }) => {
+                let idents: Vec<String> =
+                    path.segments.iter().map(|x| x.ident.to_string()).collect();
+                let (ident, supers) = idents.split_last().expect("some segment");
+                if supers.iter().all(|x| x == "super") {
+                    self.resolve(ident, supers.len())
+                        .ok_or_else(|| DynError::NotFound(to_string(e)))
+                } else {
+                    Err(unsupported(&to_string(e)))
+                }
+            }
+            Expr::Field(syn::ExprField { base, member, .. }) => {
+                let base = self.eval(base)?;
+                match member {
+                    Member::Named(ident) => base.get(ident.to_string()),
+                    Member::Unnamed(index) => base.get(index.index as usize),
+                }
+                .cloned()
+                .ok_or_else(|| DynError::NotFound(to_string(e)))
+            }
+            Expr::Index(syn::ExprIndex { expr, index, .. }) => {
+                let base = self.eval(expr)?;
+                match self.eval(index)? {
+                    Value::Number(n) => n.as_u64().and_then(|n| base.get(n as usize)),
+                    Value::String(s) => base.get(s),
+                    _ => None,
+                }
+                .cloned()
+                .ok_or_else(|| DynError::NotFound(to_string(e)))
+            }
+            Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+                Lit::Str(s) => Ok(s.value().into()),
+                Lit::Char(c) => Ok(c.value().to_string().into()),
+                Lit::Bool(b) => Ok(b.value.into()),
+                Lit::Int(n) => n
+                    .base10_parse::<i64>()
+                    .map(Into::into)
+                    .map_err(|_| unsupported(&to_string(e))),
+                Lit::Float(n) => n
+                    .base10_parse::<f64>()
+                    .ok()
+                    .and_then(Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| unsupported(&to_string(e))),
+                _ => Err(unsupported(&to_string(e))),
+            },
+            Expr::Unary(syn::ExprUnary { op, expr, .. }) => match (op, self.eval(expr)?) {
+                (UnOp::Not(_), Value::Bool(b)) => Ok((!b).into()),
+                (UnOp::Neg(_), Value::Number(n)) => match n.as_i64() {
+                    Some(n) => n
+                        .checked_neg()
+                        .map(Into::into)
+                        .ok_or_else(|| DynError::Type(to_string(e), "no overflow")),
+                    None => Ok(number(-n.as_f64().unwrap_or_default())),
+                },
+                (UnOp::Deref(_), v) => Ok(v),
+                _ => Err(DynError::Type(to_string(e), "bool or number")),
+            },
+            Expr::Binary(syn::ExprBinary {
+                left, op, right, ..
+            }) => self.eval_binary(e, left, op, right),
+            Expr::Paren(syn::ExprParen { expr, .. })
+            | Expr::Group(syn::ExprGroup { expr, .. })
+            | Expr::Reference(syn::ExprReference { expr, .. }) => self.eval(expr),
+            Expr::Array(syn::ExprArray { elems, .. })
+            | Expr::Tuple(syn::ExprTuple { elems, .. }) => elems
+                .iter()
+                .map(|x| self.eval(x))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            Expr::Range(syn::ExprRange {
+                from: Some(from),
+                limits,
+                to: Some(to),
+                ..
+            }) => {
+                let (from, to) = match (self.eval(from)?, self.eval(to)?) {
+                    (Value::Number(from), Value::Number(to)) => (from.as_i64(), to.as_i64()),
+                    _ => (None, None),
+                };
+                let (Some(from), Some(to)) = (from, to) else {
+                    return Err(DynError::Type(to_string(e), "integer range"));
+                };
+                let closed = matches!(limits, syn::RangeLimits::Closed(_));
+                if RANGE_LIMIT as i128 <= i128::from(to) - i128::from(from) - i128::from(!closed) {
+                    return Err(DynError::RangeLimit(to_string(e)));
+                }
+                Ok(if closed {
+                    (from..=to).map(Value::from).collect()
+                } else {
+                    (from..to).map(Value::from).collect()
+                })
+            }
+            Expr::MethodCall(syn::ExprMethodCall {
+                receiver,
+                method,
+                args,
+                ..
+            }) if args.is_empty() => {
+                let value = self.eval(receiver)?;
+                match (method.to_string().as_str(), value) {
+                    ("len", Value::String(s)) => Ok(s.len().into()),
+                    ("len", Value::Array(a)) => Ok(a.len().into()),
+                    ("len", Value::Object(o)) => Ok(o.len().into()),
+                    ("is_empty", Value::String(s)) => Ok(s.is_empty().into()),
+                    ("is_empty", Value::Array(a)) => Ok(a.is_empty().into()),
+                    ("is_empty", Value::Object(o)) => Ok(o.is_empty().into()),
+                    ("is_some", v) => Ok((!v.is_null()).into()),
+                    ("is_none", v) => Ok(v.is_null().into()),
+                    ("unwrap", v) if !v.is_null() => Ok(v),
+                    ("iter" | "clone" | "as_str" | "as_ref", v) => Ok(v),
+                    ("to_string", v @ Value::String(_)) => Ok(v),
+                    ("to_string", v) => Ok(v.to_string().into()),
+                    _ => Err(unsupported(&to_string(e))),
+                }
+            }
+            _ => Err(unsupported(&to_string(e))),
+        }
+    }
+
+    fn eval_binary(
+        &self,
+        e: &Expr,
+        left: &Expr,
+        op: &BinOp,
+        right: &Expr,
+    ) -> Result<Value, DynError> {
+        let type_err = || DynError::Type(to_string(e), "same type operands");
+        let left = self.eval(left)?;
+        match op {
+            BinOp::And(_) | BinOp::Or(_) => {
+                let left = left.as_bool().ok_or_else(type_err)?;
+                if let BinOp::And(_) = op {
+                    if !left {
+                        return Ok(false.into());
+                    }
+                } else if left {
+                    return Ok(true.into());
+                }
+                return self
+                    .eval(right)?
+                    .as_bool()
+                    .map(Into::into)
+                    .ok_or_else(type_err);
+            }
+            _ => (),
+        }
+
+        let right = self.eval(right)?;
+        let cmp = match (&left, &right) {
+            (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        match op {
+            BinOp::Eq(_) => return Ok(cmp.map_or(left == right, |x| x.is_eq()).into()),
+            BinOp::Ne(_) => return Ok(cmp.map_or(left != right, |x| x.is_ne()).into()),
+            BinOp::Lt(_) => return cmp.map(|x| x.is_lt().into()).ok_or_else(type_err),
+            BinOp::Le(_) => return cmp.map(|x| x.is_le().into()).ok_or_else(type_err),
+            BinOp::Gt(_) => return cmp.map(|x| x.is_gt().into()).ok_or_else(type_err),
+            BinOp::Ge(_) => return cmp.map(|x| x.is_ge().into()).ok_or_else(type_err),
+            _ => (),
+        }
+
+        let (a, b) = match (left, right) {
+            (Value::Number(a), Value::Number(b)) => (a, b),
+            _ => return Err(DynError::Type(to_string(e), "number operands")),
+        };
+        if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+            let value = match op {
+                BinOp::Add(_) => a.checked_add(b),
+                BinOp::Sub(_) => a.checked_sub(b),
+                BinOp::Mul(_) => a.checked_mul(b),
+                BinOp::Div(_) => a.checked_div(b),
+                BinOp::Rem(_) => a.checked_rem(b),
+                _ => return Err(unsupported(&to_string(e))),
+            };
+            return value
+                .map(Into::into)
+                .ok_or_else(|| DynError::Type(to_string(e), "no overflow"));
+        }
+
+        let (a, b) = (
+            a.as_f64().unwrap_or_default(),
+            b.as_f64().unwrap_or_default(),
+        );
+        match op {
+            BinOp::Add(_) => Ok(number(a + b)),
+            BinOp::Sub(_) => Ok(number(a - b)),
+            BinOp::Mul(_) => Ok(number(a * b)),
+            BinOp::Div(_) => Ok(number(a / b)),
+            BinOp::Rem(_) => Ok(number(a % b)),
+            _ => Err(unsupported(&to_string(e))),
+        }
+    }
+
+    /// Variables of the scopes and after that fields of `this`
+    fn resolve(&self, ident: &str, supers: usize) -> Option<Value> {
+        if supers == 0 {
+            if ident == "self" {
+                return Some(self.root.clone());
+            }
+            for scope in self.scopes.iter().rev() {
+                if let Some(v) = scope.vars.get(ident) {
+                    return Some(v.clone());
+                }
+                if scope.barrier {
+                    break;
+                }
+            }
+        }
+
+        let ctx = self
+            .scopes
+            .iter()
+            .rev()
+            .filter_map(|x| x.ctx.as_ref())
+            .chain(Some(self.root))
+            .nth(supers)?;
+        if ident == "this" {
+            Some(ctx.clone())
+        } else {
+            ctx.get(ident).cloned()
+        }
+    }
+
+    fn visit_lit(&mut self, lws: &str, lit: &str, rws: &str) {
+        if !lws.is_empty() {
+            if self.skip_ws {
+                self.skip_ws = false;
+            } else if lit.is_empty() {
+                self.next_ws = Some(lws.into());
+            } else {
+                self.buf.push_str(lws);
+            }
+        }
+
+        if !lit.is_empty() {
+            self.buf.push_str(lit);
+        }
+
+        if !rws.is_empty() {
+            self.next_ws = Some(rws.into());
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        self.next_ws = None;
+        self.skip_ws = true;
+    }
+
+    fn handle_ws(&mut self, ws: Ws) {
+        self.flush_ws(ws);
+        self.prepare_ws(ws);
+    }
+
+    fn flush_ws(&mut self, ws: Ws) {
+        if let Some(val) = self.next_ws.take() {
+            if !ws.0 {
+                self.buf.push_str(&val);
+            }
+        }
+    }
+
+    fn prepare_ws(&mut self, ws: Ws) {
+        self.skip_ws = ws.1;
+    }
+}
+
+fn number(n: f64) -> Value {
+    Number::from_f64(n).map_or(Value::Null, Value::Number)
+}
+
+fn unsupported(s: &str) -> DynError {
+    DynError::Unsupported(s.into())
+}
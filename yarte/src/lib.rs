@@ -6,7 +6,13 @@
 //!
 //! [Yarte book](https://yarte.netlify.com)
 //!
-use std::fmt::{self, Write};
+use std::{
+    fmt::{self, Write},
+    result,
+};
+
+#[cfg(feature = "dynamic")]
+pub mod dynamic;
 
 /// Add auto sized buffer functionality wrapping ywrites macros
 ///
@@ -26,7 +32,10 @@ pub use yarte_derive::auto;
 pub use yarte_derive::ywrite;
 pub use yarte_helpers::{
     helpers::{
-        display_fn::DisplayFn, io_fmt::IoFmt, Aligned256, IntoCopyIterator, Render, RenderA,
+        display_fn::DisplayFn,
+        escape::{Escaped, EscapedJson, Escaper},
+        io_fmt::IoFmt,
+        Aligned256, IntoCopyIterator, Render, RenderA, WithLastIterator,
     },
     recompile, Error, Result,
 };
@@ -42,12 +51,72 @@ pub trait TemplateTrait: fmt::Display {
     /// Approximation of output size used in method `call`.
     /// Yarte implements an heuristic algorithm of allocation.
     fn size_hint() -> usize;
+
+    /// Bytes of the literals written in every render, part of `size_hint`
+    fn size_hint_static() -> usize {
+        0
+    }
+
+    /// Estimation of the expressions, loops and conditionals, part of `size_hint`
+    fn size_hint_dynamic() -> usize {
+        Self::size_hint() - Self::size_hint_static()
+    }
+}
+
+/// Fragment of a template, renders the section marked with `{{#fragment "name"}}`
+///
+/// Returned by the `fragment_<name>` methods of the `Template` derives
+pub struct Fragment<'a, T> {
+    template: &'a T,
+    render: fn(&T, &mut fmt::Formatter) -> fmt::Result,
+    size_hint: usize,
+}
+
+impl<'a, T> Fragment<'a, T> {
+    #[doc(hidden)]
+    pub fn new(
+        template: &'a T,
+        render: fn(&T, &mut fmt::Formatter) -> fmt::Result,
+        size_hint: usize,
+    ) -> Self {
+        Fragment {
+            template,
+            render,
+            size_hint,
+        }
+    }
+
+    /// which will write this fragment
+    pub fn call(&self) -> Result<String> {
+        let mut buf = String::with_capacity(self.size_hint);
+        write!(buf, "{self}").map(|_| buf)
+    }
+}
+
+impl<'a, T> fmt::Display for Fragment<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.render)(self.template, f)
+    }
 }
 
 pub use yarte_derive::{Template, TemplateText};
 pub use TemplateTrait as Template;
 pub use TemplateTrait as TemplateText;
 
+/// Template trait with fallible expressions, the `?` of an expression
+/// returns its error from `try_call`
+pub trait TemplateTryTrait {
+    /// Error of the render, the `fmt::Error` of a `Display` is converted into it
+    type Error: From<fmt::Error>;
+
+    /// which will write this template or return the first error
+    fn try_call(&self) -> result::Result<String, Self::Error>;
+}
+
+pub use yarte_derive::{TemplateTry, TemplateTryText};
+pub use TemplateTryTrait as TemplateTry;
+pub use TemplateTryTrait as TemplateTryText;
+
 #[cfg(feature = "html-min")]
 pub use yarte_derive::TemplateMin;
 #[cfg(feature = "html-min")]
@@ -131,6 +200,31 @@ pub trait TemplateBytesTrait {
     fn write_ccall<B: Buffer>(self, buf: &mut B);
 }
 
+#[cfg(feature = "bytes-buf")]
+/// Template trait with fallible expressions
+pub trait TemplateBytesTryTrait {
+    /// Error of the `?` in the expressions
+    type Error;
+
+    /// Writes to buffer and return it freeze or the first error
+    ///
+    /// # Panics
+    /// Render length overflows usize
+    fn try_call<B: Buffer>(&self, capacity: usize) -> result::Result<B::Freeze, Self::Error>;
+    /// Writes to buffer or return the first error, the buffer keeps the output before it
+    ///
+    /// # Panics
+    /// Render length overflows usize
+    fn try_write_call<B: Buffer>(&self, buf: &mut B) -> result::Result<(), Self::Error>;
+}
+
+#[cfg(feature = "bytes-buf")]
+pub use yarte_derive::{TemplateBytesTry, TemplateBytesTryText};
+#[cfg(feature = "bytes-buf")]
+pub use TemplateBytesTryTrait as TemplateBytesTry;
+#[cfg(feature = "bytes-buf")]
+pub use TemplateBytesTryTrait as TemplateBytesTryText;
+
 #[cfg(all(
     any(feature = "bytes-buf", feature = "bytes-buf-tokio2"),
     feature = "html-min"
@@ -148,12 +242,56 @@ pub use TemplateBytesTrait as TemplateBytesText;
 ))]
 pub use TemplateBytesTrait as TemplateBytesMin;
 
+#[cfg(feature = "bytes-buf")]
+pub use yarte_derive::{TemplateDual, TemplateDualText};
+
 #[cfg(any(feature = "bytes-buf", feature = "bytes-buf-tokio2"))]
-pub use yarte_helpers::helpers::{RenderBytes, RenderBytesA, RenderBytesSafe, RenderBytesSafeA};
+pub use yarte_helpers::helpers::{
+    render_display, RenderBytes, RenderBytesA, RenderBytesSafe, RenderBytesSafeA,
+};
 
 #[cfg(feature = "bytes-buf")]
 pub use buf_min::Buffer;
 
+#[cfg(feature = "bytes-buf")]
+/// Template trait, writes to `io::Write`
+pub trait TemplateIoTrait {
+    /// Writes to `w` through a buffer of 8 KiB and flushes it
+    ///
+    /// Returns the first `io::Error` of the writer, the output after it is discarded.
+    /// The template is still rendered to the end, an error doesn't stop the render.
+    fn write_io<W: std::io::Write>(&self, w: W) -> std::io::Result<()>;
+}
+
+#[cfg(feature = "bytes-buf")]
+pub use yarte_derive::{TemplateIo, TemplateIoText};
+#[cfg(feature = "bytes-buf")]
+#[doc(hidden)]
+pub use yarte_helpers::helpers::io_buf::IoBuffer as __IoBuffer;
+#[cfg(feature = "bytes-buf")]
+pub use TemplateIoTrait as TemplateIo;
+#[cfg(feature = "bytes-buf")]
+pub use TemplateIoTrait as TemplateIoText;
+
+#[cfg(feature = "stream")]
+/// Template trait, renders in chunks
+pub trait TemplateStreamTrait {
+    /// Stream of the rendered chunks, sent at the explicit `{{@flush}}`
+    /// and at the end of every iteration of the outer `{{#each}}`
+    ///
+    /// `capacity` is the initial capacity of each chunk
+    fn stream<B: Buffer>(&self, capacity: usize) -> impl Stream<Item = B::Freeze>;
+}
+
+#[cfg(feature = "stream")]
+pub use yarte_derive::{TemplateStream, TemplateStreamText};
+#[cfg(feature = "stream")]
+pub use yarte_helpers::helpers::stream::{RenderStream, Stream};
+#[cfg(feature = "stream")]
+pub use TemplateStreamTrait as TemplateStream;
+#[cfg(feature = "stream")]
+pub use TemplateStreamTrait as TemplateStreamText;
+
 #[cfg(feature = "json")]
 pub use yarte_derive::Serialize;
 #[cfg(feature = "json")]
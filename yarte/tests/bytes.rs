@@ -104,3 +104,21 @@ fn test_nested_for() {
     s.write_call(&mut b);
     assert_eq!(b, "1\n  0foo1bar2baz2\n  0bar1baz")
 }
+
+#[derive(TemplateBytes)]
+#[template(
+    src = "<ul>{{ let a = n + 1 }}{{# if true }}<li>{{/if }}{{ a }}</li>{{ let b = 2 }}</ul>{{ b }}"
+)]
+struct Coalesce {
+    n: usize,
+}
+
+#[test]
+fn test_coalesce() {
+    // Capacity 0 so every literal grows the buffer
+    assert_eq!(Coalesce { n: 1 }.call::<String>(0), "<ul><li>2</li></ul>2");
+
+    let mut b = String::new();
+    Coalesce { n: 41 }.write_call(&mut b);
+    assert_eq!(b, "<ul><li>42</li></ul>2")
+}
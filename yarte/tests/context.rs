@@ -0,0 +1,202 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "<p title=\"{{ s }}\">{{ s }}</p><!-- {{ s }} --><title>{{ s }}</title>")]
+struct HtmlTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_html() {
+    let t = HtmlTemplate { s: "<a'\">" };
+    assert_eq!(
+        t.call().unwrap(),
+        "<p title=\"&lt;a&#x27;&quot;&gt;\">&lt;a&#x27;&quot;&gt;</p>\
+         <!-- &lt;a&#x27;&quot;&gt; --><title>&lt;a&#x27;&quot;&gt;</title>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<a href=\"{{ url }}\"></a><a href='/u/{{ path }}?q={{ q }}'></a>\
+                  <img alt=\"{{ q }}\" src=\"{{ url }}\">"
+)]
+struct UrlTemplate<'a> {
+    url: &'a str,
+    path: &'a str,
+    q: &'a str,
+}
+
+#[test]
+fn test_url() {
+    let t = UrlTemplate {
+        url: "https://x.org/a b?c=1&d=2",
+        path: "a/b'",
+        q: "a&b=c d",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<a href=\"https://x.org/a%20b?c=1&amp;d=2\"></a>\
+         <a href='/u/a/b%27?q=a%26b%3Dc%20d'></a>\
+         <img alt=\"a&amp;b=c d\" src=\"https://x.org/a%20b?c=1&amp;d=2\">"
+    );
+
+    let t = UrlTemplate {
+        url: "javascript:alert(1)",
+        path: "",
+        q: "",
+    };
+    assert!(t.call().unwrap().starts_with("<a href=\"about:invalid\">"));
+}
+
+#[derive(Template)]
+#[template(src = "{{#each items ~}}
+    <a href=\"{{#if first }}/x{{/if }}{{ this }}\">{{ this }}</a>
+{{~/each }}")]
+struct BranchTemplate<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_branches() {
+    let t = BranchTemplate {
+        items: &["javascript:a", "b/c"],
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<a href=\"/xjavascript%3Aa\">javascript:a</a><a href=\"b%2Fc\">b&#x2f;c</a>"
+    );
+}
+
+#[derive(Template)]
+#[template(src = "<script>var s = '{{ s }}'; // {{ s }}
+</script><button onclick=\"f('{{ s }}')\"></button>")]
+struct JsTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_js() {
+    let t = JsTemplate { s: "</script>'\"" };
+    assert_eq!(
+        t.call().unwrap(),
+        "<script>var s = '\\u003c/script\\u003e\\u0027\\u0022'; \
+         // \\u003c/script\\u003e\\u0027\\u0022\n</script>\
+         <button onclick=\"f('\\u003c/script\\u003e\\u0027\\u0022')\"></button>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<style>p::after { content: \"{{ s }}\"; color: {{ color }} }</style>\
+                  <p style=\"color: {{ color }}\"></p>"
+)]
+struct CssTemplate<'a> {
+    s: &'a str,
+    color: &'a str,
+}
+
+#[test]
+fn test_css() {
+    let t = CssTemplate {
+        s: "</style>",
+        color: "#fff",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<style>p::after { content: \"\\3c \\2f style\\3e \"; color: #fff }</style>\
+         <p style=\"color: #fff\"></p>"
+    );
+
+    let t = CssTemplate {
+        s: "",
+        color: "red; background: url(x)",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<style>p::after { content: \"\"; color: invalid }</style>\
+         <p style=\"color: invalid\"></p>"
+    );
+}
+
+#[cfg(feature = "json")]
+mod json {
+    use super::*;
+    use serde::Serialize;
+    use yarte_helpers::at_helpers::*;
+
+    #[derive(Serialize)]
+    struct Data {
+        s: &'static str,
+    }
+
+    #[derive(Template)]
+    #[template(src = "<script>var d = {{ @json d }};</script><p onclick='f({{ @json d }})'></p>")]
+    struct JsonTemplate {
+        d: Data,
+    }
+
+    #[test]
+    fn test_json() {
+        let t = JsonTemplate {
+            d: Data { s: "</script>'" },
+        };
+        assert_eq!(
+            t.call().unwrap(),
+            "<script>var d = {\"s\":\"\\u003c/script\\u003e'\"};</script>\
+             <p onclick='f({&#34;s&#34;:&#34;\\u003c/script\\u003e&#39;&#34;})'></p>"
+        );
+    }
+}
+
+#[cfg(feature = "bytes-buf")]
+mod bytes {
+    use yarte::TemplateBytes;
+
+    #[derive(TemplateBytes)]
+    #[template(src = "<a href=\"/u/{{ path }}?q={{ q }}\" onclick=\"f('{{ q }}')\"></a>")]
+    struct UrlTemplate<'a> {
+        path: &'a str,
+        q: &'a str,
+    }
+
+    #[test]
+    fn test_url_bytes() {
+        let t = UrlTemplate {
+            path: "a b",
+            q: "'&",
+        };
+        assert_eq!(
+            t.call::<String>(0),
+            "<a href=\"/u/a%20b?q=%27%26\" onclick=\"f('\\u0027\\u0026')\"></a>"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    mod json {
+        use yarte::{Serialize, TemplateBytes};
+        use yarte_helpers::at_helpers::*;
+
+        #[derive(Serialize)]
+        struct Data {
+            s: &'static str,
+        }
+
+        #[derive(TemplateBytes)]
+        #[template(src = "<script>var d = {{ @json d }};</script>")]
+        struct JsonTemplate {
+            d: Data,
+        }
+
+        #[test]
+        fn test_json_bytes() {
+            let t = JsonTemplate {
+                d: Data { s: "</script>" },
+            };
+            assert_eq!(
+                t.call::<String>(0),
+                "<script>var d = {\"s\":\"\\u003c/script\\u003e\"};</script>"
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "bytes-buf")]
+
+use yarte::{TemplateBytesTrait, TemplateDual, TemplateDualText, TemplateTrait};
+
+#[derive(TemplateDual)]
+#[template(src = "<p>{{ name }}: {{# each items }}{{ this }},{{/each }}</p>")]
+struct ListTemplate {
+    name: &'static str,
+    items: Vec<usize>,
+}
+
+#[test]
+fn test_dual() {
+    let t = ListTemplate {
+        name: "<a>",
+        items: vec![1, 2],
+    };
+    let expected = "<p>&lt;a&gt;: 1,2,</p>";
+    assert_eq!(TemplateTrait::call(&t).unwrap(), expected);
+    assert_eq!(t.to_string(), expected);
+    assert_eq!(TemplateBytesTrait::call::<String>(&t, 64), expected);
+    assert_eq!(t.ccall::<Vec<u8>>(64), expected.as_bytes());
+}
+
+#[derive(TemplateDualText)]
+#[template(src = "{{ a }}{{#fragment \"b\"}}{{ b }}{{/fragment}}")]
+struct TextTemplate {
+    a: &'static str,
+    b: f64,
+}
+
+#[test]
+fn test_dual_text() {
+    let t = TextTemplate { a: "<a>", b: 1.5 };
+    assert_eq!(t.to_string(), "<a>1.5");
+    assert_eq!(TemplateBytesTrait::call::<String>(&t, 64), "<a>1.5");
+    assert_eq!(t.fragment_b().call().unwrap(), "1.5");
+    assert_eq!(t.fragment_bytes_b::<String>(64), "1.5");
+}
@@ -0,0 +1,220 @@
+#![cfg(feature = "dynamic")]
+
+use serde::Serialize;
+use serde_json::json;
+
+use yarte::{
+    dynamic::{DynError, DynTemplate},
+    Template, TemplateText,
+};
+
+fn path(name: &str) -> String {
+    format!("{}/templates/{name}.hbs", env!("CARGO_MANIFEST_DIR"))
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "for")]
+struct ForTemplate<'a> {
+    strings: Vec<&'a str>,
+}
+
+#[test]
+fn test_each() {
+    let t = ForTemplate {
+        strings: vec!["foo", "bar", "baz"],
+    };
+    let d = DynTemplate::from_path(path("for")).unwrap();
+    assert_eq!(d.render(&t).unwrap(), t.call().unwrap());
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "nested-for")]
+struct NestedForTemplate<'a> {
+    seqs: &'a [&'a [&'a str]],
+}
+
+#[test]
+fn test_nested_each() {
+    let alpha: &[&str] = &["foo", "bar", "baz"];
+    let numbers: &[&str] = &["bar", "baz"];
+    let seqs: &[&[&str]] = &[alpha, numbers];
+    let t = NestedForTemplate { seqs };
+    let d = DynTemplate::from_path(path("nested-for")).unwrap();
+    assert_eq!(d.render(&t).unwrap(), t.call().unwrap());
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "each-else")]
+struct EachElse {
+    items: Vec<&'static str>,
+}
+
+#[test]
+fn test_each_else() {
+    let d = DynTemplate::from_path(path("each-else")).unwrap();
+    for items in [vec![], vec!["<a>", "b"]] {
+        let t = EachElse { items };
+        assert_eq!(d.render(&t).unwrap(), t.call().unwrap());
+    }
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "else-if")]
+struct ElseIf {
+    cond: bool,
+    check: bool,
+}
+
+#[test]
+fn test_else_if() {
+    let d = DynTemplate::from_path(path("else-if")).unwrap();
+    for (cond, check) in [(true, false), (false, true), (false, false)] {
+        let t = ElseIf { cond, check };
+        assert_eq!(d.render(&t).unwrap(), t.call().unwrap());
+    }
+}
+
+const SRC: &str = "{{ let n = user.visits * 2 }}
+{{~#with user ~}}
+    {{ name }} <{{ super::site }}>{{ n }}
+    {{~#unless tags.is_empty() }}: {{#each tags }}{{ this }}{{#if !@last }},{{/if }}{{/each }}{{/unless }}
+{{~/with }}";
+
+#[derive(Serialize)]
+struct User {
+    name: &'static str,
+    visits: u32,
+    tags: Vec<&'static str>,
+}
+
+#[derive(TemplateText, Serialize)]
+#[template(src = "{{ let n = user.visits * 2 }}
+{{~#with user ~}}
+    {{ name }} <{{ super::site }}>{{ n }}
+    {{~#unless tags.is_empty() }}: {{#each tags }}{{ this }}{{#if !@last }},{{/if }}{{/each }}{{/unless }}
+{{~/with }}")]
+struct Scopes {
+    site: &'static str,
+    user: User,
+}
+
+#[test]
+fn test_scopes() {
+    let t = Scopes {
+        site: "yarte",
+        user: User {
+            name: "foo",
+            visits: 21,
+            tags: vec!["a", "b"],
+        },
+    };
+    let d = DynTemplate::new(SRC).unwrap().text();
+    assert_eq!(d.render(&t).unwrap(), t.call().unwrap());
+    assert_eq!(d.render(&t).unwrap(), "foo <yarte>42: a,b");
+}
+
+#[derive(Template)]
+#[template(path = "dynamic-partial")]
+struct Partial;
+
+#[test]
+fn test_partial() {
+    let d = DynTemplate::from_path(path("dynamic-partial")).unwrap();
+    assert_eq!(d.render(&json!({})).unwrap(), Partial.call().unwrap());
+}
+
+#[test]
+fn test_escape() {
+    let ctx = json!({ "a": "<b>", "c": [1, 2.5, true] });
+    let d = DynTemplate::new("{{ a }}{{{ a }}}{{ c[0] + 1 }}{{ c.1 }}{{ c[2] }}").unwrap();
+    assert_eq!(d.render(&ctx).unwrap(), "&lt;b&gt;<b>22.5true");
+}
+
+#[test]
+fn test_fragment() {
+    let d =
+        DynTemplate::new("<ul>{{~#fragment \"item\" ~}} <li>{{ a }}</li> {{~/fragment ~}}</ul>")
+            .unwrap();
+    assert_eq!(d.render(&json!({ "a": 1 })).unwrap(), "<ul><li>1</li></ul>");
+}
+
+#[test]
+fn test_errors() {
+    assert!(matches!(
+        DynTemplate::new("{{#if foo }}"),
+        Err(DynError::Parse { line: 1, .. })
+    ));
+
+    let d = DynTemplate::new("{{ foo.bar }}").unwrap();
+    assert!(matches!(
+        d.render(&json!({ "foo": {} })),
+        Err(DynError::NotFound(_))
+    ));
+
+    let d = DynTemplate::new("{{#if foo }}{{/if }}").unwrap();
+    assert!(matches!(
+        d.render(&json!({ "foo": 1 })),
+        Err(DynError::Type(_, "bool"))
+    ));
+
+    let d = DynTemplate::new("{{ -n }}").unwrap();
+    assert_eq!(d.render(&json!({ "n": 1 })).unwrap(), "-1");
+    assert!(matches!(
+        d.render(&json!({ "n": i64::MIN })),
+        Err(DynError::Type(_, "no overflow"))
+    ));
+}
+
+#[test]
+fn test_range_limit() {
+    use yarte::dynamic::RANGE_LIMIT;
+
+    let d = DynTemplate::new("{{#each 0..n }}{{/each }}").unwrap();
+    assert_eq!(d.render(&json!({ "n": RANGE_LIMIT })).unwrap(), "");
+    assert!(matches!(
+        d.render(&json!({ "n": RANGE_LIMIT + 1 })),
+        Err(DynError::RangeLimit(_))
+    ));
+    let d = DynTemplate::new("{{#each 1..=n }}{{/each }}").unwrap();
+    assert_eq!(d.render(&json!({ "n": RANGE_LIMIT })).unwrap(), "");
+    assert!(matches!(
+        d.render(&json!({ "n": i64::MAX })),
+        Err(DynError::RangeLimit(_))
+    ));
+    let d = DynTemplate::new("{{#each n..=n }}{{ this }}{{/each }}").unwrap();
+    assert_eq!(
+        d.render(&json!({ "n": i64::MAX })).unwrap(),
+        i64::MAX.to_string()
+    );
+    let d = DynTemplate::new("{{#each 0..9223372036854775807 }}{{/each }}").unwrap();
+    assert!(matches!(d.render(&json!({})), Err(DynError::RangeLimit(_))));
+}
+
+#[test]
+fn test_unsupported() {
+    for src in [
+        "{{#match foo }}{{ when _ }}{{/match }}",
+        "{{#foo bar }}{{/foo }}",
+        "{{ @foo bar }}",
+        "{{#> layout }}{{/layout }}",
+        "{{#*inline \"foo\" }}{{/inline }}",
+        "{{#extends layout }}{{/extends }}",
+        "{{> @slot header }}",
+        "{{> foo }}",
+    ] {
+        let d = DynTemplate::new(src).unwrap();
+        assert!(
+            matches!(
+                d.render(&json!({ "bar": 1 })),
+                Err(DynError::Unsupported(_))
+            ),
+            "{src}"
+        );
+    }
+
+    // Partials are resolved relative to the template, `[partials]` aliases
+    // and `dir`/`dirs` of the configuration aren't read at runtime
+    let d = DynTemplate::from_path(path("deep/more/card/hi")).unwrap();
+    let ctx = json!({ "name": "foo", "lastname": "bar" });
+    assert!(matches!(d.render(&ctx), Err(DynError::Io(_))));
+}
@@ -54,3 +54,131 @@ fn test_for_range() {
     let s = ForRangeTemplate { init: -1, end: 1 };
     assert_eq!(s.call().unwrap(), "foo\nfoo\nbar\nbar\nfoo\nbar\nbar\n");
 }
+
+#[derive(Template)]
+#[template(path = "each-else")]
+struct EachElseTemplate<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_each_else() {
+    let s = EachElseTemplate {
+        items: &["foo", "bar"],
+    };
+    assert_eq!(s.call().unwrap(), "<ul><li>1. foo</li><li>2. bar</li></ul>");
+
+    let s = EachElseTemplate { items: &[] };
+    assert_eq!(s.call().unwrap(), "<ul><li>nothing</li></ul>");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each 0..n }}{{#each 0..this }}{{ this }}{{ else }}-{{/each }}{{ else }}empty{{/each }}"
+)]
+struct EachElseNestedTemplate {
+    n: usize,
+}
+
+#[test]
+fn test_each_else_nested() {
+    assert_eq!(EachElseNestedTemplate { n: 0 }.call().unwrap(), "empty");
+    assert_eq!(EachElseNestedTemplate { n: 3 }.call().unwrap(), "-001");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each 0..0 }}{{ this }}{{ else }}empty{{/each }}|{{#each 0..2 }}{{ this }}{{ else }}empty{{/each }}"
+)]
+struct EachElseConstTemplate;
+
+#[test]
+fn test_each_else_const() {
+    assert_eq!(EachElseConstTemplate.call().unwrap(), "empty|01");
+}
+
+#[derive(Template)]
+#[template(src = "{{#each items }}{{ this }}{{#if !@last }}, {{/if }}{{/each }}")]
+struct EachLastTemplate<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_each_last() {
+    let s = EachLastTemplate {
+        items: &["foo", "bar", "baz"],
+    };
+    assert_eq!(s.call().unwrap(), "foo, bar, baz");
+
+    let s = EachLastTemplate { items: &[] };
+    assert_eq!(s.call().unwrap(), "");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each 0..n }}{{#each 0..this }}{{#if super::@last && @last }}{{ this }}{{/if }}{{/each }}{{/each }}"
+)]
+struct EachLastNestedTemplate {
+    n: usize,
+}
+
+#[test]
+fn test_each_last_nested() {
+    assert_eq!(EachLastNestedTemplate { n: 4 }.call().unwrap(), "2");
+}
+
+#[derive(Template)]
+#[template(src = "{{#each map }}{{ @key }}={{ @value }}{{#if !@last }}&{{/if }}{{/each }}")]
+struct EachKeyValueTemplate {
+    map: std::collections::BTreeMap<&'static str, usize>,
+}
+
+#[test]
+fn test_each_key_value() {
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("foo", 1);
+    map.insert("bar", 2);
+    let s = EachKeyValueTemplate { map };
+    assert_eq!(s.call().unwrap(), "bar=2&foo=1");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each [[1, 2], [3, 4]] }}{{ @key }}:{{ @value }}{{#if !@last }},{{/if }}{{/each }}"
+)]
+struct EachLastConstTemplate;
+
+#[test]
+fn test_each_last_const() {
+    assert_eq!(EachLastConstTemplate.call().unwrap(), "1:2,3:4");
+}
+
+struct Field {
+    last: bool,
+    value: &'static str,
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each items }}{{#if last }}{{ value }}{{/if }}{{#if !@last }}{{ @index }},{{/if }}{{/each }}"
+)]
+struct EachFieldTemplate {
+    items: Vec<Field>,
+}
+
+#[test]
+fn test_each_field_not_shadowed() {
+    let s = EachFieldTemplate {
+        items: vec![
+            Field {
+                last: true,
+                value: "foo",
+            },
+            Field {
+                last: false,
+                value: "bar",
+            },
+        ],
+    };
+    assert_eq!(s.call().unwrap(), "foo1,");
+}
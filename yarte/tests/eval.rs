@@ -238,3 +238,49 @@ fn test_const_partial2() {
     let t = ConstPartial2Template { s: "bar" };
     assert_eq!(t.call().unwrap(), "foo\nIn partial\n1bar\nIn partial\n2");
 }
+
+#[derive(Template)]
+#[template(src = "
+    {{~#if EVAL_FLAG ~}}
+        {{~# each EVAL_LOCALES ~}}
+            {{ this }} {{ index }}
+        {{~/ each }}
+    {{~ else ~}}
+        none
+    {{~/if }}
+    ")]
+struct ConfigConstTemplate;
+
+#[test]
+fn test_config_const() {
+    let t = ConfigConstTemplate;
+    assert_eq!(t.call().unwrap(), "en 1es 2");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#if EVAL_FLAG }}yes{{ else }}{{ N * 2 }}{{/if }}",
+    consts = "EVAL_FLAG = false, N = 2"
+)]
+struct AttrConstTemplate;
+
+#[test]
+fn test_attr_const() {
+    let t = AttrConstTemplate;
+    assert_eq!(t.call().unwrap(), "4");
+}
+
+#[derive(Template)]
+#[template(src = "{{#if EVAL_FLAG && y }}{{ EVAL_LOCALES.len() + n }}{{/if }}")]
+struct PartlyConstTemplate {
+    y: bool,
+    n: usize,
+}
+
+#[test]
+fn test_partly_const() {
+    let t = PartlyConstTemplate { y: true, n: 1 };
+    assert_eq!(t.call().unwrap(), "3");
+    let t = PartlyConstTemplate { y: false, n: 1 };
+    assert_eq!(t.call().unwrap(), "");
+}
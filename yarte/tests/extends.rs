@@ -0,0 +1,66 @@
+use yarte::{Template, TemplateText};
+
+#[derive(Template)]
+#[template(path = "layout-base")]
+struct Base;
+
+#[test]
+fn test_extends_base() {
+    assert_eq!(Base.call().unwrap(), "<title>Base</title><p>base</p>");
+}
+
+#[derive(Template)]
+#[template(path = "layout-child")]
+struct Child {
+    title: &'static str,
+    body: &'static str,
+}
+
+#[test]
+fn test_extends() {
+    let t = Child {
+        title: "Foo",
+        body: "<bar>",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<title>Foo - Base</title><p>&lt;bar&gt;</p>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#extends layout-child }}{{#block title }}Deep | {{> @super }}{{/block }}{{#block scripts }}<script></script>{{/block }}{{/extends }}"
+)]
+struct GrandChild {
+    title: &'static str,
+    body: &'static str,
+}
+
+#[test]
+fn test_extends_nested() {
+    let t = GrandChild {
+        title: "Foo",
+        body: "<bar>",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<title>Deep | Foo - Base</title><p>&lt;bar&gt;</p><script></script>"
+    );
+}
+
+#[derive(TemplateText)]
+#[template(
+    src = "{{#extends layout-base }}{{#*inline \"name\" }}{{ this }}{{/inline }}{{#block body }}{{#each names }}{{> name }}{{/each }}{{/block }}{{/extends }}"
+)]
+struct ChildInline {
+    names: Vec<&'static str>,
+}
+
+#[test]
+fn test_extends_inline() {
+    let t = ChildInline {
+        names: vec!["<a>", "b"],
+    };
+    assert_eq!(t.call().unwrap(), "<title>Base</title><a>b");
+}
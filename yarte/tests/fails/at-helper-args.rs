@@ -0,0 +1,16 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(
+    src = "{{ @url foo, foo }}",
+    at_helper(name = "url", path = "crate::url", args = 1)
+)]
+struct Test {
+    foo: usize,
+}
+
+fn url(_: &usize) -> usize {
+    0
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "<button on{{ event }}=\"f()\"></button>")]
+struct Test {
+    event: String,
+}
+
+fn main() {}
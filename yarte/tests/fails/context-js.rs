@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "<script>var a = {{ a }};</script>")]
+struct Test {
+    a: usize,
+}
+
+fn main() {}
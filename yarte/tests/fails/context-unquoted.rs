@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "<div class={{ class }}></div>")]
+struct Test {
+    class: String,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{ A }}", consts = "A = 1, B")]
+struct Test;
+
+fn main() {}
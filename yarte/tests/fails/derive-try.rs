@@ -0,0 +1,7 @@
+use yarte::TemplateTry;
+
+#[derive(TemplateTry)]
+#[template(src = "")]
+struct Test;
+
+fn main() {}
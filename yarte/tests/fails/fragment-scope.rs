@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#each items}}{{#fragment \"item\"}}{{ this }}{{/fragment}}{{/each}}")]
+struct Test {
+    items: Vec<usize>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#foo bar }}{{/foo }}")]
+struct Test {
+    bar: usize,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::{TemplateBytes, __IoBuffer};
+
+#[derive(TemplateBytes)]
+#[template(src = "")]
+struct Test;
+
+fn main() {
+    let _ = Test.call::<__IoBuffer<Vec<u8>>>(0);
+}
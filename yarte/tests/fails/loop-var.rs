@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{ @last }}")]
+struct Test {
+    last: bool,
+}
+
+fn main() {}
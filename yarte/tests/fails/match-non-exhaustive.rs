@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#match foo }}{{ when Some(x) }}{{ x }}{{/match }}")]
+struct Test {
+    foo: Option<usize>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "<p>{{> helo }}</p>")]
+struct Test {
+    name: usize,
+}
+
+fn main() {}
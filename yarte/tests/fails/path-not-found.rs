@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(path = "helo")]
+struct Test {
+    name: usize,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#*inline \"card\" }}{{> @slot header }}{{/inline }}{{#> card }}body{{/card }}")]
+struct Test;
+
+fn main() {}
@@ -0,0 +1,7 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#block title }}{{> @super }}{{/block }}")]
+struct Test;
+
+fn main() {}
@@ -0,0 +1,145 @@
+use std::{fmt, num::ParseIntError};
+
+use yarte::{TemplateTry, TemplateTryText};
+
+#[derive(Debug, PartialEq)]
+enum Error {
+    Fmt,
+    Parse(String),
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Self {
+        Error::Fmt
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(e: ParseIntError) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
+
+#[derive(TemplateTry)]
+#[template(src = "<p>{{ n.parse::<u8>()? }}</p>", error = "Error")]
+struct ParseTemplate {
+    n: &'static str,
+}
+
+#[test]
+fn test_try() {
+    assert_eq!(ParseTemplate { n: "42" }.try_call().unwrap(), "<p>42</p>");
+    assert_eq!(
+        ParseTemplate { n: "x" }.try_call().unwrap_err(),
+        Error::Parse("invalid digit found in string".into())
+    );
+}
+
+#[derive(TemplateTryText)]
+#[template(
+    src = "{{# each items }}{{ this.parse::<u8>()? }},{{/each }}",
+    error = "Error"
+)]
+struct EachTemplate {
+    items: Vec<&'static str>,
+}
+
+#[test]
+fn test_try_each() {
+    let t = EachTemplate {
+        items: vec!["1", "2"],
+    };
+    assert_eq!(t.try_call().unwrap(), "1,2,");
+    let t = EachTemplate {
+        items: vec!["1", "256"],
+    };
+    assert_eq!(
+        t.try_call().unwrap_err(),
+        Error::Parse("number too large to fit in target type".into())
+    );
+}
+
+struct Fails;
+
+impl fmt::Display for Fails {
+    fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+        Err(fmt::Error)
+    }
+}
+
+#[derive(TemplateTry)]
+#[template(src = "{{{ fails }}}", error = "Error")]
+struct FmtTemplate {
+    fails: Fails,
+}
+
+#[test]
+fn test_try_fmt() {
+    assert_eq!(
+        FmtTemplate { fails: Fails }.try_call().unwrap_err(),
+        Error::Fmt
+    );
+}
+
+#[derive(TemplateTry)]
+#[template(
+    src = "<ul>{{#fragment \"item\"}}<li>{{ n.parse::<u8>()? }}</li>{{/fragment}}</ul>",
+    error = "Error"
+)]
+struct FragmentTemplate {
+    n: &'static str,
+}
+
+#[test]
+fn test_fragment() {
+    let t = FragmentTemplate { n: "1" };
+    assert_eq!(t.try_call().unwrap(), "<ul><li>1</li></ul>");
+    assert_eq!(t.fragment_item().unwrap(), "<li>1</li>");
+    let t = FragmentTemplate { n: "a" };
+    assert_eq!(
+        t.fragment_item(),
+        Err(Error::Parse("invalid digit found in string".into()))
+    );
+}
+
+#[cfg(feature = "bytes-buf")]
+mod bytes {
+    use std::num::ParseIntError;
+
+    use yarte::TemplateBytesTry;
+
+    #[derive(TemplateBytesTry)]
+    #[template(src = "<p>{{ n.parse::<i32>()? }}</p>", error = "ParseIntError")]
+    struct ParseTemplate {
+        n: &'static str,
+    }
+
+    #[test]
+    fn test_try() {
+        let t = ParseTemplate { n: "-1" };
+        assert_eq!(t.try_call::<String>(64).unwrap(), "<p>-1</p>");
+        let t = ParseTemplate { n: "" };
+        assert!(t.try_call::<String>(64).is_err());
+
+        let mut buf = String::new();
+        assert!(t.try_write_call(&mut buf).is_err());
+        assert_eq!(buf, "<p>");
+    }
+
+    #[derive(TemplateBytesTry)]
+    #[template(
+        src = "<ul>{{#fragment \"item\"}}<li>{{ n.parse::<i32>()? }}</li>{{/fragment}}</ul>",
+        error = "ParseIntError"
+    )]
+    struct FragmentTemplate {
+        n: &'static str,
+    }
+
+    #[test]
+    fn test_fragment() {
+        let t = FragmentTemplate { n: "-1" };
+        assert_eq!(t.fragment_item::<String>(64).unwrap(), "<li>-1</li>");
+        let t = FragmentTemplate { n: "" };
+        assert!(t.fragment_item::<String>(64).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use yarte::{Template, TemplateText};
+
+#[derive(Template)]
+#[template(
+    src = "<table>{{~# each rows }}<tr><td>{{ this }}</td></tr>{{/each ~}}\
+{{~#fragment \"row\" ~}}
+  <tr id=\"{{ id }}\"><td>{{ name }}</td></tr>
+{{~/fragment ~}}
+</table>"
+)]
+struct TableTemplate {
+    rows: Vec<&'static str>,
+    id: usize,
+    name: &'static str,
+}
+
+#[test]
+fn test_fragment() {
+    let t = TableTemplate {
+        rows: vec!["a"],
+        id: 1,
+        name: "<b>",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<table><tr><td>a</td></tr><tr id=\"1\"><td>&lt;b&gt;</td></tr></table>"
+    );
+    assert_eq!(
+        t.fragment_row().call().unwrap(),
+        "<tr id=\"1\"><td>&lt;b&gt;</td></tr>"
+    );
+    assert_eq!(
+        t.fragment_row().to_string(),
+        "<tr id=\"1\"><td>&lt;b&gt;</td></tr>"
+    );
+}
+
+#[derive(TemplateText)]
+#[template(
+    src = "{{#fragment \"head\"}}{{ title }}{{/fragment}}:{{#if ok }}{{#fragment \"body\"}}{{ let n = 1 }}{{ n }}{{/fragment}}{{/if }}"
+)]
+struct NestedTemplate {
+    title: &'static str,
+    ok: bool,
+}
+
+#[test]
+fn test_fragment_nested() {
+    let t = NestedTemplate {
+        title: "<t>",
+        ok: false,
+    };
+    assert_eq!(t.call().unwrap(), "<t>:");
+    assert_eq!(t.fragment_head().call().unwrap(), "<t>");
+    assert_eq!(t.fragment_body().call().unwrap(), "1");
+}
+
+#[cfg(feature = "bytes-buf")]
+mod bytes {
+    use yarte::TemplateBytes;
+
+    #[derive(TemplateBytes)]
+    #[template(src = "<div>{{#fragment \"item\"}}<p>{{ n }}</p>{{/fragment}}</div>")]
+    struct ItemTemplate {
+        n: i32,
+    }
+
+    #[test]
+    fn test_fragment() {
+        let t = ItemTemplate { n: -1 };
+        assert_eq!(t.call::<String>(64), "<div><p>-1</p></div>");
+        assert_eq!(t.fragment_item::<String>(64), "<p>-1</p>");
+    }
+}
+
+#[cfg(feature = "html-min")]
+mod html_min {
+    use yarte::TemplateMin;
+
+    #[derive(TemplateMin)]
+    #[template(src = "<ul>\n  {{#fragment \"item\"}}<li> {{ n }} </li>{{/fragment}}\n</ul>")]
+    struct ItemTemplate {
+        n: i32,
+    }
+
+    #[test]
+    fn test_fragment() {
+        let t = ItemTemplate { n: 1 };
+        assert_eq!(t.fragment_item().call().unwrap(), "<li>1</li>");
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt::Display;
+
+use yarte::{DisplayFn, Template, TemplateText};
+
+fn card<'a, T: Display + ?Sized, B: Display + 'a>(title: &'a T, body: B) -> impl Display + 'a {
+    DisplayFn::new(move |f| write!(f, "<div><h1>{title}</h1>{body}</div>"))
+}
+
+fn url<T: Display + ?Sized>(path: &T) -> String {
+    format!("/static/{path}?q=<a>")
+}
+
+fn asset<T: Display + ?Sized>(kind: &T, name: &T) -> String {
+    format!("<link rel=\"{kind}\" href=\"/{name}\">")
+}
+
+struct Card {
+    title: &'static str,
+    body: &'static str,
+}
+
+#[derive(Template)]
+#[template(path = "helper-card")]
+struct CardTemplate {
+    cards: Vec<Card>,
+}
+
+#[test]
+fn test_block_helper() {
+    let t = CardTemplate {
+        cards: vec![
+            Card {
+                title: "foo",
+                body: "<bar>",
+            },
+            Card {
+                title: "baz",
+                body: "qux",
+            },
+        ],
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<div><h1>foo</h1><p>&lt;bar&gt;</p></div><div><h1>baz</h1><p>qux</p></div>"
+    );
+}
+
+#[derive(TemplateText)]
+#[template(src = "{{#card name }}{{#card \"inner\" }}{{ body }}{{/card }}{{/card }}")]
+struct NestedTemplate {
+    name: &'static str,
+    body: &'static str,
+}
+
+#[test]
+fn test_block_helper_nested() {
+    let t = NestedTemplate {
+        name: "outer",
+        body: "<b>",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<div><h1>outer</h1><div><h1>inner</h1><b></div></div>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "<a href=\"{{ @url path }}\">{{ @asset \"style\", path }}</a>",
+    at_helper(name = "asset", path = "crate::asset", args = 2, safe)
+)]
+struct AtHelperTemplate {
+    path: &'static str,
+}
+
+#[test]
+fn test_at_helper() {
+    let t = AtHelperTemplate { path: "main.css" };
+    assert_eq!(
+        t.call().unwrap(),
+        "<a href=\"/static/main.css?q=%3Ca%3E\"><link rel=\"style\" href=\"/main.css\"></a>"
+    );
+}
+
+#[cfg(feature = "bytes-buf")]
+mod bytes {
+    use super::*;
+    use yarte::{TemplateBytes, TemplateBytesText};
+
+    #[derive(TemplateBytes)]
+    #[template(path = "helper-card")]
+    struct CardTemplate {
+        cards: Vec<Card>,
+    }
+
+    #[test]
+    fn test_block_helper_bytes() {
+        let t = CardTemplate {
+            cards: vec![Card {
+                title: "foo",
+                body: "<bar>",
+            }],
+        };
+        assert_eq!(
+            t.call::<String>(0),
+            "<div><h1>foo</h1><p>&lt;bar&gt;</p></div>"
+        );
+    }
+
+    #[derive(TemplateBytesText)]
+    #[template(src = "{{#card name }}{{#card \"inner\" }}{{ body }}{{/card }}{{/card }}")]
+    struct NestedTemplate {
+        name: &'static str,
+        body: &'static str,
+    }
+
+    #[test]
+    fn test_block_helper_nested_bytes() {
+        let t = NestedTemplate {
+            name: "outer",
+            body: "<b>",
+        };
+        let mut buf = String::new();
+        t.write_call(&mut buf);
+        assert_eq!(buf, "<div><h1>outer</h1><div><h1>inner</h1><b></div></div>");
+    }
+
+    #[derive(TemplateBytes)]
+    #[template(
+        src = "<a href=\"{{ @url path }}\">{{ @asset \"style\", path }}</a>",
+        at_helper(name = "asset", path = "crate::asset", args = 2, safe)
+    )]
+    struct AtHelperTemplate {
+        path: &'static str,
+    }
+
+    #[test]
+    fn test_at_helper_bytes() {
+        let t = AtHelperTemplate { path: "main.css" };
+        assert_eq!(
+            t.call::<String>(0),
+            "<a href=\"/static/main.css?q=%3Ca%3E\"><link rel=\"style\" href=\"/main.css\"></a>"
+        );
+    }
+}
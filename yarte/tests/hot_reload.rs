@@ -0,0 +1,130 @@
+#![cfg(all(feature = "hot-reload", debug_assertions))]
+
+use std::{
+    env, fs,
+    marker::PhantomData,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde::{Serialize, Serializer};
+
+use yarte::{
+    dynamic::{on_reload_error, DynError, RELOAD_DIR_VAR, RELOAD_VAR},
+    Template, TemplateText,
+};
+
+fn upper<S: Serializer>(name: &str, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&name.to_uppercase())
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "hello")]
+struct HelloTemplate<'a> {
+    #[serde(serialize_with = "upper")]
+    name: &'a str,
+}
+
+#[derive(TemplateText, Serialize)]
+#[template(path = "hello")]
+struct HelloTextTemplate<'a> {
+    #[serde(serialize_with = "upper")]
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "hello")]
+struct NoSerialize<'a> {
+    name: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "hello")]
+struct Generic<T> {
+    name: &'static str,
+    _t: PhantomData<T>,
+}
+
+#[derive(Template, Serialize)]
+#[template(src = "Hello, {{ name }}!")]
+struct Inline<'a> {
+    #[serde(serialize_with = "upper")]
+    name: &'a str,
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "hot-reload-match")]
+struct Unsupported {
+    n: usize,
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "hot-reload-context")]
+struct Context<'a> {
+    url: &'a str,
+    #[serde(serialize_with = "upper")]
+    name: &'a str,
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "modes/item.json")]
+struct Json<'a> {
+    #[serde(serialize_with = "upper")]
+    name: &'a str,
+}
+
+static ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+fn count(path: &Path, e: &DynError) {
+    assert!(path.ends_with("templates/hot-reload-match.hbs"));
+    assert!(matches!(e, DynError::Unsupported(_)));
+    ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+// Environment is shared by all tests of this binary
+#[test]
+fn test_reload() {
+    on_reload_error(count);
+    env::remove_var(RELOAD_VAR);
+    let t = HelloTemplate { name: "<world>" };
+    assert_eq!(t.call().unwrap(), "Hello, &lt;world&gt;!");
+
+    env::set_var(RELOAD_VAR, "1");
+    assert_eq!(t.call().unwrap(), "Hello, &lt;WORLD&gt;!");
+    let t = HelloTextTemplate { name: "<world>" };
+    assert_eq!(t.call().unwrap(), "Hello, <WORLD>!");
+
+    // Fallback to compiled code
+    let t = NoSerialize { name: "world" };
+    assert_eq!(t.call().unwrap(), "Hello, world!");
+    let t = Generic::<()> {
+        name: "world",
+        _t: PhantomData,
+    };
+    assert_eq!(t.call().unwrap(), "Hello, world!");
+    let t = Inline { name: "world" };
+    assert_eq!(t.call().unwrap(), "Hello, world!");
+    let t = Unsupported { n: 1 };
+    assert_eq!(t.call().unwrap(), "one");
+    assert_eq!(ERRORS.load(Ordering::Relaxed), 1);
+    // the runtime has no context escape nor other modes
+    let t = Context {
+        url: "javascript:alert(1)",
+        name: "a",
+    };
+    assert_eq!(t.call().unwrap(), "<a href=\"about:invalid\">a</a>");
+    let t = Json { name: "<b>" };
+    assert_eq!(t.call().unwrap(), r#"{"name": "<b>", "tag": "a\"b"}"#);
+
+    // Sources moved out of the crate root
+    let dir = env::temp_dir().join("yarte-hot-reload");
+    fs::create_dir_all(dir.join("templates")).unwrap();
+    fs::write(dir.join("templates/hello.hbs"), "Bye, {{ name }}!").unwrap();
+    env::set_var(RELOAD_DIR_VAR, &dir);
+    let t = HelloTemplate { name: "world" };
+    assert_eq!(t.call().unwrap(), "Bye, WORLD!");
+    env::remove_var(RELOAD_DIR_VAR);
+    fs::remove_dir_all(dir).unwrap();
+
+    env::remove_var(RELOAD_VAR);
+}
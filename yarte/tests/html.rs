@@ -30,8 +30,7 @@ fn test_fortune() {
     };
     assert_eq!(
         "<!DOCTYPE html><html><head><title>Fortunes</title></head><body><table>\
-        <thead><tr><th>id</th><th>message</th></tr></thead>\
-        <tbody><tr><td>0</td><td>foo</td></tr><tr><td>1</td><td>bar</td></tr></tbody>\
+        <thead><tr><th>id<th>message<tbody><tr><td>0<td>foo</tr><tr><td>1<td>bar</tr>\
         </table></body></html>",
         t.call().unwrap()
     );
@@ -101,9 +100,9 @@ fn test_article() {
 
     assert_eq!(
         "<article id=\"text__code\"><header><h1>Code</h1></header><div><p><strong>Keyboard \
-         input:</strong> <kbd>Cmd</kbd></p><p><strong>Inline code:</strong> \
-         <code>&lt;div&gt;code&lt;/div&gt;</code></p><p><strong>Sample output:</strong> \
-         <samp>This is sample output from a computer program.</samp></p><h2>Pre-formatted \
+         input:</strong> <kbd>Cmd</kbd><p><strong>Inline code:</strong> \
+         <code>&lt;div&gt;code&lt;/div&gt;</code><p><strong>Sample output:</strong> \
+         <samp>This is sample output from a computer program.</samp><h2>Pre-formatted \
          text</h2></div><footer><p><a href=\"#top\">[Top]</a></p></footer></article>",
         t.call().unwrap()
     );
@@ -138,7 +137,7 @@ fn test_section() {
     let expected = "<section id=\"embedded\"><header><h1>Embedded content</h1></header>\
         <article id=\"embedded__images\"><header><h2>Images</h2></header><div>\
         <h3>No <code>&lt;figure&gt;</code> element</h3><p>\
-        <img src=\"http://placekitten.com/480/480\" alt=\"Image alt text\"></p>\
+        <img src=\"http://placekitten.com/480/480\" alt=\"Image alt text\">\
         <h3>Wrapped in a <code>&lt;figure&gt;</code> element, no <code>&lt;figcaption&gt;</code>\
         </h3><figure><img src=\"http://placekitten.com/420/420\" alt=\"Image alt text\"></figure>\
         <h3>Wrapped in a <code>&lt;figure&gt;</code> element, with a <code>&lt;figcaption&gt;\
@@ -184,7 +183,7 @@ struct Attr {
 }
 
 #[derive(Template)]
-#[template(src = "{{#with attr}}<div {{ name }}=\"{{ value }}\"></div>{{/with }}")]
+#[template(src = "{{#with attr}}<div {{{ name }}}=\"{{ value }}\"></div>{{/with }}")]
 struct AttrExpr {
     attr: Attr,
 }
@@ -213,8 +212,31 @@ use std::path::Path;
 fn test_raw_html() {
     use yarte_helpers::config::{read_config_file, Config};
     let config = read_config_file();
-    let config = Config::new(&config);
-    let (_, expected) = config.get_template(Path::new("html/raw/expected.html"));
+    let config = Config::new(&config.unwrap()).unwrap();
+    let (_, expected) = config
+        .get_template(Path::new("html/raw/expected.html").into())
+        .unwrap();
 
     assert_eq!(RawHtml.call().unwrap(), expected);
 }
+
+#[cfg(feature = "bytes-buf")]
+mod bytes {
+    use yarte::TemplateBytesMin;
+
+    #[derive(TemplateBytesMin)]
+    #[template(src = "<ul>
+    {{~#each items }}
+    <li> {{ this }} </li>
+    {{/each }}
+</ul>")]
+    struct ListTemplate<'a> {
+        items: &'a [&'a str],
+    }
+
+    #[test]
+    fn test_list_bytes() {
+        let t = ListTemplate { items: &["a", "b"] };
+        assert_eq!(t.call::<String>(0), "<ul><li>a</li><li>b</li></ul>");
+    }
+}
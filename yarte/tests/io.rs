@@ -0,0 +1,82 @@
+#![cfg(feature = "bytes-buf")]
+
+use std::io;
+
+use yarte::{TemplateIo, TemplateIoText};
+
+#[derive(TemplateIo)]
+#[template(src = "<p>{{ name }}: {{ n }}, {{ f }}, {{ b }}</p>")]
+struct VariablesTemplate {
+    name: &'static str,
+    n: i64,
+    f: f64,
+    b: bool,
+}
+
+#[test]
+fn test_variables() {
+    let t = VariablesTemplate {
+        name: "<a>",
+        n: -42,
+        f: 1.5,
+        b: true,
+    };
+    let mut out = vec![];
+    t.write_io(&mut out).unwrap();
+    assert_eq!(out, b"<p>&lt;a&gt;: -42, 1.5, true</p>");
+}
+
+#[derive(TemplateIoText)]
+#[template(src = "{{# each items }}{{ this }},{{/each }}")]
+struct ListTemplate {
+    items: Vec<String>,
+}
+
+#[test]
+fn test_large() {
+    let items: Vec<_> = (0..10_000).map(|i| format!("<{i}>")).collect();
+    let expected: String = items.iter().map(|x| format!("{x},")).collect();
+    let mut out = vec![];
+    ListTemplate { items }.write_io(&mut out).unwrap();
+    assert_eq!(out, expected.as_bytes());
+}
+
+struct Fail;
+
+impl io::Write for Fail {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_error() {
+    let t = VariablesTemplate {
+        name: "a",
+        n: 1,
+        f: 1.0,
+        b: false,
+    };
+    assert_eq!(
+        t.write_io(Fail).unwrap_err().kind(),
+        io::ErrorKind::BrokenPipe
+    );
+}
+
+#[derive(TemplateIo)]
+#[template(src = "<ul>{{#fragment \"item\"}}<li>{{ name }}</li>{{/fragment}}</ul>")]
+struct FragmentTemplate {
+    name: &'static str,
+}
+
+#[test]
+fn test_fragment() {
+    let t = FragmentTemplate { name: "<a>" };
+    let mut out = vec![];
+    t.fragment_item(&mut out).unwrap();
+    assert_eq!(out, b"<li>&lt;a&gt;</li>");
+}
@@ -25,7 +25,7 @@ fn i_224() {
     let t = I224 {
         url: "foo".to_string(),
     };
-    let conf = Config::new("");
+    let conf = Config::new("").unwrap();
     let path = conf.get_dir().clone().join("issues/issue-224.txt");
     let expect = read_to_string(path).unwrap();
     assert_eq!(expect.trim(), t.call().unwrap());
@@ -0,0 +1,93 @@
+use yarte::{Template, TemplateText};
+
+#[derive(Template)]
+#[template(
+    src = "{{#match name }}{{ when Some(n) }}Hello, {{ n }}!{{ when None }}Hello!{{/match }}"
+)]
+struct MatchOption {
+    name: Option<&'static str>,
+}
+
+#[test]
+fn test_match_option() {
+    let t = MatchOption {
+        name: Some("<foo>"),
+    };
+    assert_eq!(t.call().unwrap(), "Hello, &lt;foo&gt;!");
+
+    let t = MatchOption { name: None };
+    assert_eq!(t.call().unwrap(), "Hello!");
+}
+
+#[allow(dead_code)]
+enum Shape {
+    Circle { radius: u32 },
+    Rect(u32, u32),
+    Empty,
+}
+
+#[derive(TemplateText)]
+#[template(src = "{{#each shapes ~}}
+    {{#match this ~}}
+        {{~ when Shape::Circle { radius } ~}} circle {{ radius }}
+        {{~ when Shape::Rect(w, h) if w == h ~}} square {{ w }}
+        {{~ when Shape::Rect(w, h) ~}} rect {{ w }}x{{ h }}
+        {{~ when _ ~}} empty
+    {{~/match }}
+{{~/each }}")]
+struct MatchEnum {
+    shapes: Vec<Shape>,
+}
+
+#[test]
+fn test_match_enum() {
+    let t = MatchEnum {
+        shapes: vec![
+            Shape::Circle { radius: 1 },
+            Shape::Rect(2, 2),
+            Shape::Rect(2, 3),
+            Shape::Empty,
+        ],
+    };
+    assert_eq!(t.call().unwrap(), "circle 1square 2rect 2x3empty");
+}
+
+#[derive(TemplateText)]
+#[template(
+    src = "{{#match n }}{{ when 0 }}zero{{ when 1..=9 }}digit{{ when n if n % 2 == 0 }}even {{ n }}{{ when _ }}odd{{/match }}"
+)]
+struct MatchLit {
+    n: u32,
+}
+
+#[test]
+fn test_match_lit() {
+    assert_eq!(MatchLit { n: 0 }.call().unwrap(), "zero");
+    assert_eq!(MatchLit { n: 7 }.call().unwrap(), "digit");
+    assert_eq!(MatchLit { n: 42 }.call().unwrap(), "even 42");
+    assert_eq!(MatchLit { n: 43 }.call().unwrap(), "odd");
+}
+
+#[cfg(feature = "bytes-buf")]
+mod bytes {
+    use yarte::TemplateBytes;
+
+    #[derive(TemplateBytes)]
+    #[template(
+        src = "{{#match name }}{{ when Some(n) }}Hello, {{ n }}!{{ when None }}Hello!{{/match }}"
+    )]
+    struct MatchOption {
+        name: Option<&'static str>,
+    }
+
+    #[test]
+    fn test_match_option() {
+        let t = MatchOption {
+            name: Some("<foo>"),
+        };
+        assert_eq!(t.call::<String>(64), "Hello, &lt;foo&gt;!");
+
+        let t = MatchOption { name: None };
+        assert_eq!(t.call::<String>(64), "Hello!");
+    }
+}
@@ -0,0 +1,97 @@
+use yarte::{Template, TemplateText};
+
+#[derive(Template)]
+#[template(path = "modes/item.json")]
+struct JsonItem<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_json() {
+    let t = JsonItem {
+        name: "\"quoted\"\n<b>",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        r#"{"name": "\"quoted\"\n<b>", "tag": "a\"b"}"#
+    );
+}
+
+#[derive(Template)]
+#[template(path = "modes/feed.xml.hbs")]
+struct Feed<'a> {
+    title: &'a str,
+    body: &'a str,
+}
+
+#[test]
+fn test_xml() {
+    let t = Feed {
+        title: "Tom's \"feed\"",
+        body: "a < b & c/d",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<item title=\"Tom&apos;s &quot;feed&quot;\">a &lt; b &amp; c/d</item>"
+    );
+}
+
+#[derive(Template)]
+#[template(path = "modes/note.txt")]
+struct Note<'a> {
+    text: &'a str,
+}
+
+#[test]
+fn test_text() {
+    let t = Note {
+        text: "<i>a & b</i>",
+    };
+    assert_eq!(t.call().unwrap(), "<b><i>a & b</i></b>");
+}
+
+#[derive(TemplateText)]
+#[template(path = "modes/page.html")]
+struct Page<'a> {
+    text: &'a str,
+    url: &'a str,
+}
+
+#[test]
+fn test_html_in_text_derive() {
+    let t = Page {
+        text: "<a href='/'>",
+        url: "/a b?c=\"d\"",
+    };
+    assert_eq!(
+        t.call().unwrap(),
+        "<p title=\"&lt;a href=&#x27;&#x2f;&#x27;&gt;\">&lt;a href=&#x27;&#x2f;&#x27;&gt;</p>\
+         <a href=\"/a%20b?c=%22d%22\"></a>"
+    );
+}
+
+#[derive(Template)]
+#[template(src = "<a>{{ a }}{{ \"<b>\" }}</a>", mode = "xml")]
+struct XmlAttr {
+    a: usize,
+}
+
+#[test]
+fn test_mode_attribute() {
+    assert_eq!(XmlAttr { a: 1 }.call().unwrap(), "<a>1&lt;b&gt;</a>");
+}
+
+#[cfg(feature = "bytes-buf")]
+#[test]
+fn test_bytes() {
+    use yarte::TemplateBytes;
+
+    #[derive(TemplateBytes)]
+    #[template(path = "modes/item.json")]
+    struct JsonBytes<'a> {
+        name: &'a str,
+    }
+
+    let t = JsonBytes { name: "\\" };
+    assert_eq!(t.call::<String>(64), r#"{"name": "\\", "tag": "a\"b"}"#);
+}
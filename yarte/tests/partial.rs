@@ -423,3 +423,116 @@ fn test_partial_block_with_ctx() {
 
     assert_eq!(t.call().unwrap(), "FoobBar_0a1");
 }
+
+#[derive(Template)]
+#[template(path = "inline-partial")]
+struct InlinePartial<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_inline_partial() {
+    let t = InlinePartial {
+        items: &["foo", "<bar>"],
+    };
+
+    assert_eq!(
+        t.call().unwrap(),
+        "<ul><li>foo</li><li>&lt;bar&gt;</li></ul><p><li>child</li></p>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{> hello }}{{#with foo }}{{#*inline \"hello\" }}Bye {{ bar }}{{/inline }}{{> hello }}{{/with }}{{#*inline \"hello\" }}Hi {{ bar }}.{{/inline }}"
+)]
+struct InlinePartialScope {
+    bar: usize,
+    foo: Foo,
+}
+
+struct Foo {
+    bar: usize,
+}
+
+#[test]
+fn test_inline_partial_scope() {
+    let t = InlinePartialScope {
+        bar: 1,
+        foo: Foo { bar: 2 },
+    };
+
+    assert_eq!(t.call().unwrap(), "Hi 1.Bye 2");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#if cond }}{{#*inline \"empty\" }}inline{{/inline }}{{> empty }}{{/if }} {{> empty }}"
+)]
+struct InlinePartialFile {
+    cond: bool,
+}
+
+#[test]
+fn test_inline_partial_file() {
+    let t = InlinePartialFile { cond: true };
+
+    assert_eq!(t.call().unwrap(), "inline foo");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#> partial-slots }}{{#*slot header }}<h1>{{ title }}</h1>{{/slot }}Body{{/partial-slots }}"
+)]
+struct PartialSlots {
+    title: &'static str,
+}
+
+#[test]
+fn test_partial_slots() {
+    let t = PartialSlots { title: "<foo>" };
+
+    assert_eq!(
+        t.call().unwrap(),
+        "<div><h1>&lt;foo&gt;</h1><p>Body</p><i>none</i></div>"
+    );
+}
+
+#[derive(Template)]
+#[template(src = "{{#> partial-slots ~}}
+    {{#*slot header ~}} Header {{~/slot }}
+    {{~#*slot footer }}{{ title }}{{/slot ~}}
+    Body
+{{~/partial-slots }}")]
+struct PartialSlotsWs {
+    title: &'static str,
+}
+
+#[test]
+fn test_partial_slots_ws() {
+    let t = PartialSlotsWs { title: "Footer" };
+
+    assert_eq!(t.call().unwrap(), "<div>Header<p>Body</p>Footer</div>");
+}
+
+#[derive(Template)]
+#[template(src = "<p>{{> button label = \"a\" }}</p>")]
+struct PartialSearchDirs;
+
+#[derive(Template)]
+#[template(path = "button")]
+struct SearchDirs {
+    label: &'static str,
+}
+
+#[test]
+fn test_partial_search_dirs() {
+    assert_eq!(
+        PartialSearchDirs.call().unwrap(),
+        "<p><button>a</button></p>"
+    );
+    assert_eq!(
+        SearchDirs { label: "<b>" }.call().unwrap(),
+        "<button>&lt;b&gt;</button>"
+    );
+}
@@ -18,5 +18,6 @@ struct RawPartialTemplate;
 #[test]
 fn test_raw_partial() {
     let raw = RawPartialTemplate;
-    assert_eq!("{{&gt; partial }}", raw.call().unwrap());
+    // Raw blocks aren't minified, the old dom minifier escaped them as text
+    assert_eq!("{{> partial }}", raw.call().unwrap());
 }
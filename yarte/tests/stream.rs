@@ -0,0 +1,140 @@
+#![cfg(feature = "stream")]
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use yarte::{Stream, Template, TemplateStream, TemplateStreamText};
+
+fn chunks<S: Stream<Item = String>>(s: S) -> Vec<String> {
+    let mut s = Box::pin(s);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut chunks = vec![];
+    loop {
+        match Pin::as_mut(&mut s).poll_next(&mut cx) {
+            Poll::Ready(Some(chunk)) => chunks.push(chunk),
+            Poll::Ready(None) => return chunks,
+            Poll::Pending => panic!("render is never pending"),
+        }
+    }
+}
+
+#[derive(TemplateStream)]
+#[template(src = "<ul>{{# each items }}<li>{{ this }}</li>{{/each }}</ul>")]
+struct ListTemplate {
+    items: Vec<&'static str>,
+}
+
+#[test]
+fn test_each() {
+    let t = ListTemplate {
+        items: vec!["a", "<b>", "c"],
+    };
+    assert_eq!(
+        chunks(send(t.stream::<String>(64))),
+        [
+            "<ul><li>a</li>",
+            "<li>&lt;b&gt;</li>",
+            "<li>c</li>",
+            "</ul>"
+        ]
+    );
+}
+
+// Streams can be sent to other threads when the template is `Sync`
+fn send<T: Send>(t: T) -> T {
+    t
+}
+
+#[derive(TemplateStream)]
+#[template(src = "{{# each rows }}{{# each this }}{{ this }}{{/each }};{{/each }}")]
+struct NestedTemplate {
+    rows: Vec<Vec<usize>>,
+}
+
+#[test]
+fn test_nested_each() {
+    let t = NestedTemplate {
+        rows: vec![vec![1, 2], vec![], vec![3]],
+    };
+    assert_eq!(chunks(t.stream::<String>(64)), ["12;", ";", "3;"]);
+}
+
+#[derive(TemplateStreamText)]
+#[template(src = "<head>{{ title }}</head>{{~@flush~}}\n<body>{{ body }}</body>")]
+struct FlushTemplate {
+    title: &'static str,
+    body: &'static str,
+}
+
+#[test]
+fn test_flush() {
+    let t = FlushTemplate {
+        title: "<t>",
+        body: "b",
+    };
+    assert_eq!(
+        chunks(t.stream::<String>(64)),
+        ["<head><t></head>", "<body>b</body>"]
+    );
+}
+
+#[derive(Template)]
+#[template(src = "a{{ @flush }}b")]
+struct NoStreamTemplate;
+
+#[test]
+fn test_flush_no_stream() {
+    assert_eq!(NoStreamTemplate.call().unwrap(), "ab");
+}
+
+#[derive(TemplateStream)]
+#[template(src = "{{@flush}}{{# each items }}{{/each }}")]
+struct EmptyTemplate {
+    items: Vec<usize>,
+}
+
+#[test]
+fn test_empty() {
+    let t = EmptyTemplate { items: vec![1, 2] };
+    assert!(chunks(t.stream::<String>(64)).is_empty());
+}
+
+#[test]
+fn test_bytes() {
+    let t = ListTemplate { items: vec!["a"] };
+    assert_eq!(
+        chunks_bytes(t.stream::<Vec<u8>>(64)),
+        [b"<ul><li>a</li>".to_vec(), b"</ul>".to_vec()]
+    );
+}
+
+fn chunks_bytes<S: Stream<Item = Vec<u8>>>(s: S) -> Vec<Vec<u8>> {
+    let mut s = Box::pin(s);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut chunks = vec![];
+    while let Poll::Ready(Some(chunk)) = Pin::as_mut(&mut s).poll_next(&mut cx) {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+#[derive(TemplateStream)]
+#[template(
+    src = "<ul>{{#fragment \"list\"}}{{# each items }}<li>{{ this }}</li>{{/each }}{{/fragment}}</ul>"
+)]
+struct FragmentTemplate {
+    items: Vec<&'static str>,
+}
+
+#[test]
+fn test_fragment() {
+    let t = FragmentTemplate {
+        items: vec!["a", "b"],
+    };
+    assert_eq!(
+        chunks(send(t.fragment_list::<String>(64))),
+        ["<li>a</li>", "<li>b</li>"]
+    );
+}
@@ -75,3 +75,21 @@ fn test_whit_simple_iterator() {
         t.call().unwrap()
     );
 }
+
+#[derive(Template)]
+#[template(src = "<p>{{ n }}</p>{{# if ok }}yes{{/if }}")]
+struct SizeHint {
+    n: u8,
+    ok: bool,
+}
+
+#[test]
+fn test_size_hint() {
+    assert_eq!(SizeHint::size_hint_static(), 7);
+    assert_eq!(SizeHint::size_hint_dynamic(), 3 + 3);
+    assert_eq!(SizeHint::size_hint(), 13);
+    assert_eq!(
+        SizeHint { n: 255, ok: true }.call().unwrap(),
+        "<p>255</p>yes"
+    );
+}
@@ -1,16 +1,18 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 
-use yarte_hir::{Struct, HIR};
+use yarte_hir::{BlockHelper, Struct, HIR};
 
+use crate::coalesce::coalesce;
 use crate::EachCodeGen;
-use crate::{CodeGen, IfElseCodeGen};
+use crate::{BlockHelperCodeGen, CodeGen, IfElseCodeGen, MatchCodeGen};
 
 pub struct BytesCodeGen<'a, T: CodeGen> {
     codegen: T,
     s: &'a Struct<'a>,
     parent: Ident,
     buf: Ident,
+    fragment: &'static str,
 }
 
 impl<'a, T: CodeGen> BytesCodeGen<'a, T> {
@@ -25,9 +27,16 @@ impl<'a, T: CodeGen> BytesCodeGen<'a, T> {
             s,
             parent: format_ident!("{}", parent),
             buf,
+            fragment: "fragment",
         }
     }
 
+    /// Prefix of the fragment methods, `fragment` by default
+    pub fn fragment_prefix(mut self, prefix: &'static str) -> Self {
+        self.fragment = prefix;
+        self
+    }
+
     #[inline]
     fn template(&mut self, nodes: Vec<HIR>, tokens: &mut TokenStream) {
         let nodes = self.codegen.gen(nodes);
@@ -84,6 +93,26 @@ impl<'a, T: CodeGen> CodeGen for BytesCodeGen<'a, T> {
 
         tokens
     }
+
+    fn gen_fragment(&mut self, name: &str, v: Vec<HIR>) -> TokenStream {
+        let nodes = self.codegen.gen(v);
+        let parent = &self.parent;
+        let buf = &self.buf;
+        let method = format_ident!("{}_{}", self.fragment, name);
+        let doc = format!("Renders the fragment `{name}` to buffer and return it freeze");
+        self.s.implement(&quote!(
+            #[doc = #doc]
+            pub fn #method<B: #parent::Buffer>(&self, capacity: usize) -> B::Freeze {
+                use #parent::*;
+                let mut #buf = B::with_capacity(capacity);
+                macro_rules! buf_ref {
+                    ($b:expr) => { &mut $b };
+                }
+                #nodes
+                #buf.freeze()
+            }
+        ))
+    }
 }
 
 pub struct TextBytesCodeGen<'a> {
@@ -98,26 +127,38 @@ impl<'a> TextBytesCodeGen<'a> {
 
 impl<'a> EachCodeGen for TextBytesCodeGen<'a> {}
 impl<'a> IfElseCodeGen for TextBytesCodeGen<'a> {}
+impl<'a> MatchCodeGen for TextBytesCodeGen<'a> {}
+
+impl<'a> BlockHelperCodeGen for TextBytesCodeGen<'a> {
+    fn gen_block_helper(&mut self, BlockHelper { path, args, body }: BlockHelper) -> TokenStream {
+        let block = block_buf();
+        let body = TextBytesCodeGen::new(&block).gen(body);
+        block_helper(path, args, body, self.buf)
+    }
+}
 
 // TODO: Create new trait for decouple concepts
 impl<'a> CodeGen for TextBytesCodeGen<'a> {
     fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
         let mut tokens = TokenStream::new();
-        for i in v {
+        for i in coalesce(v) {
             use HIR::*;
             tokens.extend(match i {
                 Local(a) => quote!(#a),
-                Lit(a) => {
+                Lit(a) | Raw(a) => {
                     let buf = &self.buf;
                     let buf = &quote!(#buf);
                     literal(a, buf)
                 }
-                Safe(a) | Expr(a) => {
+                Safe(a) | Expr(a, _) => {
                     let buf = &self.buf;
                     quote!((&(#a)).__render_itb_safe(buf_ref!(#buf));)
                 }
                 Each(a) => self.gen_each(*a),
                 IfElse(a) => self.gen_if_else(*a),
+                Match(a) => self.gen_match(*a),
+                BlockHelper(a) => self.gen_block_helper(*a),
+                Flush => quote!(__yarte_flush!();),
             });
         }
         tokens
@@ -126,18 +167,21 @@ impl<'a> CodeGen for TextBytesCodeGen<'a> {
 
 fn gen<C>(codegen: &mut C, v: Vec<HIR>, buf: TokenStream) -> TokenStream
 where
-    C: CodeGen + EachCodeGen + IfElseCodeGen,
+    C: CodeGen + EachCodeGen + IfElseCodeGen + MatchCodeGen + BlockHelperCodeGen,
 {
     let mut tokens = TokenStream::new();
-    for i in v {
+    for i in coalesce(v) {
         use HIR::*;
         tokens.extend(match i {
             Local(a) => quote!(#a),
-            Lit(a) => literal(a, &buf),
+            Lit(a) | Raw(a) => literal(a, &buf),
             Safe(a) => quote!((&(#a)).__render_itb_safe(buf_ref!(#buf));),
-            Expr(a) => quote!((&(#a)).__render_itb(buf_ref!(#buf));),
+            Expr(a, _) => quote!((&(#a)).__render_itb(buf_ref!(#buf));),
             Each(a) => codegen.gen_each(*a),
             IfElse(a) => codegen.gen_if_else(*a),
+            Match(a) => codegen.gen_match(*a),
+            BlockHelper(a) => codegen.gen_block_helper(*a),
+            Flush => quote!(__yarte_flush!();),
         })
     }
 
@@ -171,6 +215,30 @@ fn literal(a: String, buf: &TokenStream) -> TokenStream {
     }
 }
 
+fn block_buf() -> syn::Expr {
+    syn::parse2(quote!(__yarte_block)).unwrap()
+}
+
+/// Render the body in a new buffer and pass it to the helper
+fn block_helper(
+    path: syn::Path,
+    args: syn::Expr,
+    body: TokenStream,
+    buf: &syn::Expr,
+) -> TokenStream {
+    quote! {{
+        let __yarte_body = {
+            macro_rules! buf_ref {
+                ($b:expr) => { &mut $b };
+            }
+            let mut __yarte_block = String::new();
+            #body
+            __yarte_block
+        };
+        yarte::render_display(&#path(&(#args), __yarte_body.as_str()), buf_ref!(#buf));
+    }}
+}
+
 fn write_bb(b: &[u8], buf: &TokenStream) -> TokenStream {
     b.iter()
         .enumerate()
@@ -195,6 +263,15 @@ impl<'a> HTMLBytesCodeGen<'a> {
 impl<'a> EachCodeGen for HTMLBytesCodeGen<'a> {}
 
 impl<'a> IfElseCodeGen for HTMLBytesCodeGen<'a> {}
+impl<'a> MatchCodeGen for HTMLBytesCodeGen<'a> {}
+
+impl<'a> BlockHelperCodeGen for HTMLBytesCodeGen<'a> {
+    fn gen_block_helper(&mut self, BlockHelper { path, args, body }: BlockHelper) -> TokenStream {
+        let block = block_buf();
+        let body = HTMLBytesCodeGen::new(&block).gen(body);
+        block_helper(path, args, body, self.buf)
+    }
+}
 impl<'a> CodeGen for HTMLBytesCodeGen<'a> {
     fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
         let buf = self.buf;
@@ -205,8 +282,9 @@ impl<'a> CodeGen for HTMLBytesCodeGen<'a> {
 #[cfg(feature = "html-min")]
 pub mod html_min {
     use super::*;
-    use yarte_dom::DOMFmt;
+    use crate::minify::minify;
 
+    /// Html bytes code generator with the literals minified
     pub struct HTMLMinBytesCodeGen<'a> {
         buf: &'a syn::Expr,
     }
@@ -217,14 +295,9 @@ pub mod html_min {
         }
     }
 
-    impl<'a> EachCodeGen for HTMLMinBytesCodeGen<'a> {}
-    impl<'a> IfElseCodeGen for HTMLMinBytesCodeGen<'a> {}
-
     impl<'a> CodeGen for HTMLMinBytesCodeGen<'a> {
         fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
-            let dom: DOMFmt = v.into();
-            let buf = self.buf;
-            gen(self, dom.0, quote!(#buf))
+            HTMLBytesCodeGen::new(self.buf).gen(minify(v))
         }
     }
 }
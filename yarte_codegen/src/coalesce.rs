@@ -0,0 +1,73 @@
+//! Literal coalescing over a straight-line sequence of the HIR
+//!
+//! Partials, const evaluated branches and whitespace control leave adjacent
+//! literals in the HIR, sometimes split by `Local` nodes. Local statements
+//! don't write, so they are moved before the pending literal and the
+//! literals are merged into a single write.
+use std::mem;
+
+use yarte_hir::HIR;
+
+/// Merges the adjacent literals of `v`, the bodies are merged by its own code generation
+pub fn coalesce(v: Vec<HIR>) -> Vec<HIR> {
+    let mut out = Vec::with_capacity(v.len());
+    let mut lit = String::new();
+    for i in v {
+        match i {
+            HIR::Lit(a) | HIR::Raw(a) => lit.push_str(&a),
+            HIR::Local(a) => out.push(HIR::Local(a)),
+            i => {
+                if !lit.is_empty() {
+                    out.push(HIR::Lit(mem::take(&mut lit)));
+                }
+                out.push(i);
+            }
+        }
+    }
+    if !lit.is_empty() {
+        out.push(HIR::Lit(lit));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_str;
+    use yarte_hir::Span;
+
+    const SPAN: Span = Span { lo: 0, hi: 0 };
+
+    fn local(s: &str) -> HIR {
+        match parse_str::<syn::Stmt>(s).unwrap() {
+            syn::Stmt::Local(a) => HIR::Local(Box::new(a)),
+            _ => unreachable!(),
+        }
+    }
+
+    fn expr(s: &str) -> HIR {
+        HIR::Expr(Box::new(parse_str(s).unwrap()), SPAN)
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let v = vec![
+            HIR::Lit("<p>".into()),
+            local("let a = 1;"),
+            HIR::Raw("{{ b }}".into()),
+            HIR::Lit("</p>".into()),
+            expr("a"),
+            HIR::Lit("<br>".into()),
+        ];
+        assert_eq!(
+            coalesce(v),
+            vec![
+                local("let a = 1;"),
+                HIR::Lit("<p>{{ b }}</p>".into()),
+                expr("a"),
+                HIR::Lit("<br>".into()),
+            ]
+        );
+    }
+}
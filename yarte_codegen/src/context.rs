@@ -0,0 +1,664 @@
+//! Context aware escape of html templates
+//!
+//! Tracks the html, javascript and css around each `{{ expr }}` from the
+//! literals of the HIR and wraps it in the escaper of its position. Expressions
+//! in positions without a safe escaper are rejected.
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use yarte_hir::{Span, HIR};
+
+/// Error of the escape, with the span of the expression in a position without escaper
+#[derive(Debug)]
+pub struct EscapeError {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+impl From<String> for EscapeError {
+    fn from(message: String) -> Self {
+        EscapeError {
+            message,
+            span: None,
+        }
+    }
+}
+
+type EResult<T> = Result<T, EscapeError>;
+
+/// Wraps the expressions of `v` in the escaper of its context
+///
+/// `parent` is the crate that exports `Escaped`, `EscapedJson` and `Escaper`
+pub fn escape_context(v: Vec<HIR>, parent: &str) -> EResult<Vec<HIR>> {
+    escape_context_text(v, parent, false)
+}
+
+/// Like `escape_context`, with `text` the html text and attribute values are
+/// wrapped too, for code generators writing the expressions without escape
+pub(crate) fn escape_context_text(v: Vec<HIR>, parent: &str, text: bool) -> EResult<Vec<HIR>> {
+    Tracker {
+        ctx: Context::default(),
+        parent: format_ident!("{}", parent),
+        text,
+    }
+    .nodes(v)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Url {
+    Start,
+    Path,
+    Query,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Js {
+    Code,
+    Str(char, bool),
+    LineComment,
+    BlockComment,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Css {
+    Code,
+    Str(char, bool),
+    Comment,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Attr {
+    Normal,
+    Url(Url),
+    Js(Js),
+    Css(Css),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Text,
+    /// `<!-- ... -->`
+    Comment,
+    /// `<!doctype ...>` or `<?...>`
+    Declaration,
+    /// After `<`
+    TagOpen,
+    TagName {
+        end: bool,
+    },
+    /// Before an attribute name
+    Tag,
+    AttrName,
+    AfterAttrName,
+    BeforeValue,
+    Unquoted,
+    Value(char, Attr),
+    /// Body of `<textarea>` or `<title>`
+    RawText,
+    Script(Js),
+    Style(Css),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Context {
+    state: State,
+    tag: String,
+    attr: String,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context {
+            state: State::Text,
+            tag: String::new(),
+            attr: String::new(),
+        }
+    }
+}
+
+impl Context {
+    fn lit(&mut self, s: &str) {
+        let mut i = 0;
+        while let Some(c) = s[i..].chars().next() {
+            i += self.next(c, &s[i..]);
+        }
+    }
+
+    /// Consumes the start of `rest`, being `c` its first char, returns the consumed length
+    fn next(&mut self, c: char, rest: &str) -> usize {
+        use State::*;
+        let len = c.len_utf8();
+        match self.state {
+            Text => {
+                if c == '<' {
+                    self.state = TagOpen;
+                }
+            }
+            Comment => {
+                if rest.starts_with("-->") {
+                    self.state = Text;
+                    return 3;
+                }
+            }
+            Declaration => {
+                if c == '>' {
+                    self.close_tag(true);
+                }
+            }
+            TagOpen => {
+                if rest.starts_with("!--") {
+                    self.state = Comment;
+                    return 3;
+                }
+                self.state = match c {
+                    '!' | '?' => Declaration,
+                    '/' => {
+                        self.tag.clear();
+                        TagName { end: true }
+                    }
+                    c if c.is_ascii_alphabetic() => {
+                        self.tag.clear();
+                        self.tag.push(c.to_ascii_lowercase());
+                        TagName { end: false }
+                    }
+                    '<' => TagOpen,
+                    _ => Text,
+                }
+            }
+            TagName { end } => match c {
+                '>' => self.close_tag(end),
+                c if c.is_whitespace() || c == '/' => {
+                    self.state = if end { Declaration } else { Tag };
+                }
+                c => self.tag.push(c.to_ascii_lowercase()),
+            },
+            Tag => match c {
+                '>' => self.close_tag(false),
+                c if c.is_whitespace() || c == '/' => (),
+                c => self.attr_name(c),
+            },
+            AttrName => match c {
+                '=' => self.state = BeforeValue,
+                '>' => self.close_tag(false),
+                '/' => self.state = Tag,
+                c if c.is_whitespace() => self.state = AfterAttrName,
+                c => self.attr.push(c.to_ascii_lowercase()),
+            },
+            AfterAttrName => match c {
+                '=' => self.state = BeforeValue,
+                '>' => self.close_tag(false),
+                '/' => self.state = Tag,
+                c if c.is_whitespace() => (),
+                c => self.attr_name(c),
+            },
+            BeforeValue => match c {
+                '"' | '\'' => self.state = Value(c, self.attr_kind()),
+                '>' => self.close_tag(false),
+                c if c.is_whitespace() => (),
+                _ => self.state = Unquoted,
+            },
+            Unquoted => match c {
+                '>' => self.close_tag(false),
+                c if c.is_whitespace() => {
+                    self.attr.clear();
+                    self.state = Tag;
+                }
+                _ => (),
+            },
+            Value(quote, _) if c == quote => {
+                self.attr.clear();
+                self.state = Tag;
+            }
+            Value(quote, attr) => {
+                let attr = match attr {
+                    Attr::Normal => Attr::Normal,
+                    Attr::Url(Url::Query) => Attr::Url(Url::Query),
+                    Attr::Url(_) if c == '?' || c == '#' => Attr::Url(Url::Query),
+                    Attr::Url(_) => Attr::Url(Url::Path),
+                    Attr::Js(js) => {
+                        let (js, n) = next_js(js, c, rest);
+                        self.state = Value(quote, Attr::Js(js));
+                        return n;
+                    }
+                    Attr::Css(css) => {
+                        let (css, n) = next_css(css, c, rest);
+                        self.state = Value(quote, Attr::Css(css));
+                        return n;
+                    }
+                };
+                self.state = Value(quote, attr);
+            }
+            RawText | Script(_) | Style(_) if self.is_end_tag(rest) => {
+                self.state = TagName { end: true };
+                return 2 + self.tag.len();
+            }
+            RawText => (),
+            Script(js) => {
+                let (js, n) = next_js(js, c, rest);
+                self.state = Script(js);
+                return n;
+            }
+            Style(css) => {
+                let (css, n) = next_css(css, c, rest);
+                self.state = Style(css);
+                return n;
+            }
+        }
+
+        len
+    }
+
+    fn attr_name(&mut self, c: char) {
+        self.attr.clear();
+        self.attr.push(c.to_ascii_lowercase());
+        self.state = State::AttrName;
+    }
+
+    fn attr_kind(&self) -> Attr {
+        const URL: &[&str] = &[
+            "action",
+            "background",
+            "cite",
+            "codebase",
+            "formaction",
+            "href",
+            "icon",
+            "longdesc",
+            "manifest",
+            "poster",
+            "src",
+            "xlink:href",
+        ];
+        if self.attr.starts_with("on") {
+            Attr::Js(Js::Code)
+        } else if self.attr == "style" {
+            Attr::Css(Css::Code)
+        } else if URL.contains(&self.attr.as_str()) {
+            Attr::Url(Url::Start)
+        } else {
+            Attr::Normal
+        }
+    }
+
+    fn close_tag(&mut self, end: bool) {
+        self.attr.clear();
+        self.state = if end {
+            State::Text
+        } else {
+            match self.tag.as_str() {
+                "script" => State::Script(Js::Code),
+                "style" => State::Style(Css::Code),
+                "textarea" | "title" => State::RawText,
+                _ => State::Text,
+            }
+        };
+        if self.state == State::Text {
+            self.tag.clear();
+        }
+    }
+
+    fn is_end_tag(&self, rest: &str) -> bool {
+        rest.starts_with("</")
+            && rest
+                .get(2..2 + self.tag.len())
+                .is_some_and(|x| x.eq_ignore_ascii_case(&self.tag))
+    }
+
+    /// Merge contexts of branches, only different parts of a url are compatible
+    fn merge(&self, other: &Context) -> Option<Context> {
+        if self == other {
+            return Some(self.clone());
+        }
+        match (self.state, other.state) {
+            (State::Value(a, Attr::Url(_)), State::Value(b, Attr::Url(_)))
+                if a == b && self.tag == other.tag && self.attr == other.attr =>
+            {
+                Some(Context {
+                    state: State::Value(a, Attr::Url(Url::Query)),
+                    ..self.clone()
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn next_js(js: Js, c: char, rest: &str) -> (Js, usize) {
+    let len = c.len_utf8();
+    let js = match js {
+        Js::Code => match c {
+            '"' | '\'' | '`' => Js::Str(c, false),
+            '/' if rest.starts_with("//") => return (Js::LineComment, 2),
+            '/' if rest.starts_with("/*") => return (Js::BlockComment, 2),
+            _ => Js::Code,
+        },
+        Js::Str(q, true) => Js::Str(q, false),
+        Js::Str(q, false) => match c {
+            '\\' => Js::Str(q, true),
+            c if c == q => Js::Code,
+            _ => js,
+        },
+        Js::LineComment if c == '\n' => Js::Code,
+        Js::BlockComment if rest.starts_with("*/") => return (Js::Code, 2),
+        Js::LineComment | Js::BlockComment => js,
+    };
+
+    (js, len)
+}
+
+fn next_css(css: Css, c: char, rest: &str) -> (Css, usize) {
+    let len = c.len_utf8();
+    let css = match css {
+        Css::Code => match c {
+            '"' | '\'' => Css::Str(c, false),
+            '/' if rest.starts_with("/*") => return (Css::Comment, 2),
+            _ => Css::Code,
+        },
+        Css::Str(q, true) => Css::Str(q, false),
+        Css::Str(q, false) => match c {
+            '\\' => Css::Str(q, true),
+            c if c == q => Css::Code,
+            _ => css,
+        },
+        Css::Comment if rest.starts_with("*/") => return (Css::Code, 2),
+        Css::Comment => css,
+    };
+
+    (css, len)
+}
+
+struct Tracker {
+    ctx: Context,
+    parent: Ident,
+    /// Wraps the expressions of html text and attribute values
+    text: bool,
+}
+
+impl Tracker {
+    fn nodes(&mut self, v: Vec<HIR>) -> EResult<Vec<HIR>> {
+        v.into_iter().map(|i| self.node(i)).collect()
+    }
+
+    fn node(&mut self, i: HIR) -> EResult<HIR> {
+        use HIR::*;
+        Ok(match i {
+            Lit(a) => {
+                self.ctx.lit(&a);
+                Lit(a)
+            }
+            Raw(a) => {
+                self.ctx.lit(&a);
+                Raw(a)
+            }
+            Expr(a, span) => Expr(self.expr(a, span)?, span),
+            Safe(a) => {
+                self.skip_url_start();
+                // safe attributes in a tag
+                if self.ctx.state == State::Tag {
+                    self.ctx.attr.clear();
+                    self.ctx.state = State::AttrName;
+                }
+                Safe(a)
+            }
+            Local(a) => Local(a),
+            Flush => Flush,
+            Each(a) => {
+                let yarte_hir::Each {
+                    args,
+                    body,
+                    expr,
+                    els,
+                } = *a;
+                let start = self.ctx.clone();
+                let (body, end) = self.body(body, "each")?;
+                let els = match els {
+                    Some(els) => {
+                        let (els, els_end) = self.branch(&start, els)?;
+                        self.ctx = merge(&end, &els_end, "each")?;
+                        Some(els)
+                    }
+                    None => None,
+                };
+                Each(Box::new(yarte_hir::Each {
+                    args,
+                    body,
+                    expr,
+                    els,
+                }))
+            }
+            IfElse(a) => {
+                let yarte_hir::IfElse { ifs, if_else, els } = *a;
+                let start = self.ctx.clone();
+                let (body, mut end) = self.branch(&start, ifs.1)?;
+                let ifs = (ifs.0, body);
+                let mut branches = vec![];
+                for (cond, body) in if_else {
+                    let (body, e) = self.branch(&start, body)?;
+                    end = merge(&end, &e, "if")?;
+                    branches.push((cond, body));
+                }
+                let els = match els {
+                    Some(els) => {
+                        let (els, e) = self.branch(&start, els)?;
+                        end = merge(&end, &e, "if")?;
+                        Some(els)
+                    }
+                    None => {
+                        end = merge(&end, &start, "if")?;
+                        None
+                    }
+                };
+                self.ctx = end;
+                IfElse(Box::new(yarte_hir::IfElse {
+                    ifs,
+                    if_else: branches,
+                    els,
+                }))
+            }
+            Match(a) => {
+                let yarte_hir::Match { expr, arms } = *a;
+                let start = self.ctx.clone();
+                let mut end: Option<Context> = None;
+                let mut new = vec![];
+                for (pat, guard, body) in arms {
+                    let (body, e) = self.branch(&start, body)?;
+                    end = Some(match end {
+                        Some(end) => merge(&end, &e, "match")?,
+                        None => e,
+                    });
+                    new.push((pat, guard, body));
+                }
+                self.ctx = end.unwrap_or(start);
+                Match(Box::new(yarte_hir::Match { expr, arms: new }))
+            }
+            BlockHelper(a) => {
+                let yarte_hir::BlockHelper { path, args, body } = *a;
+                let (body, end) = self.body(body, "block helper")?;
+                self.ctx = end;
+                BlockHelper(Box::new(yarte_hir::BlockHelper { path, args, body }))
+            }
+        })
+    }
+
+    fn branch(&mut self, start: &Context, v: Vec<HIR>) -> EResult<(Vec<HIR>, Context)> {
+        self.ctx = start.clone();
+        let v = self.nodes(v)?;
+        Ok((v, self.ctx.clone()))
+    }
+
+    /// Body rendered zero or more times, its start context is the merge of the
+    /// context before it and at its end
+    fn body(&mut self, v: Vec<HIR>, name: &str) -> EResult<(Vec<HIR>, Context)> {
+        let start = self.ctx.clone();
+        let (_, end) = self.branch(&start, v.clone())?;
+        let start = merge(&start, &end, name)?;
+        let (v, end) = self.branch(&start, v)?;
+        let end = merge(&start, &end, name)?;
+        if end != start {
+            return Err(ambiguous(name).into());
+        }
+        self.ctx = end.clone();
+        Ok((v, end))
+    }
+
+    fn skip_url_start(&mut self) {
+        if let State::Value(q, Attr::Url(Url::Start)) = self.ctx.state {
+            self.ctx.state = State::Value(q, Attr::Url(Url::Path));
+        }
+    }
+
+    fn expr(&mut self, e: Box<syn::Expr>, span: Span) -> EResult<Box<syn::Expr>> {
+        use State::*;
+        let parent = &self.parent;
+        let escaper = |e: &str| -> TokenStream {
+            let e = format_ident!("{}", e);
+            quote!(#parent::Escaper::#e)
+        };
+        // `@json` is lowered to a reference
+        let json = |attr: bool| Ok(quote!(#parent::EscapedJson(#e, #attr)));
+        let escaped = |escaper: TokenStream| Ok(quote!(#parent::Escaped(&(#e), #escaper)));
+
+        let tokens: Result<TokenStream, String> = match self.ctx.state {
+            Text | Comment | Declaration | RawText | Value(_, Attr::Normal) if self.text => {
+                escaped(escaper("Html"))
+            }
+            Text | Comment | Declaration | RawText | Value(_, Attr::Normal) => return Ok(e),
+            TagOpen | TagName { .. } => Err("expression in a tag name".into()),
+            Tag | AttrName | AfterAttrName => Err("expression in an attribute name".into()),
+            BeforeValue | Unquoted => Err(format!(
+                "expression in the unquoted value of attribute `{}`, quote it",
+                self.ctx.attr
+            )),
+            Value(_, Attr::Url(url)) => escaped(escaper(match url {
+                Url::Start => "Url",
+                Url::Path => "UrlPath",
+                Url::Query => "UrlQuery",
+            })),
+            Value(_, Attr::Js(Js::Code)) if is_json(&e) => json(true),
+            Script(Js::Code) if is_json(&e) => json(false),
+            Value(_, Attr::Js(Js::Code)) => Err(format!(
+                "expression in javascript code of attribute `{}`, use a string or `@json`",
+                self.ctx.attr
+            )),
+            Script(Js::Code) => {
+                Err("expression in javascript code of `<script>`, use a string or `@json`".into())
+            }
+            Value(_, Attr::Js(_)) | Script(_) => escaped(escaper("JsString")),
+            Value(_, Attr::Css(Css::Code)) | Style(Css::Code) => escaped(escaper("CssValue")),
+            Value(_, Attr::Css(_)) | Style(_) => escaped(escaper("CssString")),
+        };
+        self.skip_url_start();
+
+        match tokens {
+            Ok(tokens) => Ok(Box::new(syn::parse2(tokens).expect("valid expression"))),
+            Err(message) => Err(EscapeError {
+                message,
+                span: Some(span),
+            }),
+        }
+    }
+}
+
+fn merge(a: &Context, b: &Context, name: &str) -> EResult<Context> {
+    a.merge(b).ok_or_else(|| ambiguous(name).into())
+}
+
+fn ambiguous(name: &str) -> String {
+    format!("html context at the end of `{name}` depends on the rendered branch")
+}
+
+/// Some expression of `v` is wrapped in an escaper of `parent`
+pub(crate) fn has_escaper(v: &[HIR], parent: &Ident) -> bool {
+    let escaped = |e: &syn::Expr| match e {
+        syn::Expr::Call(syn::ExprCall { func, .. }) => match &**func {
+            syn::Expr::Path(syn::ExprPath { path, .. }) => {
+                let mut segments = path.segments.iter().map(|s| &s.ident);
+                segments.next() == Some(parent)
+                    && segments
+                        .next()
+                        .is_some_and(|i| i == "Escaped" || i == "EscapedJson")
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+    v.iter().any(|i| match i {
+        HIR::Expr(e, _) | HIR::Safe(e) => escaped(e),
+        HIR::Each(e) => has_escaper(&e.body, parent) || has_escaper_opt(&e.els, parent),
+        HIR::IfElse(e) => {
+            has_escaper(&e.ifs.1, parent)
+                || e.if_else.iter().any(|(_, v)| has_escaper(v, parent))
+                || has_escaper_opt(&e.els, parent)
+        }
+        HIR::Match(e) => e.arms.iter().any(|(_, _, v)| has_escaper(v, parent)),
+        HIR::BlockHelper(e) => has_escaper(&e.body, parent),
+        _ => false,
+    })
+}
+
+fn has_escaper_opt(v: &Option<Vec<HIR>>, parent: &Ident) -> bool {
+    v.as_ref().is_some_and(|v| has_escaper(v, parent))
+}
+
+/// `@json` helper, `(&(expr).__as_json())`
+fn is_json(e: &syn::Expr) -> bool {
+    match e {
+        syn::Expr::Paren(e) => is_json(&e.expr),
+        syn::Expr::Reference(e) => is_json(&e.expr),
+        syn::Expr::MethodCall(e) => e.method == "__as_json" || e.method == "__as_json_pretty",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn state(src: &str) -> State {
+        let mut ctx = Context::default();
+        ctx.lit(src);
+        ctx.state
+    }
+
+    #[test]
+    fn test_html() {
+        use State::*;
+        assert_eq!(state("<p>"), Text);
+        assert_eq!(state("<p class=\"a"), Value('"', Attr::Normal));
+        assert_eq!(state("<p class=\"a\" "), Tag);
+        assert_eq!(state("<p class=a"), Unquoted);
+        assert_eq!(state("<p class="), BeforeValue);
+        assert_eq!(state("<p on"), AttrName);
+        assert_eq!(state("<"), TagOpen);
+        assert_eq!(state("<!-- <p class=\""), Comment);
+        assert_eq!(
+            state("<!-- a --><a href='"),
+            Value('\'', Attr::Url(Url::Start))
+        );
+        assert_eq!(state("<a href='/a?"), Value('\'', Attr::Url(Url::Query)));
+        assert_eq!(state("<title><p class=\""), RawText);
+        assert_eq!(state("<title></TITLE><p>"), Text);
+    }
+
+    #[test]
+    fn test_js_css() {
+        use State::*;
+        assert_eq!(state("<script>var a = "), Script(Js::Code));
+        assert_eq!(state("<script>var a = '\\'"), Script(Js::Str('\'', false)));
+        assert_eq!(state("<script>// '\n"), Script(Js::Code));
+        assert_eq!(state("<script>'</script>"), Text);
+        assert_eq!(
+            state("<a onclick=\"f('"),
+            Value('"', Attr::Js(Js::Str('\'', false)))
+        );
+        assert_eq!(state("<style>p { color: "), Style(Css::Code));
+        assert_eq!(
+            state("<style>/* \"a */ p::after { content: \""),
+            Style(Css::Str('"', false))
+        );
+        assert_eq!(
+            state("<p style=\"color: "),
+            Value('"', Attr::Css(Css::Code))
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use proc_macro2::TokenStream;
+
+use yarte_hir::HIR;
+
+use crate::CodeGen;
+
+/// Implements the code of two code generators from the same HIR
+///
+/// Both generate the fragments, so their methods need distinct names.
+pub struct DualCodeGen<A: CodeGen, B: CodeGen> {
+    a: A,
+    b: B,
+}
+
+impl<A: CodeGen, B: CodeGen> DualCodeGen<A, B> {
+    pub fn new(a: A, b: B) -> DualCodeGen<A, B> {
+        DualCodeGen { a, b }
+    }
+}
+
+impl<A: CodeGen, B: CodeGen> CodeGen for DualCodeGen<A, B> {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let mut tokens = self.a.gen(v.clone());
+        tokens.extend(self.b.gen(v));
+        tokens
+    }
+
+    fn gen_fragment(&mut self, name: &str, v: Vec<HIR>) -> TokenStream {
+        let mut tokens = self.a.gen_fragment(name, v.clone());
+        tokens.extend(self.b.gen_fragment(name, v));
+        tokens
+    }
+}
@@ -0,0 +1,212 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use yarte_hir::{Struct, HIR};
+
+use crate::{size_hint::size_hint, CodeGen};
+
+fn error_ty(s: &Struct) -> Result<TokenStream, TokenStream> {
+    match &s.error {
+        Some(ty) => {
+            let ty: syn::Type = syn::parse_str(ty).expect("valid type");
+            Ok(quote!(#ty))
+        }
+        None => Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "fallible templates must specify the attribute 'error'",
+        )
+        .to_compile_error()),
+    }
+}
+
+/// Implements `TemplateTryTrait` over a fmt code generator
+///
+/// The render runs in a closure returning `Result<(), Self::Error>`,
+/// so the `?` of the expressions is converted into the error of the template.
+pub struct TryFmtCodeGen<'a, T: CodeGen> {
+    codegen: T,
+    s: &'a Struct<'a>,
+    parent: Ident,
+}
+
+impl<'a, T: CodeGen> TryFmtCodeGen<'a, T> {
+    pub fn new<'n>(codegen: T, s: &'n Struct, parent: &'static str) -> TryFmtCodeGen<'n, T> {
+        TryFmtCodeGen {
+            codegen,
+            s,
+            parent: format_ident!("{}", parent),
+        }
+    }
+}
+
+impl<'a, T: CodeGen> TryFmtCodeGen<'a, T> {
+    /// Expression rendering `v` to a `String` or returning the first error
+    fn try_call(&mut self, v: Vec<HIR>, error: &TokenStream) -> TokenStream {
+        let size_hint = size_hint(&v, &self.s.fields).total();
+        let nodes = self.codegen.gen(v);
+        let parent = &self.parent;
+        // The closure is typed out of the glob import, which could shadow the error
+        quote!({
+            use std::fmt::Write;
+            let mut __yarte_error = None;
+            let mut __yarte_buf = String::with_capacity(#size_hint);
+            let __yarte_render =
+                |_fmt: &mut std::fmt::Formatter| -> std::result::Result<(), #error> {
+                    #[allow(unused_imports)]
+                    use std::fmt::Display;
+                    #[allow(unused_imports)]
+                    use #parent::*;
+                    #nodes
+                    Ok(())
+                };
+            let _ = write!(
+                __yarte_buf,
+                "{}",
+                #parent::DisplayFn::new(|_fmt: &mut std::fmt::Formatter| {
+                    __yarte_render(_fmt).map_err(|e| {
+                        __yarte_error = Some(e);
+                        std::fmt::Error
+                    })
+                })
+            );
+            match __yarte_error {
+                Some(e) => Err(e),
+                None => Ok(__yarte_buf),
+            }
+        })
+    }
+}
+
+impl<'a, T: CodeGen> CodeGen for TryFmtCodeGen<'a, T> {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let error = match error_ty(self.s) {
+            Ok(ty) => ty,
+            Err(e) => return e,
+        };
+        let try_call = self.try_call(v, &error);
+        let parent = &self.parent;
+        self.s.implement_head(
+            quote!(#parent::TemplateTryTrait),
+            &quote!(
+                type Error = #error;
+
+                fn try_call(&self) -> std::result::Result<String, Self::Error> {
+                    #try_call
+                }
+            ),
+        )
+    }
+
+    fn gen_fragment(&mut self, name: &str, v: Vec<HIR>) -> TokenStream {
+        let error = match error_ty(self.s) {
+            Ok(ty) => ty,
+            Err(e) => return e,
+        };
+        let try_call = self.try_call(v, &error);
+        let method = format_ident!("fragment_{}", name);
+        let doc = format!("Renders the fragment `{name}` or returns the first error");
+        self.s.implement(&quote!(
+            #[doc = #doc]
+            pub fn #method(&self) -> std::result::Result<String, #error> {
+                #try_call
+            }
+        ))
+    }
+}
+
+/// Implements `TemplateBytesTryTrait` over a bytes code generator
+#[cfg(feature = "bytes-buf")]
+pub struct TryBytesCodeGen<'a, T: CodeGen> {
+    codegen: T,
+    s: &'a Struct<'a>,
+    parent: Ident,
+    buf: Ident,
+}
+
+#[cfg(feature = "bytes-buf")]
+impl<'a, T: CodeGen> TryBytesCodeGen<'a, T> {
+    pub fn new<'n>(
+        codegen: T,
+        s: &'n Struct,
+        buf: Ident,
+        parent: &'static str,
+    ) -> TryBytesCodeGen<'n, T> {
+        TryBytesCodeGen {
+            codegen,
+            s,
+            parent: format_ident!("{}", parent),
+            buf,
+        }
+    }
+}
+
+#[cfg(feature = "bytes-buf")]
+impl<'a, T: CodeGen> CodeGen for TryBytesCodeGen<'a, T> {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let error = match error_ty(self.s) {
+            Ok(ty) => ty,
+            Err(e) => return e,
+        };
+        let nodes = self.codegen.gen(v);
+        let parent = &self.parent;
+        let buf = &self.buf;
+        self.s.implement_head(
+            quote!(#parent::TemplateBytesTryTrait),
+            &quote!(
+                type Error = #error;
+
+                fn try_call<B: #parent::Buffer>(
+                    &self,
+                    capacity: usize,
+                ) -> std::result::Result<B::Freeze, Self::Error> {
+                    use #parent::*;
+                    let mut #buf = B::with_capacity(capacity);
+                    macro_rules! buf_ref {
+                        ($b:expr) => { &mut $b };
+                    }
+                    #nodes
+                    Ok(#buf.freeze())
+                }
+
+                fn try_write_call<B: #parent::Buffer>(
+                    &self,
+                    #buf: &mut B,
+                ) -> std::result::Result<(), Self::Error> {
+                    use #parent::*;
+                    macro_rules! buf_ref {
+                        ($b:expr) => { $b };
+                    }
+                    #nodes
+                    Ok(())
+                }
+            ),
+        )
+    }
+
+    fn gen_fragment(&mut self, name: &str, v: Vec<HIR>) -> TokenStream {
+        let error = match error_ty(self.s) {
+            Ok(ty) => ty,
+            Err(e) => return e,
+        };
+        let nodes = self.codegen.gen(v);
+        let parent = &self.parent;
+        let buf = &self.buf;
+        let method = format_ident!("fragment_{}", name);
+        let doc = format!("Renders the fragment `{name}` to buffer or returns the first error");
+        self.s.implement(&quote!(
+            #[doc = #doc]
+            pub fn #method<B: #parent::Buffer>(
+                &self,
+                capacity: usize,
+            ) -> std::result::Result<B::Freeze, #error> {
+                use #parent::*;
+                let mut #buf = B::with_capacity(capacity);
+                macro_rules! buf_ref {
+                    ($b:expr) => { &mut $b };
+                }
+                #nodes
+                Ok(#buf.freeze())
+            }
+        ))
+    }
+}
@@ -1,14 +1,22 @@
+use std::env;
+
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 
+use yarte_helpers::config::Mode;
 use yarte_hir::{Struct, HIR};
 
-use crate::CodeGen;
+use crate::{
+    context::has_escaper,
+    size_hint::{size_hint, SizeHint},
+    CodeGen,
+};
 
 pub struct FmtCodeGen<'a, T: CodeGen> {
     codegen: T,
     s: &'a Struct<'a>,
     parent: Ident,
+    reload: Option<bool>,
 }
 
 impl<'a, T: CodeGen> FmtCodeGen<'a, T> {
@@ -17,24 +25,70 @@ impl<'a, T: CodeGen> FmtCodeGen<'a, T> {
             codegen,
             s,
             parent: format_ident!("{}", parent),
+            reload: None,
+        }
+    }
+
+    /// Re-render from the template file at runtime in debug builds when
+    /// `YARTE_RELOAD` is set, needs the `dynamic` module of parent.
+    /// The path is relative to the crate root, so `YARTE_RELOAD_DIR` can move it.
+    ///
+    /// The runtime only escapes html or nothing, so templates of other modes aren't reloaded
+    pub fn hot_reload(mut self, is_text: bool) -> Self {
+        let mode = if is_text { Mode::Text } else { Mode::Html };
+        if self.s.from_file && self.s.resolve_mode(is_text) == mode {
+            self.reload = Some(is_text);
+        }
+        self
+    }
+
+    fn reload(&self) -> TokenStream {
+        match self.reload {
+            Some(is_text) => {
+                let parent = &self.parent;
+                let root = env::var("CARGO_MANIFEST_DIR").unwrap();
+                let path = self.s.path.strip_prefix(&root).unwrap_or(&self.s.path);
+                let path = path.to_str().expect("valid utf-8 path");
+                quote!(
+                    #[cfg(debug_assertions)]
+                    {
+                        use #parent::dynamic::{Reload, ReloadFallback as _, ReloadSerialize as _};
+                        if let Some(s) = (&&Reload(self)).reload(env!("CARGO_MANIFEST_DIR"), #path, #is_text) {
+                            return _fmt.write_str(&s);
+                        }
+                    }
+                )
+            }
+            None => TokenStream::new(),
         }
     }
 
     #[inline]
-    fn template(&self, size_hint: usize, tokens: &mut TokenStream) {
+    fn template(&self, size_hint: SizeHint, tokens: &mut TokenStream) {
+        let SizeHint { fixed, .. } = size_hint;
+        let size_hint = size_hint.total();
         tokens.extend(self.s.implement_head(
             quote!(yarte::TemplateTrait),
             &quote!(
             fn size_hint() -> usize {
                 #size_hint
+            }
+
+            fn size_hint_static() -> usize {
+                #fixed
             }),
         ));
     }
 
-    fn display(&mut self, nodes: Vec<HIR>, tokens: &mut TokenStream) -> usize {
+    fn display(&mut self, nodes: Vec<HIR>, tokens: &mut TokenStream) -> SizeHint {
+        let size_hint = size_hint(&nodes, &self.s.fields);
+        // nor the escapers of the html context
+        let reload = if has_escaper(&nodes, &self.parent) {
+            TokenStream::new()
+        } else {
+            self.reload()
+        };
         let nodes = self.codegen.gen(nodes);
-        // heuristic based on https://github.com/lfairy/maud
-        let size_hint = nodes.to_string().len();
         let parent = &self.parent;
         let func = quote!(
             fn fmt(&self, _fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -42,6 +96,7 @@ impl<'a, T: CodeGen> FmtCodeGen<'a, T> {
                 use std::fmt::Display;
                 #[allow(unused_imports)]
                 use #parent::*;
+                #reload
                 #nodes
                 Ok(())
             }
@@ -63,4 +118,29 @@ impl<'a, T: CodeGen> CodeGen for FmtCodeGen<'a, T> {
 
         tokens
     }
+
+    fn gen_fragment(&mut self, name: &str, v: Vec<HIR>) -> TokenStream {
+        let size_hint = size_hint(&v, &self.s.fields).total();
+        let nodes = self.codegen.gen(v);
+        let parent = &self.parent;
+        let method = format_ident!("fragment_{}", name);
+        let render = format_ident!("__yarte_fragment_{}", name);
+        let doc = format!("Renders the fragment `{name}`");
+        self.s.implement(&quote!(
+            #[doc = #doc]
+            pub fn #method(&self) -> #parent::Fragment<'_, Self> {
+                #parent::Fragment::new(self, Self::#render, #size_hint)
+            }
+
+            #[doc(hidden)]
+            fn #render(&self, _fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+                #[allow(unused_imports)]
+                use std::fmt::Display;
+                #[allow(unused_imports)]
+                use #parent::*;
+                #nodes
+                Ok(())
+            }
+        ))
+    }
 }
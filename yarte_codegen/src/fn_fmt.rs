@@ -3,7 +3,7 @@ use quote::{format_ident, quote};
 
 use yarte_hir::HIR;
 
-use crate::CodeGen;
+use crate::{size_hint::size_hint, CodeGen};
 
 pub struct FnFmtCodeGen<T: CodeGen> {
     codegen: T,
@@ -19,9 +19,8 @@ impl<T: CodeGen> FnFmtCodeGen<T> {
     }
 
     fn body(&mut self, nodes: Vec<HIR>) -> (TokenStream, usize) {
+        let size_hint = size_hint(&nodes, &[]).total();
         let body = self.codegen.gen(nodes);
-        // heuristic based on https://github.com/lfairy/maud
-        let size_hint = body.to_string().len();
 
         (body, size_hint)
     }
@@ -1,22 +1,25 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use super::{CodeGen, EachCodeGen, IfElseCodeGen, HIR};
+use super::{BlockHelperCodeGen, CodeGen, EachCodeGen, IfElseCodeGen, MatchCodeGen, HIR};
 
 fn gen<C>(codegen: &mut C, v: Vec<HIR>) -> TokenStream
 where
-    C: CodeGen + EachCodeGen + IfElseCodeGen,
+    C: CodeGen + EachCodeGen + IfElseCodeGen + MatchCodeGen + BlockHelperCodeGen,
 {
     let mut tokens = TokenStream::new();
     for i in v {
         use HIR::*;
         tokens.extend(match i {
             Local(a) => quote!(#a),
-            Lit(a) => quote!(_fmt.write_str(#a)?;),
+            Lit(a) | Raw(a) => quote!(_fmt.write_str(#a)?;),
             Safe(a) => quote!((&(#a)).fmt(_fmt)?;),
-            Expr(a) => quote!((&(#a)).__renders_it(_fmt)?;),
+            Expr(a, _) => quote!((&(#a)).__renders_it(_fmt)?;),
             Each(a) => codegen.gen_each(*a),
             IfElse(a) => codegen.gen_if_else(*a),
+            Match(a) => codegen.gen_match(*a),
+            BlockHelper(a) => codegen.gen_block_helper(*a),
+            Flush => quote!(),
         })
     }
     tokens
@@ -27,6 +30,8 @@ pub struct HTMLCodeGen;
 impl EachCodeGen for HTMLCodeGen {}
 
 impl IfElseCodeGen for HTMLCodeGen {}
+impl MatchCodeGen for HTMLCodeGen {}
+impl BlockHelperCodeGen for HTMLCodeGen {}
 impl CodeGen for HTMLCodeGen {
     fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
         gen(self, v)
@@ -36,16 +41,14 @@ impl CodeGen for HTMLCodeGen {
 #[cfg(feature = "html-min")]
 pub mod html_min {
     use super::*;
-    use yarte_dom::DOMFmt;
+    use crate::minify::minify;
 
+    /// Html code generator with the literals minified
     pub struct HTMLMinCodeGen;
-    impl EachCodeGen for HTMLMinCodeGen {}
-    impl IfElseCodeGen for HTMLMinCodeGen {}
 
     impl CodeGen for HTMLMinCodeGen {
         fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
-            let dom: DOMFmt = v.into();
-            gen(self, dom.0)
+            HTMLCodeGen.gen(minify(v))
         }
     }
 }
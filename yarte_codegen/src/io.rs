@@ -0,0 +1,119 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use yarte_hir::{Struct, HIR};
+
+use crate::CodeGen;
+
+/// Implements `TemplateIoTrait` over a bytes code generator
+///
+/// The render is buffered in an internal `IoBuffer` which writes to the `io::Write` when it's full.
+/// It's wrapped in a `Buffer` local to the method, so no template can be called with it.
+pub struct IoCodeGen<'a, T: CodeGen> {
+    codegen: T,
+    s: &'a Struct<'a>,
+    parent: Ident,
+    buf: Ident,
+}
+
+impl<'a, T: CodeGen> IoCodeGen<'a, T> {
+    pub fn new<'n>(
+        codegen: T,
+        s: &'n Struct,
+        buf: Ident,
+        parent: &'static str,
+    ) -> IoCodeGen<'n, T> {
+        IoCodeGen {
+            codegen,
+            s,
+            parent: format_ident!("{}", parent),
+            buf,
+        }
+    }
+}
+
+/// `Buffer` over the `IoBuffer`, private to the render
+fn io_buffer(parent: &Ident) -> TokenStream {
+    quote! {
+        struct __YarteIoBuffer<W: std::io::Write>(#parent::__IoBuffer<W>);
+        impl<W: std::io::Write> #parent::Buffer for __YarteIoBuffer<W> {
+            type Freeze = std::io::Result<()>;
+            fn with_capacity(_: usize) -> Self {
+                unreachable!()
+            }
+            #[inline]
+            fn is_empty(&self) -> bool {
+                self.0.is_empty()
+            }
+            #[inline]
+            fn len(&self) -> usize {
+                self.0.len()
+            }
+            #[inline]
+            unsafe fn extend_from_slice(&mut self, src: &[u8]) {
+                self.0.extend_from_slice(src)
+            }
+            #[inline]
+            fn reserve(&mut self, additional: usize) {
+                self.0.reserve(additional)
+            }
+            fn freeze(self) -> Self::Freeze {
+                self.0.freeze()
+            }
+            #[inline]
+            unsafe fn advance(&mut self, cnt: usize) {
+                self.0.advance(cnt)
+            }
+            #[inline]
+            unsafe fn buf_ptr(&mut self) -> *mut u8 {
+                self.0.buf_ptr()
+            }
+        }
+    }
+}
+
+impl<'a, T: CodeGen> CodeGen for IoCodeGen<'a, T> {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let nodes = self.codegen.gen(v);
+        let parent = &self.parent;
+        let buf = &self.buf;
+        let io_buffer = io_buffer(parent);
+        self.s.implement_head(
+            quote!(#parent::TemplateIoTrait),
+            &quote!(
+                fn write_io<W: std::io::Write>(&self, __yarte_writer: W) -> std::io::Result<()> {
+                    use #parent::*;
+                    #io_buffer
+                    let mut #buf = __YarteIoBuffer(#parent::__IoBuffer::new(__yarte_writer));
+                    macro_rules! buf_ref {
+                        ($b:expr) => { &mut $b };
+                    }
+                    #nodes
+                    #buf.freeze()
+                }
+            ),
+        )
+    }
+
+    fn gen_fragment(&mut self, name: &str, v: Vec<HIR>) -> TokenStream {
+        let nodes = self.codegen.gen(v);
+        let parent = &self.parent;
+        let buf = &self.buf;
+        let io_buffer = io_buffer(parent);
+        let method = format_ident!("fragment_{}", name);
+        let doc = format!("Writes the fragment `{name}` to the `io::Write`");
+        self.s.implement(&quote!(
+            #[doc = #doc]
+            pub fn #method<W: std::io::Write>(&self, __yarte_writer: W) -> std::io::Result<()> {
+                use #parent::*;
+                #io_buffer
+                let mut #buf = __YarteIoBuffer(#parent::__IoBuffer::new(__yarte_writer));
+                macro_rules! buf_ref {
+                    ($b:expr) => { &mut $b };
+                }
+                #nodes
+                #buf.freeze()
+            }
+        ))
+    }
+}
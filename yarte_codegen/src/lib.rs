@@ -2,36 +2,100 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use yarte_hir::{Each, IfElse, HIR};
+use yarte_hir::{BlockHelper, Each, IfElse, Match, HIR};
 
 #[cfg(feature = "bytes-buf")]
 mod attr_b;
 #[cfg(feature = "bytes-buf")]
 mod bytes;
+#[cfg(feature = "bytes-buf")]
+mod coalesce;
+mod context;
+#[cfg(feature = "bytes-buf")]
+mod dual;
+mod fallible;
 mod fmt;
 mod fn_fmt;
 mod html;
+#[cfg(feature = "bytes-buf")]
+mod io;
+#[cfg(feature = "html-min")]
+mod minify;
+mod mode;
+mod size_hint;
+#[cfg(feature = "stream")]
+mod stream;
 mod text;
+#[cfg(any(feature = "wasm-app", feature = "wasm-server"))]
+pub mod wasm;
 #[cfg(feature = "bytes-buf")]
 mod write_b;
 
-pub use self::{fmt::FmtCodeGen, fn_fmt::FnFmtCodeGen, html::HTMLCodeGen, text::TextCodeGen};
+pub use self::{
+    context::{escape_context, EscapeError},
+    fallible::TryFmtCodeGen,
+    fmt::FmtCodeGen,
+    fn_fmt::FnFmtCodeGen,
+    html::HTMLCodeGen,
+    mode::escape_mode,
+    text::TextCodeGen,
+};
 
 #[cfg(feature = "bytes-buf")]
 pub use self::attr_b::AttrBCodeGen;
 #[cfg(feature = "bytes-buf")]
 pub use self::bytes::{BytesCodeGen, HTMLBytesCodeGen, TextBytesCodeGen};
 #[cfg(feature = "bytes-buf")]
+pub use self::dual::DualCodeGen;
+#[cfg(feature = "bytes-buf")]
+pub use self::fallible::TryBytesCodeGen;
+#[cfg(feature = "bytes-buf")]
+pub use self::io::IoCodeGen;
+#[cfg(feature = "stream")]
+pub use self::stream::StreamCodeGen;
+#[cfg(feature = "bytes-buf")]
 pub use self::write_b::WriteBCodeGen;
 
+#[cfg(all(feature = "bytes-buf", feature = "html-min"))]
+pub use self::bytes::html_min::HTMLMinBytesCodeGen;
+#[cfg(feature = "html-min")]
+pub use self::html::html_min::HTMLMinCodeGen;
+
 pub trait CodeGen {
     fn gen(&mut self, v: Vec<HIR>) -> TokenStream;
+
+    /// Entry point rendering only the fragment `name`
+    ///
+    /// By default the fragment is only rendered in place, as part of the template.
+    fn gen_fragment(&mut self, _name: &str, _v: Vec<HIR>) -> TokenStream {
+        TokenStream::new()
+    }
 }
 
 pub trait EachCodeGen: CodeGen {
-    fn gen_each(&mut self, Each { args, body, expr }: Each) -> TokenStream {
+    fn gen_each(
+        &mut self,
+        Each {
+            args,
+            body,
+            expr,
+            els,
+        }: Each,
+    ) -> TokenStream {
         let body = self.gen(body);
-        quote!(for #expr in #args { #body })
+        if let Some(els) = els {
+            let els = self.gen(els);
+            quote! {{
+                let mut __yarte_empty = true;
+                for #expr in #args {
+                    __yarte_empty = false;
+                    #body
+                }
+                if __yarte_empty { #els }
+            }}
+        } else {
+            quote!(for #expr in #args { #body })
+        }
     }
 }
 
@@ -56,3 +120,24 @@ pub trait IfElseCodeGen: CodeGen {
         tokens
     }
 }
+
+pub trait MatchCodeGen: CodeGen {
+    fn gen_match(&mut self, Match { expr, arms }: Match) -> TokenStream {
+        let mut tokens = TokenStream::new();
+
+        for (pat, guard, body) in arms {
+            let body = self.gen(body);
+            let guard = guard.map(|guard| quote!(if #guard));
+            tokens.extend(quote!(#pat #guard => { #body }));
+        }
+
+        quote!(match #expr { #tokens })
+    }
+}
+
+pub trait BlockHelperCodeGen: CodeGen {
+    fn gen_block_helper(&mut self, BlockHelper { path, args, body }: BlockHelper) -> TokenStream {
+        let body = self.gen(body);
+        quote!((&#path(&(#args), DisplayFn::new(|_fmt| { #body Ok(()) }))).fmt(_fmt)?;)
+    }
+}
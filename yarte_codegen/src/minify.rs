@@ -0,0 +1,638 @@
+//! Html minifier over the literals of the HIR
+//!
+//! Collapses whitespace, drops comments and omits the optional end tags at
+//! compile time. The content of `<pre>`, `<textarea>`, `<script>`, `<style>`
+//! and `{{R}}` blocks is written as is, like the comments with an expression
+//! inside. Decisions at the end of a literal look at the next node, so an
+//! unknown neighbor, like an expression, keeps the html.
+use std::mem;
+
+use yarte_hir::HIR;
+
+/// Minify the html of the literals of `v`
+pub fn minify(v: Vec<HIR>) -> Vec<HIR> {
+    let mut m = Minifier {
+        state: State {
+            mode: Mode::Text,
+            prev: Side::Block,
+        },
+        ws: false,
+        pending: None,
+        comment: String::new(),
+        tag: String::new(),
+        out: String::new(),
+    };
+    let mut v = m.nodes(v);
+    m.before(Next::End);
+    m.write(&mut v);
+    v
+}
+
+const INLINE: &[&str] = &[
+    "a", "abbr", "acronym", "audio", "b", "bdi", "bdo", "big", "br", "button", "canvas", "cite",
+    "code", "data", "del", "dfn", "em", "font", "i", "iframe", "img", "input", "ins", "kbd",
+    "label", "mark", "math", "meter", "noscript", "object", "output", "picture", "progress", "q",
+    "ruby", "s", "samp", "select", "small", "span", "strike", "strong", "sub", "sup", "svg",
+    "textarea", "time", "tt", "u", "var", "video", "wbr",
+];
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+const RAW: &[&str] = &["pre", "script", "style", "textarea"];
+
+/// Start tags closing an open `<p>`
+const P_CLOSERS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// End tags that can be omitted by the next html
+const OPTIONAL: &[&str] = &[
+    "li", "dt", "dd", "p", "td", "th", "tr", "thead", "tbody", "tfoot", "option", "optgroup",
+];
+
+/// Kind of the html next to a whitespace
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    Block,
+    Inline,
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Mode {
+    Text,
+    /// True once a node is found inside, then the comment is written
+    Comment(bool),
+    /// Inside `<...>`, the tag is buffered until its end or until a node splits it
+    Tag {
+        name: String,
+        end: bool,
+        attrs: bool,
+        quote: Option<char>,
+        ws: bool,
+        split: bool,
+    },
+    /// Content of a raw element, like `<pre>`
+    Raw(String),
+    /// Branches end in different html, written as is
+    Off,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    mode: Mode,
+    prev: Side,
+}
+
+/// Next html after a whitespace or an optional end tag
+enum Next<'a> {
+    Start(&'a str),
+    EndTag(&'a str),
+    Text,
+    Unknown,
+    /// End of the template
+    End,
+}
+
+impl<'a> Next<'a> {
+    fn side(&self) -> Side {
+        match self {
+            Next::Start(name) | Next::EndTag(name) => side(name),
+            Next::End => Side::Block,
+            Next::Text => Side::Inline,
+            Next::Unknown => Side::Unknown,
+        }
+    }
+}
+
+/// The end tag of `name` can be omitted before `next`
+fn omit(name: &str, next: &Next) -> bool {
+    use Next::*;
+    match (name, next) {
+        ("li", Start("li")) => true,
+        ("dt" | "dd", Start("dt" | "dd")) => true,
+        ("p", Start(n)) => P_CLOSERS.contains(n),
+        ("td" | "th", Start("td" | "th")) => true,
+        ("tr", Start("tr")) => true,
+        ("thead" | "tbody", Start("tbody" | "tfoot")) => true,
+        ("option", Start("option" | "optgroup")) => true,
+        ("optgroup", Start("optgroup")) => true,
+        // end of the parent element
+        (
+            "li" | "dd" | "td" | "th" | "tr" | "tbody" | "tfoot" | "option" | "optgroup",
+            EndTag(_),
+        ) => true,
+        _ => false,
+    }
+}
+
+fn side(name: &str) -> Side {
+    if INLINE.contains(&name) {
+        Side::Inline
+    } else {
+        Side::Block
+    }
+}
+
+struct Minifier {
+    state: State,
+    /// Whitespace in text waiting for the next html
+    ws: bool,
+    /// Optional end tag waiting for the next html
+    pending: Option<String>,
+    /// Comment waiting for its end or a node
+    comment: String,
+    tag: String,
+    out: String,
+}
+
+impl Minifier {
+    fn nodes(&mut self, v: Vec<HIR>) -> Vec<HIR> {
+        let mut buf = vec![];
+        for i in v {
+            use HIR::*;
+            match i {
+                Lit(a) => self.lit(&a),
+                Local(_) | Flush => {
+                    self.write(&mut buf);
+                    buf.push(i);
+                }
+                Expr(..) | Safe(_) | Raw(_) => {
+                    self.node(Next::Text);
+                    self.write(&mut buf);
+                    buf.push(i);
+                    self.state.prev = Side::Inline;
+                }
+                Each(_) | IfElse(_) | Match(_) | BlockHelper(_) => {
+                    self.node(Next::Unknown);
+                    self.write(&mut buf);
+                    buf.push(self.block(i));
+                }
+            }
+        }
+        buf
+    }
+
+    fn write(&mut self, buf: &mut Vec<HIR>) {
+        if !self.out.is_empty() {
+            buf.push(HIR::Lit(mem::take(&mut self.out)));
+        }
+    }
+
+    /// Prepares the html before a node
+    fn node(&mut self, next: Next) {
+        match &mut self.state.mode {
+            Mode::Text => self.before(next),
+            // The node is rendered, so the comment is kept
+            Mode::Comment(false) => {
+                self.state.mode = Mode::Comment(true);
+                self.before(Next::Unknown);
+                self.out.push_str("<!--");
+                let comment = mem::take(&mut self.comment);
+                self.out.push_str(&comment);
+            }
+            Mode::Tag { ws, .. } => {
+                if mem::take(ws) {
+                    self.tag.push(' ');
+                }
+                self.split_tag();
+            }
+            Mode::Comment(true) | Mode::Raw(_) | Mode::Off => (),
+        }
+    }
+
+    fn block(&mut self, i: HIR) -> HIR {
+        use HIR::*;
+        let start = self.state.clone();
+        match i {
+            Each(a) => {
+                let yarte_hir::Each {
+                    args,
+                    body,
+                    expr,
+                    els,
+                } = *a;
+                let body = self.body(body);
+                let els = els.map(|els| {
+                    let end = self.state.clone();
+                    let (els, els_end) = self.branch(&start, els);
+                    self.state = merge(&end, &els_end);
+                    els
+                });
+                Each(Box::new(yarte_hir::Each {
+                    args,
+                    body,
+                    expr,
+                    els,
+                }))
+            }
+            IfElse(a) => {
+                let yarte_hir::IfElse { ifs, if_else, els } = *a;
+                let (body, mut end) = self.branch(&start, ifs.1);
+                let ifs = (ifs.0, body);
+                let if_else = if_else
+                    .into_iter()
+                    .map(|(cond, body)| {
+                        let (body, e) = self.branch(&start, body);
+                        end = merge(&end, &e);
+                        (cond, body)
+                    })
+                    .collect();
+                let els = match els {
+                    Some(els) => {
+                        let (els, e) = self.branch(&start, els);
+                        end = merge(&end, &e);
+                        Some(els)
+                    }
+                    None => {
+                        end = merge(&end, &start);
+                        None
+                    }
+                };
+                self.state = end;
+                IfElse(Box::new(yarte_hir::IfElse { ifs, if_else, els }))
+            }
+            Match(a) => {
+                let yarte_hir::Match { expr, arms } = *a;
+                let mut end: Option<State> = None;
+                let arms = arms
+                    .into_iter()
+                    .map(|(pat, guard, body)| {
+                        let (body, e) = self.branch(&start, body);
+                        end = Some(end.as_ref().map_or_else(|| e.clone(), |end| merge(end, &e)));
+                        (pat, guard, body)
+                    })
+                    .collect();
+                self.state = end.unwrap_or(start);
+                Match(Box::new(yarte_hir::Match { expr, arms }))
+            }
+            BlockHelper(a) => {
+                let yarte_hir::BlockHelper { path, args, body } = *a;
+                let body = self.body(body);
+                BlockHelper(Box::new(yarte_hir::BlockHelper { path, args, body }))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn branch(&mut self, start: &State, v: Vec<HIR>) -> (Vec<HIR>, State) {
+        self.state = start.clone();
+        let mut v = self.nodes(v);
+        if self.state.mode == Mode::Text {
+            self.before(Next::Unknown);
+        }
+        self.write(&mut v);
+        (v, self.state.clone())
+    }
+
+    /// Body rendered zero or more times
+    fn body(&mut self, v: Vec<HIR>) -> Vec<HIR> {
+        let start = self.state.clone();
+        let (_, end) = self.branch(&start, v.clone());
+        let start = merge(&start, &end);
+        let (v, end) = self.branch(&start, v);
+        self.state = merge(&start, &end);
+        v
+    }
+
+    fn lit(&mut self, s: &str) {
+        let mut i = 0;
+        while let Some(c) = s[i..].chars().next() {
+            i += self.next(c, &s[i..]);
+        }
+    }
+
+    /// Consumes the start of `rest`, being `c` its first char, returns the consumed length
+    fn next(&mut self, c: char, rest: &str) -> usize {
+        let len = c.len_utf8();
+        match &mut self.state.mode {
+            Mode::Text => {
+                if c.is_whitespace() {
+                    self.ws = true;
+                } else if rest.starts_with("<!--") {
+                    self.state.mode = Mode::Comment(false);
+                    self.comment.clear();
+                    return 4;
+                } else if let Some(end) = is_tag(rest) {
+                    self.tag.clear();
+                    self.tag.push('<');
+                    if end {
+                        self.tag.push('/');
+                    }
+                    self.state.mode = Mode::Tag {
+                        name: String::new(),
+                        end,
+                        attrs: false,
+                        quote: None,
+                        ws: false,
+                        split: false,
+                    };
+                    return if end { 2 } else { 1 };
+                } else {
+                    self.before(Next::Text);
+                    self.out.push(c);
+                    self.state.prev = Side::Inline;
+                }
+            }
+            Mode::Comment(kept) => {
+                if rest.starts_with("-->") {
+                    if *kept {
+                        self.out.push_str("-->");
+                    }
+                    self.state.mode = Mode::Text;
+                    return 3;
+                } else if *kept {
+                    self.out.push(c);
+                } else {
+                    self.comment.push(c);
+                }
+            }
+            Mode::Tag {
+                name,
+                attrs,
+                quote,
+                ws,
+                ..
+            } => match *quote {
+                Some(q) => {
+                    self.tag.push(c);
+                    if c == q {
+                        *quote = None;
+                        // empty value of a boolean attribute
+                        if self.tag.ends_with("=\"\"") || self.tag.ends_with("=''") {
+                            self.tag.truncate(self.tag.len() - 3);
+                        }
+                    }
+                }
+                None if c.is_whitespace() => {
+                    *attrs = true;
+                    *ws = true;
+                }
+                None if c == '>' => self.end_tag(),
+                None => {
+                    if mem::take(ws) && !matches!(c, '/' | '=') && !self.tag.ends_with('=') {
+                        self.tag.push(' ');
+                    }
+                    if c == '"' || c == '\'' {
+                        *quote = Some(c);
+                    }
+                    if !*attrs && (c.is_alphanumeric() || c == '-' || c == ':' || c == '!') {
+                        name.push(c.to_ascii_lowercase());
+                    } else {
+                        *attrs = true;
+                    }
+                    self.tag.push(c);
+                }
+            },
+            Mode::Raw(name) => {
+                if rest.starts_with("</")
+                    && rest
+                        .get(2..2 + name.len())
+                        .is_some_and(|x| x.eq_ignore_ascii_case(name))
+                {
+                    self.state.mode = Mode::Text;
+                    return 0;
+                }
+                self.out.push(c);
+            }
+            Mode::Off => self.out.push(c),
+        }
+
+        len
+    }
+
+    /// Writes a tag split by a node, it can't be omitted
+    fn split_tag(&mut self) {
+        if let Mode::Tag {
+            name, end, split, ..
+        } = &mut self.state.mode
+        {
+            if !mem::replace(split, true) {
+                let (name, end) = (name.clone(), *end);
+                self.before(if name.is_empty() {
+                    Next::Unknown
+                } else if end {
+                    Next::EndTag(&name)
+                } else {
+                    Next::Start(&name)
+                });
+            }
+        }
+        let tag = mem::take(&mut self.tag);
+        self.out.push_str(&tag);
+    }
+
+    fn end_tag(&mut self) {
+        let Mode::Tag {
+            name, end, split, ..
+        } = mem::replace(&mut self.state.mode, Mode::Text)
+        else {
+            unreachable!()
+        };
+        self.tag.push('>');
+
+        let tag = mem::take(&mut self.tag);
+        if split {
+            self.out.push_str(&tag);
+        } else {
+            self.before(if end {
+                Next::EndTag(&name)
+            } else {
+                Next::Start(&name)
+            });
+            if end && OPTIONAL.contains(&name.as_str()) {
+                self.pending = Some(name.clone());
+            } else if name == "!doctype" {
+                self.out.push_str("<!DOCTYPE");
+                self.out.push_str(&tag[9..]);
+            } else {
+                self.out.push_str(&tag);
+            }
+        }
+
+        self.state.prev = if name.starts_with('!') {
+            Side::Block
+        } else {
+            side(&name)
+        };
+        if !end && RAW.contains(&name.as_str()) {
+            self.state.mode = Mode::Raw(name);
+        } else if !end && VOID.contains(&name.as_str()) {
+            self.state.mode = Mode::Text;
+        }
+    }
+
+    /// Resolves the optional end tag and the whitespace with the next html
+    fn before(&mut self, next: Next) {
+        if let Some(name) = self.pending.take() {
+            if !omit(&name, &next) {
+                self.out.push_str("</");
+                self.out.push_str(&name);
+                self.out.push('>');
+            }
+        }
+        if mem::take(&mut self.ws) && self.state.prev != Side::Block && next.side() != Side::Block {
+            self.out.push(' ');
+            self.state.prev = Side::Inline;
+        }
+    }
+}
+
+/// Start of a tag, returns if it's an end tag
+fn is_tag(s: &str) -> Option<bool> {
+    let mut chars = s.strip_prefix('<')?.chars();
+    match (chars.next(), chars.next()) {
+        (Some('/'), Some(c)) if c.is_ascii_alphabetic() => Some(true),
+        (Some(c), _) if c.is_ascii_alphabetic() || c == '!' || c == '?' => Some(false),
+        _ => None,
+    }
+}
+
+fn merge(a: &State, b: &State) -> State {
+    State {
+        mode: if a.mode == b.mode {
+            a.mode.clone()
+        } else {
+            Mode::Off
+        },
+        prev: if a.prev == b.prev {
+            a.prev
+        } else {
+            Side::Unknown
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yarte_hir::Span;
+
+    const SPAN: Span = Span { lo: 0, hi: 0 };
+
+    fn min(s: &str) -> String {
+        match minify(vec![HIR::Lit(s.into())]).as_slice() {
+            [HIR::Lit(s)] => s.clone(),
+            [] => String::new(),
+            v => panic!("{v:?}"),
+        }
+    }
+
+    #[test]
+    fn test_whitespace() {
+        assert_eq!(
+            min("  <div>\n  <b>a</b>  <i>b</i>  </div>  "),
+            "<div><b>a</b> <i>b</i></div>"
+        );
+        assert_eq!(min("<p>a\n\n  b </p>"), "<p>a b</p>");
+        assert_eq!(
+            min("<a\n  href=\"a  b\"   class = 'c' >x</a>"),
+            "<a href=\"a  b\" class='c'>x</a>"
+        );
+        assert_eq!(min("<br />"), "<br/>");
+    }
+
+    #[test]
+    fn test_raw() {
+        assert_eq!(min("<pre>  a\n  b </pre> "), "<pre>  a\n  b </pre>");
+        assert_eq!(
+            min("<textarea> a  </TEXTAREA>"),
+            "<textarea> a  </TEXTAREA>"
+        );
+        assert_eq!(
+            min("<script> if (a  <b) {} </script>"),
+            "<script> if (a  <b) {} </script>"
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_eq!(min("<p>a<!-- b <p> --> c</p>"), "<p>a c</p>");
+        assert_eq!(min("<!-- a -->\n<div></div>"), "<div></div>");
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(
+            min("<!doctype html>\n<html>\n<head><title>a</title></head><body><ul><li>a</li> <li>b</li></ul></body></html>"),
+            "<!DOCTYPE html><html><head><title>a</title></head><body><ul><li>a<li>b</ul></body></html>"
+        );
+        assert_eq!(
+            min("<audio controls=\"\" class=''></audio>"),
+            "<audio controls class></audio>"
+        );
+        assert_eq!(
+            min("<p><label>a</label>\n  <input> <b>b</b> <i>c</i>\n  <i>d</i></p>"),
+            "<p><label>a</label> <input> <b>b</b> <i>c</i> <i>d</i></p>"
+        );
+    }
+
+    #[test]
+    fn test_optional() {
+        assert_eq!(
+            min("<table>\n  <tr><th>a</th> <th>b</th></tr>\n  <tr><td>1</td><td>2</td></tr>\n</table>"),
+            "<table><tr><th>a<th>b<tr><td>1<td>2</table>"
+        );
+        assert_eq!(
+            min("<dl><dt>a</dt><dd>b</dd></dl><p>c</p> <div>d</div>"),
+            "<dl><dt>a<dd>b</dl><p>c<div>d</div>"
+        );
+        // only before known html
+        assert_eq!(min("<p>a</p> b"), "<p>a</p>b");
+        assert_eq!(min("<p>a</p><span>b</span>"), "<p>a</p><span>b</span>");
+        assert_eq!(min("<ul><li>a</li>"), "<ul><li>a</li>");
+    }
+    #[test]
+    fn test_nodes() {
+        let e: Box<syn::Expr> = Box::new(syn::parse_str("a").unwrap());
+        let v = minify(vec![
+            HIR::Lit("<ul>\n  <li>a </li>\n <li> ".into()),
+            HIR::Expr(e.clone(), SPAN),
+            HIR::Lit(" </li>\n  <!-- ".into()),
+            HIR::Expr(e.clone(), SPAN),
+            HIR::Lit(" -->\n</ul>".into()),
+        ]);
+        assert_eq!(
+            v,
+            vec![
+                HIR::Lit("<ul><li>a<li>".into()),
+                HIR::Expr(e.clone(), SPAN),
+                HIR::Lit("</li><!-- ".into()),
+                HIR::Expr(e, SPAN),
+                HIR::Lit(" --></ul>".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Escape of the expressions by the mode of the template
+//!
+//! Html templates are escaped by its context. The expressions of the other
+//! modes, or of a mode different from the one of the code generator, are
+//! wrapped in its escaper, which every code generator writes as is.
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use yarte_helpers::config::Mode;
+use yarte_hir::{BlockHelper, Each, IfElse, Match, HIR};
+
+use crate::context::{escape_context_text, EscapeError};
+
+/// Escapes the expressions of `v` by `mode`
+///
+/// `is_text` is true when the code generator writes the expressions without escape.
+/// `parent` is the crate that exports `Escaped` and `Escaper`
+pub fn escape_mode(
+    v: Vec<HIR>,
+    mode: Mode,
+    is_text: bool,
+    parent: &str,
+) -> Result<Vec<HIR>, EscapeError> {
+    let parent = format_ident!("{}", parent);
+    let escaper = |e: &str| {
+        let e = format_ident!("{}", e);
+        Some(quote!(#parent::Escaper::#e))
+    };
+    let wrap = |v: Vec<HIR>, escaper: Option<TokenStream>| {
+        Wrap {
+            parent: &parent,
+            escaper,
+        }
+        .nodes(v)
+    };
+
+    match mode {
+        Mode::Html => escape_context_text(v, &parent.to_string(), is_text),
+        Mode::Text if is_text => Ok(v),
+        Mode::Text => Ok(wrap(v, None)),
+        Mode::Json => Ok(wrap(v, escaper("JsonString"))),
+        Mode::Xml => Ok(wrap(v, escaper("Xml"))),
+    }
+}
+
+/// Wraps the expressions in `escaper`, or writes them as safe without escaper
+struct Wrap<'a> {
+    parent: &'a Ident,
+    escaper: Option<TokenStream>,
+}
+
+impl<'a> Wrap<'a> {
+    fn nodes(&self, v: Vec<HIR>) -> Vec<HIR> {
+        v.into_iter().map(|i| self.node(i)).collect()
+    }
+
+    fn node(&self, i: HIR) -> HIR {
+        match i {
+            HIR::Expr(e, span) => match &self.escaper {
+                Some(escaper) => {
+                    let parent = self.parent;
+                    HIR::Expr(
+                        Box::new(syn::parse2(quote!(#parent::Escaped(&(#e), #escaper))).unwrap()),
+                        span,
+                    )
+                }
+                None => HIR::Safe(e),
+            },
+            HIR::Each(e) => {
+                let Each {
+                    args,
+                    body,
+                    expr,
+                    els,
+                } = *e;
+                HIR::Each(Box::new(Each {
+                    args,
+                    body: self.nodes(body),
+                    expr,
+                    els: els.map(|els| self.nodes(els)),
+                }))
+            }
+            HIR::IfElse(e) => {
+                let IfElse { ifs, if_else, els } = *e;
+                HIR::IfElse(Box::new(IfElse {
+                    ifs: (ifs.0, self.nodes(ifs.1)),
+                    if_else: if_else
+                        .into_iter()
+                        .map(|(cond, body)| (cond, self.nodes(body)))
+                        .collect(),
+                    els: els.map(|els| self.nodes(els)),
+                }))
+            }
+            HIR::Match(e) => {
+                let Match { expr, arms } = *e;
+                HIR::Match(Box::new(Match {
+                    expr,
+                    arms: arms
+                        .into_iter()
+                        .map(|(pat, guard, body)| (pat, guard, self.nodes(body)))
+                        .collect(),
+                }))
+            }
+            HIR::BlockHelper(e) => {
+                let BlockHelper { path, args, body } = *e;
+                HIR::BlockHelper(Box::new(BlockHelper {
+                    path,
+                    args,
+                    body: self.nodes(body),
+                }))
+            }
+            i => i,
+        }
+    }
+}
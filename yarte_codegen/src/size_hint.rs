@@ -0,0 +1,209 @@
+//! Estimation of the render length from the HIR
+use yarte_hir::{BlockHelper, Each, IfElse, Match, HIR};
+
+/// Iterations estimated for a loop
+const EACH_ITERATIONS: usize = 8;
+/// Length estimated for an expression of unknown type
+const UNKNOWN_EXPR: usize = 16;
+
+/// Length of a render
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SizeHint {
+    /// Bytes of the literals written in every render
+    pub fixed: usize,
+    /// Estimation of the expressions, loops and conditionals
+    pub dynamic: usize,
+}
+
+impl SizeHint {
+    pub fn total(self) -> usize {
+        self.fixed + self.dynamic
+    }
+}
+
+/// Estimates the length of `ir`, `fields` of the template struct give the bounds
+/// of numbers, booleans and chars
+pub fn size_hint(ir: &[HIR], fields: &[syn::Field]) -> SizeHint {
+    let mut hint = SizeHint::default();
+    for i in ir {
+        match i {
+            HIR::Lit(a) | HIR::Raw(a) => hint.fixed += a.len(),
+            i => hint.dynamic += node(i, fields),
+        }
+    }
+    hint
+}
+
+fn total(ir: &[HIR], fields: &[syn::Field]) -> usize {
+    size_hint(ir, fields).total()
+}
+
+fn node(i: &HIR, fields: &[syn::Field]) -> usize {
+    match i {
+        HIR::Lit(a) | HIR::Raw(a) => a.len(),
+        HIR::Expr(e, _) | HIR::Safe(e) => expr(e, fields),
+        HIR::Each(e) => {
+            let Each { body, els, .. } = &**e;
+            let body = total(body, fields) * EACH_ITERATIONS;
+            els.as_ref()
+                .map_or(body, |els| body.max(total(els, fields)))
+        }
+        HIR::IfElse(e) => {
+            let IfElse { ifs, if_else, els } = &**e;
+            Some(&ifs.1)
+                .into_iter()
+                .chain(if_else.iter().map(|(_, body)| body))
+                .chain(els)
+                .map(|body| total(body, fields))
+                .max()
+                .unwrap_or(0)
+        }
+        HIR::Match(e) => {
+            let Match { arms, .. } = &**e;
+            arms.iter()
+                .map(|(_, _, body)| total(body, fields))
+                .max()
+                .unwrap_or(0)
+        }
+        HIR::BlockHelper(e) => {
+            let BlockHelper { body, .. } = &**e;
+            total(body, fields)
+        }
+        HIR::Local(_) | HIR::Flush => 0,
+    }
+}
+
+fn expr(e: &syn::Expr, fields: &[syn::Field]) -> usize {
+    use syn::Expr::*;
+    match e {
+        Lit(syn::ExprLit { lit, .. }) => match lit {
+            syn::Lit::Str(s) => s.value().len(),
+            syn::Lit::Char(_) => 4,
+            syn::Lit::Bool(_) => 5,
+            syn::Lit::Int(i) => i.base10_digits().len(),
+            syn::Lit::Float(f) => f.base10_digits().len(),
+            _ => UNKNOWN_EXPR,
+        },
+        Cast(syn::ExprCast { ty, .. }) => ty_len(ty),
+        Paren(syn::ExprParen { expr: e, .. })
+        | Group(syn::ExprGroup { expr: e, .. })
+        | Reference(syn::ExprReference { expr: e, .. }) => expr(e, fields),
+        Field(syn::ExprField {
+            base,
+            member: syn::Member::Named(name),
+            ..
+        }) if is_self(base) => {
+            // Fields and expressions can have spans of different proc_macro2 backends
+            let name = name.to_string();
+            fields
+                .iter()
+                .find(|f| f.ident.as_ref().is_some_and(|i| *i == name))
+                .map_or(UNKNOWN_EXPR, |f| ty_len(&f.ty))
+        }
+        _ => UNKNOWN_EXPR,
+    }
+}
+
+fn is_self(e: &syn::Expr) -> bool {
+    matches!(e, syn::Expr::Path(syn::ExprPath { path, .. }) if path.is_ident("self"))
+}
+
+/// Max length of the primitive types
+fn ty_len(ty: &syn::Type) -> usize {
+    match ty {
+        syn::Type::Reference(syn::TypeReference { elem, .. })
+        | syn::Type::Paren(syn::TypeParen { elem, .. })
+        | syn::Type::Group(syn::TypeGroup { elem, .. }) => ty_len(elem),
+        syn::Type::Path(syn::TypePath { qself: None, path }) => {
+            match path.get_ident().map(|x| x.to_string()).as_deref() {
+                Some("u8") => 3,
+                Some("i8") => 4,
+                Some("u16") => 5,
+                Some("i16") => 6,
+                Some("u32") => 10,
+                Some("i32") => 11,
+                Some("u64" | "i64" | "usize" | "isize") => 20,
+                Some("u128") => 39,
+                Some("i128") => 40,
+                Some("f32") => 16,
+                Some("f64") => 24,
+                Some("bool") => 5,
+                Some("char") => 4,
+                _ => UNKNOWN_EXPR,
+            }
+        }
+        _ => UNKNOWN_EXPR,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yarte_hir::Span;
+
+    const SPAN: Span = Span { lo: 0, hi: 0 };
+    use syn::parse_str;
+
+    fn fields() -> Vec<syn::Field> {
+        let s: syn::ItemStruct =
+            parse_str("struct A { a: u8, b: &'a f64, c: String, d: bool }").unwrap();
+        s.fields.into_iter().collect()
+    }
+
+    fn e(s: &str) -> Box<syn::Expr> {
+        Box::new(parse_str(s).unwrap())
+    }
+
+    #[test]
+    fn test_lit() {
+        let ir = vec![HIR::Lit("<p>".into()), HIR::Raw("ab".into())];
+        assert_eq!(
+            size_hint(&ir, &[]),
+            SizeHint {
+                fixed: 5,
+                dynamic: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_expr() {
+        let ir = vec![
+            HIR::Expr(e("self.a"), SPAN),
+            HIR::Safe(e("self.b")),
+            HIR::Expr(e("self.c"), SPAN),
+            HIR::Expr(e("(self.d)"), SPAN),
+            HIR::Expr(e("n as i32"), SPAN),
+        ];
+        assert_eq!(
+            size_hint(&ir, &fields()),
+            SizeHint {
+                fixed: 0,
+                dynamic: 3 + 24 + UNKNOWN_EXPR + 5 + 11
+            }
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let each = HIR::Each(Box::new(Each {
+            args: *e("self.items"),
+            body: vec![HIR::Lit("<li>".into()), HIR::Expr(e("self.a"), SPAN)],
+            expr: *e("_"),
+            els: None,
+        }));
+        let if_else = HIR::IfElse(Box::new(IfElse {
+            ifs: (*e("self.d"), vec![HIR::Lit("yes".into())]),
+            if_else: vec![],
+            els: Some(vec![HIR::Lit("no, no".into())]),
+        }));
+        let ir = vec![HIR::Lit("<ul>".into()), each, if_else];
+        assert_eq!(
+            size_hint(&ir, &fields()),
+            SizeHint {
+                fixed: 4,
+                dynamic: 7 * EACH_ITERATIONS + 6
+            }
+        );
+    }
+}
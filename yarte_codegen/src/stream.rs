@@ -0,0 +1,97 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use yarte_hir::{Struct, HIR};
+
+use crate::CodeGen;
+
+/// Implements `TemplateStreamTrait` over a bytes code generator
+///
+/// The render runs in an `async` block and `HIR::Flush` sends the buffer.
+pub struct StreamCodeGen<'a, T: CodeGen> {
+    codegen: T,
+    s: &'a Struct<'a>,
+    parent: Ident,
+    buf: Ident,
+}
+
+impl<'a, T: CodeGen> StreamCodeGen<'a, T> {
+    pub fn new<'n>(
+        codegen: T,
+        s: &'n Struct,
+        buf: Ident,
+        parent: &'static str,
+    ) -> StreamCodeGen<'n, T> {
+        StreamCodeGen {
+            codegen,
+            s,
+            parent: format_ident!("{}", parent),
+            buf,
+        }
+    }
+}
+
+impl<'a, T: CodeGen> StreamCodeGen<'a, T> {
+    /// Stream of the chunks of `v`, sent at each `HIR::Flush` and at the end
+    fn stream(&mut self, v: Vec<HIR>) -> TokenStream {
+        let nodes = self.codegen.gen(v);
+        let parent = &self.parent;
+        let buf = &self.buf;
+        quote! {
+            #parent::RenderStream::new(move |__yarte_sink| async move {
+                use #parent::*;
+                let mut #buf = B::with_capacity(capacity);
+                macro_rules! buf_ref {
+                    ($b:expr) => { &mut $b };
+                }
+                macro_rules! __yarte_flush {
+                    () => {
+                        if !#parent::Buffer::is_empty(&#buf) {
+                            let __yarte_chunk = std::mem::replace(
+                                &mut #buf,
+                                B::with_capacity(capacity),
+                            );
+                            __yarte_sink.send(__yarte_chunk.freeze()).await;
+                        }
+                    };
+                }
+                #nodes
+                __yarte_flush!();
+            })
+        }
+    }
+}
+
+impl<'a, T: CodeGen> CodeGen for StreamCodeGen<'a, T> {
+    fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
+        let stream = self.stream(v);
+        let parent = &self.parent;
+        self.s.implement_head(
+            quote!(#parent::TemplateStreamTrait),
+            &quote!(
+                fn stream<B: #parent::Buffer>(
+                    &self,
+                    capacity: usize,
+                ) -> impl #parent::Stream<Item = B::Freeze> {
+                    #stream
+                }
+            ),
+        )
+    }
+
+    fn gen_fragment(&mut self, name: &str, v: Vec<HIR>) -> TokenStream {
+        let stream = self.stream(v);
+        let parent = &self.parent;
+        let method = format_ident!("fragment_{}", name);
+        let doc = format!("Streams the fragment `{name}`");
+        self.s.implement(&quote!(
+            #[doc = #doc]
+            pub fn #method<'a, B: #parent::Buffer + 'a>(
+                &'a self,
+                capacity: usize,
+            ) -> impl #parent::Stream<Item = B::Freeze> + 'a {
+                #stream
+            }
+        ))
+    }
+}
@@ -1,12 +1,14 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use super::{CodeGen, EachCodeGen, IfElseCodeGen, HIR};
+use super::{BlockHelperCodeGen, CodeGen, EachCodeGen, IfElseCodeGen, MatchCodeGen, HIR};
 
 pub struct TextCodeGen;
 
 impl EachCodeGen for TextCodeGen {}
 impl IfElseCodeGen for TextCodeGen {}
+impl MatchCodeGen for TextCodeGen {}
+impl BlockHelperCodeGen for TextCodeGen {}
 
 impl CodeGen for TextCodeGen {
     fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
@@ -15,10 +17,13 @@ impl CodeGen for TextCodeGen {
             use HIR::*;
             tokens.extend(match i {
                 Local(a) => quote!(#a),
-                Lit(a) => quote!(_fmt.write_str(#a)?;),
-                Safe(a) | Expr(a) => quote!((&(#a)).fmt(_fmt)?;),
+                Lit(a) | Raw(a) => quote!(_fmt.write_str(#a)?;),
+                Safe(a) | Expr(a, _) => quote!((&(#a)).fmt(_fmt)?;),
                 Each(a) => self.gen_each(*a),
                 IfElse(a) => self.gen_if_else(*a),
+                Match(a) => self.gen_match(*a),
+                BlockHelper(a) => self.gen_block_helper(*a),
+                Flush => quote!(),
             });
         }
         tokens
@@ -5,6 +5,7 @@ use quote::{format_ident, quote};
 use syn::{parse2, Expr, Ident};
 
 use yarte_dom::dom::{Each, ExprId, VarId};
+use yarte_hir::HIR;
 
 use super::{
     component::get_component,
@@ -13,6 +14,21 @@ use super::{
     BlackBox, WASMCodeGen,
 };
 
+/// Arguments of the first `{{#each}}` with `{{else}}` in `ir`, not supported yet
+pub(super) fn each_else(ir: &[HIR]) -> Option<&Expr> {
+    ir.iter().find_map(|i| match i {
+        HIR::Each(e) if e.els.is_some() => Some(&e.args),
+        HIR::Each(e) => each_else(&e.body),
+        HIR::IfElse(e) => iter::once(&e.ifs.1)
+            .chain(e.if_else.iter().map(|(_, body)| body))
+            .chain(&e.els)
+            .find_map(|body| each_else(body)),
+        HIR::Match(e) => e.arms.iter().find_map(|(_, _, body)| each_else(body)),
+        HIR::BlockHelper(e) => each_else(&e.body),
+        _ => None,
+    })
+}
+
 impl<'a> WASMCodeGen<'a> {
     #[inline]
     pub(super) fn gen_each(
@@ -23,11 +39,14 @@ impl<'a> WASMCodeGen<'a> {
             body,
             expr,
             var,
+            els,
         }: Each,
         fragment: bool,
         last: bool,
         insert_point: &[InsertPath],
     ) {
+        debug_assert!(els.is_none(), "each else is checked by `each_else`");
+
         // Get current state
         let current_bb = self.current_bb();
 
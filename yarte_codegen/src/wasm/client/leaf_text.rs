@@ -46,7 +46,7 @@ impl<'a> LeafTextBuilder<'a> {
                     .push_str(&t.replace('{', "{{").replace('}', "}}")),
                 Node::Expr(e) => match e {
                     // TODO
-                    Expression::Safe(id, e) | Expression::Unsafe(id, e) => {
+                    Expression::Safe(id, e) | Expression::Unsafe(id, e, _) => {
                         let vars = self.solver.expr_inner_var(&id);
                         self.buff.extend(vars);
                         self.buff_expr.push_str("{}");
@@ -509,7 +509,7 @@ impl<'a> WASMCodeGen<'a> {
                         }
                     }
                     Expression::Local(..) => todo!("resolve local expression"),
-                    Expression::Safe(id, _) | Expression::Unsafe(id, _) => unreachable!(),
+                    Expression::Safe(id, _) | Expression::Unsafe(id, ..) => unreachable!(),
                 },
                 Node::Elem(Element::Text(_)) => (),
             }
@@ -623,6 +623,10 @@ impl<'a> WASMCodeGen<'a> {
 
 impl<'a> CodeGen for WASMCodeGen<'a> {
     fn gen(&mut self, ir: Vec<HIR>) -> TokenStream {
+        if let Some(args) = each::each_else(&ir) {
+            return syn::Error::new_spanned(args, "each else is not supported in wasm-app")
+                .to_compile_error();
+        }
         let build = self.init(ir.into());
 
         let mut cur = self.stack.pop();
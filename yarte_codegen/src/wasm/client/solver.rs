@@ -2,7 +2,7 @@ use std::collections::{BTreeSet, HashMap};
 
 use yarte_dom::dom::{ExprId, TreeMap, Var, VarId, VarInner, VarMap};
 
-use crate::client::utils::get_self_id;
+use super::utils::get_self_id;
 
 #[derive(Default, Debug)]
 pub struct Solver {
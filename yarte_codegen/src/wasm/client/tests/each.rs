@@ -169,3 +169,21 @@ impl std::default::Default for Test {
     let c = tokens(der, false);
     assert_eq!(c, expected)
 }
+
+#[test]
+fn test_each_else() {
+    let src = r#"
+    <!doctype html><html><body>
+    <ul>{{#each fortunes}}<li>{{ id }}</li>{{else}}<li>none</li>{{/each}}</ul>
+    </body></html>"#;
+    let der = quote! {
+        #[derive(App)]
+        #[template(src = #src)]
+        #[msg(pub enum Msg {})]
+        pub struct Test {
+            black_box: <Self as App>::BlackBox,
+        }
+    };
+
+    assert!(tokens(der, false).contains("each else is not supported in wasm-app"));
+}
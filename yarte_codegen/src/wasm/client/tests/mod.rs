@@ -1,15 +1,12 @@
-use std::collections::BTreeMap;
+use std::{collections::HashMap, rc::Rc};
 
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::parse2;
 
 use yarte_helpers::{config::Config, logger::log};
-use yarte_hir::{generate, visit_derive};
-use yarte_parser::{
-    emitter, parse,
-    source_map::{clean, get_cursor},
-};
+use yarte_hir::{generate, resolve_imports, visit_derive, HIROptions};
+use yarte_parser::{emitter, source_map::clean, OwnParsed};
 
 use crate::CodeGen;
 
@@ -20,17 +17,20 @@ mod tree_diff;
 use super::WASMCodeGen;
 
 fn tokens(i: TokenStream, wlog: bool) -> String {
-    let config = &Config::new("");
+    let config = &Config::new("").unwrap();
     let der = parse2(i).unwrap();
-    let s = visit_derive(&der, config).unwrap();
-    let mut src = BTreeMap::new();
-    src.insert(s.path.clone(), s.src.clone());
-    let sources = parse(get_cursor(&s.path, &s.src)).unwrap();
-    let mut ctx = BTreeMap::new();
-    ctx.insert(&s.path, sources);
-
-    let ir = generate(config, &s, &ctx, Default::default())
-        .unwrap_or_else(|e| emitter(&src, config, e.into_iter()));
+    let (s, src) = visit_derive(&der, config).unwrap();
+    let mut parsed: OwnParsed = HashMap::new();
+    resolve_imports(src, Rc::clone(&s.path), config, &mut parsed)
+        .unwrap_or_else(|e| panic!("{}", emitter(&parsed, config, e)));
+
+    // TODO: loop variables in the wasm application
+    let opt = HIROptions {
+        loop_var: false,
+        ..Default::default()
+    };
+    let ir = generate(config, &s, &parsed, opt)
+        .unwrap_or_else(|e| panic!("{}", emitter(&parsed, config, e)));
     clean();
 
     let res = WASMCodeGen::new(&s).gen(ir).to_string();
@@ -12,22 +12,35 @@ use quote::{format_ident, quote};
 use syn::parse::{ParseBuffer, ParseStream};
 use syn::spanned::Spanned;
 
-use yarte_codegen::{CodeGen, FmtCodeGen, HTMLCodeGen, TextCodeGen};
+use yarte_codegen::{escape_mode, CodeGen, EscapeError, FmtCodeGen, HTMLCodeGen, TextCodeGen};
 use yarte_helpers::{
     config::{get_source, read_config_file, Config, PrintConfig},
     logger::log,
 };
-use yarte_hir::{generate, resolve_imports, visit_derive, HIROptions, Print, Struct};
-use yarte_parser::{emitter, parse, source_map, OwnParsed, Partial};
+use yarte_hir::{
+    generate, generate_with_fragments, resolve_imports, visit_derive, HIROptions, Print, Struct,
+};
+use yarte_parser::{emitter, parse, source_map, ErrorMessage, OwnParsed, Partial};
 
 #[cfg(feature = "json")]
 mod ser_json;
 
 macro_rules! build {
     ($i:ident, $codegen:ident, $opt:expr) => {{
-        let config_toml: &str = &read_config_file();
-        let config = &Config::new(config_toml);
-        let (struct_, source) = match visit_derive($i, config) {
+        let i: &syn::DeriveInput = $i;
+        let config = &match read_config_file().and_then(|s| Config::new(&s)) {
+            Ok(config) => config,
+            // at the template attribute, since the config is read for it
+            Err(e) => {
+                return match i.attrs.iter().find(|a| a.path.is_ident("template")) {
+                    Some(attr) => syn::Error::new_spanned(attr, e),
+                    None => syn::Error::new(proc_macro2::Span::call_site(), e),
+                }
+                .to_compile_error()
+                .into()
+            }
+        };
+        let (struct_, source) = match visit_derive(i, config) {
             Ok(s) => s,
             Err(ts) => return ts.into(),
         };
@@ -41,7 +54,10 @@ macro_rules! build {
 /// Implements TemplateTrait without html escape functionality
 pub fn template(input: TokenStream) -> TokenStream {
     fn get_codegen<'a>(s: &'a Struct) -> Box<dyn CodeGen + 'a> {
-        Box::new(FmtCodeGen::new(TextCodeGen, s, "yarte"))
+        let codegen = FmtCodeGen::new(TextCodeGen, s, "yarte");
+        #[cfg(feature = "hot-reload")]
+        let codegen = codegen.hot_reload(true);
+        Box::new(codegen)
     }
 
     let i = &syn::parse(input).unwrap();
@@ -60,7 +76,50 @@ pub fn template(input: TokenStream) -> TokenStream {
 /// Implements TemplateTrait with html escape functionality
 pub fn template_html(input: TokenStream) -> TokenStream {
     fn get_codegen<'a>(s: &'a Struct) -> Box<dyn CodeGen + 'a> {
-        Box::new(FmtCodeGen::new(HTMLCodeGen, s, "yarte"))
+        let codegen = FmtCodeGen::new(HTMLCodeGen, s, "yarte");
+        #[cfg(feature = "hot-reload")]
+        let codegen = codegen.hot_reload(false);
+        Box::new(codegen)
+    }
+    let i = &syn::parse(input).unwrap();
+    build!(i, get_codegen, Default::default()).into()
+}
+
+#[proc_macro_derive(TemplateMin, attributes(template))]
+#[cfg(feature = "html-min")]
+/// Implements TemplateTrait with html escape functionality and minified html
+pub fn template_html_min(input: TokenStream) -> TokenStream {
+    fn get_codegen<'a>(s: &'a Struct) -> Box<dyn CodeGen + 'a> {
+        Box::new(FmtCodeGen::new(yarte_codegen::HTMLMinCodeGen, s, "yarte"))
+    }
+    let i = &syn::parse(input).unwrap();
+    build!(i, get_codegen, Default::default()).into()
+}
+
+#[proc_macro_derive(TemplateTryText, attributes(template))]
+/// Implements TemplateTryTrait without html escape functionality
+pub fn template_try(input: TokenStream) -> TokenStream {
+    fn get_codegen<'a>(s: &'a Struct) -> Box<dyn CodeGen + 'a> {
+        Box::new(yarte_codegen::TryFmtCodeGen::new(TextCodeGen, s, "yarte"))
+    }
+
+    let i = &syn::parse(input).unwrap();
+    build!(
+        i,
+        get_codegen,
+        HIROptions {
+            is_text: true,
+            ..Default::default()
+        }
+    )
+    .into()
+}
+
+#[proc_macro_derive(TemplateTry, attributes(template))]
+/// Implements TemplateTryTrait with html escape functionality
+pub fn template_html_try(input: TokenStream) -> TokenStream {
+    fn get_codegen<'a>(s: &'a Struct) -> Box<dyn CodeGen + 'a> {
+        Box::new(yarte_codegen::TryFmtCodeGen::new(HTMLCodeGen, s, "yarte"))
     }
     let i = &syn::parse(input).unwrap();
     build!(i, get_codegen, Default::default()).into()
@@ -115,6 +174,245 @@ pub fn template_html_bytes(input: TokenStream) -> TokenStream {
     build!(i, get_codegen, Default::default()).into()
 }
 
+#[proc_macro_derive(TemplateBytesMin, attributes(template))]
+#[cfg(all(feature = "bytes-buf", feature = "html-min"))]
+/// Implements TemplateBytesTrait with html escape functionality and minified html
+pub fn template_html_min_bytes(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        Box::new(yarte_codegen::BytesCodeGen::new(
+            yarte_codegen::HTMLMinBytesCodeGen::new(&buf),
+            s,
+            buf_i,
+            PARENT,
+        ))
+    };
+    let i = &syn::parse(input).unwrap();
+    build!(i, get_codegen, Default::default()).into()
+}
+
+#[proc_macro_derive(TemplateBytesTryText, attributes(template))]
+#[cfg(feature = "bytes-buf")]
+/// Implements TemplateBytesTryTrait without html escape functionality
+pub fn template_bytes_try(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        Box::new(yarte_codegen::TryBytesCodeGen::new(
+            yarte_codegen::TextBytesCodeGen::new(&buf),
+            s,
+            buf_i,
+            PARENT,
+        ))
+    };
+
+    let i = &syn::parse(input).unwrap();
+    build!(
+        i,
+        get_codegen,
+        HIROptions {
+            is_text: true,
+            ..Default::default()
+        }
+    )
+    .into()
+}
+
+#[proc_macro_derive(TemplateBytesTry, attributes(template))]
+#[cfg(feature = "bytes-buf")]
+/// Implements TemplateBytesTryTrait with html escape functionality
+pub fn template_html_bytes_try(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        Box::new(yarte_codegen::TryBytesCodeGen::new(
+            yarte_codegen::HTMLBytesCodeGen::new(&buf),
+            s,
+            buf_i,
+            PARENT,
+        ))
+    };
+    let i = &syn::parse(input).unwrap();
+    build!(i, get_codegen, Default::default()).into()
+}
+
+#[proc_macro_derive(TemplateIoText, attributes(template))]
+#[cfg(feature = "bytes-buf")]
+/// Implements TemplateIoTrait without html escape functionality
+pub fn template_io(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        Box::new(yarte_codegen::IoCodeGen::new(
+            yarte_codegen::TextBytesCodeGen::new(&buf),
+            s,
+            buf_i,
+            PARENT,
+        ))
+    };
+
+    let i = &syn::parse(input).unwrap();
+    build!(
+        i,
+        get_codegen,
+        HIROptions {
+            is_text: true,
+            ..Default::default()
+        }
+    )
+    .into()
+}
+
+#[proc_macro_derive(TemplateIo, attributes(template))]
+#[cfg(feature = "bytes-buf")]
+/// Implements TemplateIoTrait with html escape functionality
+pub fn template_html_io(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        Box::new(yarte_codegen::IoCodeGen::new(
+            yarte_codegen::HTMLBytesCodeGen::new(&buf),
+            s,
+            buf_i,
+            PARENT,
+        ))
+    };
+    let i = &syn::parse(input).unwrap();
+    build!(i, get_codegen, Default::default()).into()
+}
+
+#[proc_macro_derive(TemplateDualText, attributes(template))]
+#[cfg(feature = "bytes-buf")]
+/// Implements TemplateTrait and TemplateBytesTrait without html escape functionality
+pub fn template_dual(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        let codegen = FmtCodeGen::new(TextCodeGen, s, PARENT);
+        #[cfg(feature = "hot-reload")]
+        let codegen = codegen.hot_reload(true);
+        Box::new(yarte_codegen::DualCodeGen::new(
+            codegen,
+            yarte_codegen::BytesCodeGen::new(
+                yarte_codegen::TextBytesCodeGen::new(&buf),
+                s,
+                buf_i,
+                PARENT,
+            )
+            .fragment_prefix("fragment_bytes"),
+        ))
+    };
+
+    let i = &syn::parse(input).unwrap();
+    build!(
+        i,
+        get_codegen,
+        HIROptions {
+            is_text: true,
+            ..Default::default()
+        }
+    )
+    .into()
+}
+
+#[proc_macro_derive(TemplateDual, attributes(template))]
+#[cfg(feature = "bytes-buf")]
+/// Implements TemplateTrait and TemplateBytesTrait with html escape functionality
+pub fn template_html_dual(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        let codegen = FmtCodeGen::new(HTMLCodeGen, s, PARENT);
+        #[cfg(feature = "hot-reload")]
+        let codegen = codegen.hot_reload(false);
+        Box::new(yarte_codegen::DualCodeGen::new(
+            codegen,
+            yarte_codegen::BytesCodeGen::new(
+                yarte_codegen::HTMLBytesCodeGen::new(&buf),
+                s,
+                buf_i,
+                PARENT,
+            )
+            .fragment_prefix("fragment_bytes"),
+        ))
+    };
+    let i = &syn::parse(input).unwrap();
+    build!(i, get_codegen, Default::default()).into()
+}
+
+#[proc_macro_derive(TemplateStreamText, attributes(template))]
+#[cfg(feature = "stream")]
+/// Implements TemplateStreamTrait without html escape functionality
+pub fn template_stream(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        Box::new(yarte_codegen::StreamCodeGen::new(
+            yarte_codegen::TextBytesCodeGen::new(&buf),
+            s,
+            buf_i,
+            PARENT,
+        ))
+    };
+
+    let i = &syn::parse(input).unwrap();
+    build!(
+        i,
+        get_codegen,
+        HIROptions {
+            is_text: true,
+            stream: true,
+            ..Default::default()
+        }
+    )
+    .into()
+}
+
+#[proc_macro_derive(TemplateStream, attributes(template))]
+#[cfg(feature = "stream")]
+/// Implements TemplateStreamTrait with html escape functionality
+pub fn template_html_stream(input: TokenStream) -> TokenStream {
+    const PARENT: &str = "yarte";
+
+    let buf_i = format_ident!("bytes_mut");
+    let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+    let get_codegen = |s| {
+        Box::new(yarte_codegen::StreamCodeGen::new(
+            yarte_codegen::HTMLBytesCodeGen::new(&buf),
+            s,
+            buf_i,
+            PARENT,
+        ))
+    };
+    let i = &syn::parse(input).unwrap();
+    build!(
+        i,
+        get_codegen,
+        HIROptions {
+            stream: true,
+            ..Default::default()
+        }
+    )
+    .into()
+}
+
 #[proc_macro_derive(Serialize)]
 #[cfg(feature = "json")]
 pub fn serialize_json(i: TokenStream) -> TokenStream {
@@ -215,6 +513,8 @@ pub fn ywrite(i: TokenStream) -> TokenStream {
             resolve_to_self: false,
             is_text: true,
             parent: PARENT,
+            stream: false,
+            loop_var: true,
         }
     )
     .into()
@@ -253,6 +553,17 @@ impl syn::parse::Parse for WriteArg {
     }
 }
 
+/// Errors of the template, the source map is cleaned after its use
+fn emit<I, T>(parsed: &OwnParsed, config: &Config, errors: I) -> proc_macro2::TokenStream
+where
+    I: IntoIterator<Item = ErrorMessage<T>>,
+    T: std::fmt::Display + std::fmt::Debug,
+{
+    let tokens = emitter(parsed, config, errors);
+    source_map::clean();
+    tokens
+}
+
 fn sources_to_tokens<'a>(
     src: String,
     config: &Config,
@@ -261,8 +572,9 @@ fn sources_to_tokens<'a>(
     opt: HIROptions,
 ) -> proc_macro2::TokenStream {
     let mut parsed: OwnParsed = HashMap::new();
-    resolve_imports(src, Rc::clone(&s.path), config, &mut parsed)
-        .unwrap_or_else(|e| emitter(&parsed, config, e));
+    if let Err(e) = resolve_imports(src, Rc::clone(&s.path), config, &mut parsed) {
+        return emit(&parsed, config, e);
+    }
 
     if cfg!(debug_assertions) && config.print_override == PrintConfig::Ast
         || config.print_override == PrintConfig::All
@@ -272,11 +584,47 @@ fn sources_to_tokens<'a>(
         eprintln!("{parsed:?}\n");
     }
 
-    let hir = generate(config, s, &parsed, opt).unwrap_or_else(|e| emitter(&parsed, config, e));
+    let is_text = opt.is_text;
+    let (hir, fragments) = match generate_with_fragments(config, s, &parsed, opt) {
+        Ok(x) => x,
+        Err(e) => return emit(&parsed, config, e),
+    };
+
+    let mode = s.resolve_mode(is_text);
+    let escape = |hir| match escape_mode(hir, mode, is_text, "yarte") {
+        Ok(hir) => Ok(hir),
+        Err(EscapeError {
+            message,
+            span: Some(span),
+        }) => Err(emitter(
+            &parsed,
+            config,
+            Some(ErrorMessage { message, span }),
+        )),
+        Err(EscapeError { message, .. }) => {
+            Err(syn::Error::new(proc_macro2::Span::call_site(), message).to_compile_error())
+        }
+    };
+    let hir = escape(hir);
+    let fragments = fragments
+        .into_iter()
+        .map(|(name, hir)| Ok((name, escape(hir)?)))
+        .collect::<Result<Vec<_>, _>>();
     // when multiple templates
     source_map::clean();
 
-    let tokens = codegen.gen(hir);
+    let mut tokens = match hir {
+        Ok(hir) => codegen.gen(hir),
+        Err(e) => return e,
+    };
+    match fragments {
+        Ok(fragments) => {
+            for (name, hir) in fragments {
+                tokens.extend(codegen.gen_fragment(&name, hir));
+            }
+        }
+        Err(e) => return e,
+    }
 
     if cfg!(debug_assertions) && config.print_override == PrintConfig::Code
         || config.print_override == PrintConfig::All
@@ -0,0 +1,747 @@
+//! DOM representation of a html template
+//!
+//! Every expression gets an [`ExprId`], in document order, and its
+//! variables are registered in the [`TreeMap`] so the wasm client knows
+//! which nodes depend on which fields.
+use std::collections::{BTreeMap, BTreeSet};
+
+use quote::quote;
+use syn::visit::{self, Visit};
+
+use yarte_helpers::calculate_hash;
+use yarte_hir::{Each as HEach, IfElse as HIfElse, Span, HIR};
+
+use crate::tokenizer::{Token, Tokenizer};
+
+pub type Document = Vec<Node>;
+pub type ExprId = usize;
+pub type VarId = u64;
+
+/// Expression -> Inner variables
+pub type TreeMap = BTreeMap<ExprId, BTreeSet<VarId>>;
+/// Variable -> Variable details
+pub type VarMap = BTreeMap<VarId, Var>;
+
+/// Elements without content nor end tag
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements closing an open `<p>`
+const P_CLOSERS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+#[derive(Debug, PartialEq)]
+pub struct DOM {
+    pub doctype: Option<String>,
+    pub doc: Document,
+    pub tree_map: TreeMap,
+    pub var_map: VarMap,
+}
+
+impl DOM {
+    pub fn new(ir: Vec<HIR>) -> Result<DOM, String> {
+        let mut builder = DOMBuilder::default();
+        let doc = builder.document(ir, true)?;
+        Ok(DOM {
+            doctype: builder.doctype,
+            doc,
+            tree_map: builder.tree_map,
+            var_map: builder.var_map,
+        })
+    }
+}
+
+impl From<Vec<HIR>> for DOM {
+    fn from(ir: Vec<HIR>) -> Self {
+        DOM::new(ir).unwrap_or_else(|e| panic!("html: {e}"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Var {
+    /// Field of `self` or of an each key
+    This(VarInner),
+    /// Variable declared in a local expression
+    Local(ExprId, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarInner {
+    pub base: VarId,
+    pub ident: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespace {
+    Html,
+    Svg,
+    MathMl,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Elem(Element),
+    Expr(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    Node {
+        name: (Namespace, ExprOrText),
+        attrs: Vec<Attribute>,
+        children: Document,
+    },
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub name: ExprOrText,
+    pub value: Vec<ExprOrText>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprOrText {
+    Text(String),
+    Expr(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Unsafe(ExprId, Box<syn::Expr>, Span),
+    Safe(ExprId, Box<syn::Expr>),
+    Each(ExprId, Box<Each>),
+    IfElse(ExprId, Box<IfElse>),
+    Local(ExprId, VarId, Box<syn::Local>),
+}
+
+/// for expr in args { body }
+#[derive(Debug, Clone, PartialEq)]
+pub struct Each {
+    pub args: syn::Expr,
+    pub body: Document,
+    pub expr: syn::Expr,
+    /// Key and index variables
+    pub var: (VarId, Option<VarId>),
+    pub els: Option<Document>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IfElse {
+    pub ifs: IfBlock,
+    pub if_else: Vec<IfBlock>,
+    pub els: Option<Document>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IfBlock {
+    pub expr: syn::Expr,
+    pub block: Document,
+}
+
+fn var_id<T: quote::ToTokens>(t: &T) -> VarId {
+    calculate_hash(&quote!(#t).to_string())
+}
+
+/// Identifier in scope
+enum Scoped {
+    /// Each key or index
+    Var(VarId),
+    /// Local variable with the variables of its initializer
+    Local(BTreeSet<VarId>),
+}
+
+#[derive(Default)]
+struct DOMBuilder {
+    count: ExprId,
+    doctype: Option<String>,
+    scope: Vec<(String, Scoped)>,
+    tree_map: TreeMap,
+    var_map: VarMap,
+}
+
+impl DOMBuilder {
+    fn document(&mut self, ir: Vec<HIR>, root: bool) -> Result<Document, String> {
+        let scope = self.scope.len();
+        let mut tokenizer = Tokenizer::default();
+        let mut tree = TreeBuilder::new(root);
+
+        for x in ir {
+            match x {
+                HIR::Lit(s) => tree.tokens(tokenizer.feed(&s), &mut self.doctype),
+                HIR::Raw(s) => tokenizer.raw(&s)?,
+                HIR::Expr(e, _) | HIR::Safe(e) if tokenizer.in_comment() => drop(e),
+                HIR::Expr(e, span) => {
+                    let id = self.expr(&e);
+                    if let Some(e) = tokenizer.expr(Expression::Unsafe(id, e, span))? {
+                        tree.tokens(tokenizer.take()?, &mut self.doctype);
+                        tree.push(Node::Expr(e));
+                    }
+                }
+                HIR::Safe(e) => {
+                    let id = self.expr(&e);
+                    if let Some(e) = tokenizer.expr(Expression::Safe(id, e))? {
+                        tree.tokens(tokenizer.take()?, &mut self.doctype);
+                        tree.push(Node::Expr(e));
+                    }
+                }
+                _ if tokenizer.in_comment() => (),
+                HIR::Each(each) => {
+                    tree.tokens(tokenizer.take()?, &mut self.doctype);
+                    let each = self.each(*each)?;
+                    tree.push(Node::Expr(each));
+                }
+                HIR::IfElse(if_else) => {
+                    tree.tokens(tokenizer.take()?, &mut self.doctype);
+                    let if_else = self.if_else(*if_else)?;
+                    tree.push(Node::Expr(if_else));
+                }
+                HIR::Local(local) => {
+                    tree.tokens(tokenizer.take()?, &mut self.doctype);
+                    let local = self.local(local);
+                    tree.push(Node::Expr(local));
+                }
+                HIR::Flush => (),
+                HIR::BlockHelper(_) | HIR::Match(_) => {
+                    return Err("block helpers and match are not supported in DOM".into())
+                }
+            }
+        }
+        tree.tokens(tokenizer.end()?, &mut self.doctype);
+        self.scope.truncate(scope);
+
+        Ok(tree.end())
+    }
+
+    fn next_id(&mut self) -> ExprId {
+        let id = self.count;
+        self.count += 1;
+        id
+    }
+
+    /// Registers an expression and its variables
+    fn expr(&mut self, e: &syn::Expr) -> ExprId {
+        let id = self.next_id();
+        let vars = self.vars(e);
+        self.tree_map.insert(id, vars);
+        id
+    }
+
+    fn vars(&mut self, e: &syn::Expr) -> BTreeSet<VarId> {
+        let mut c = Collector {
+            b: self,
+            vars: BTreeSet::new(),
+        };
+        c.visit_expr(e);
+        c.vars
+    }
+
+    fn each(&mut self, each: HEach) -> Result<Expression, String> {
+        let HEach {
+            args,
+            body,
+            expr,
+            els,
+        } = each;
+        let id = self.expr(&args);
+
+        // key, (index, key) or (last, (index, key))
+        let mut idents = vec![];
+        let mut e = &expr;
+        loop {
+            match e {
+                syn::Expr::Tuple(t) if t.elems.len() == 2 => {
+                    idents.push(&t.elems[0]);
+                    e = &t.elems[1];
+                }
+                syn::Expr::Paren(p) => e = &p.expr,
+                e => {
+                    idents.push(e);
+                    break;
+                }
+            }
+        }
+        let var = |b: &mut Self, e: &syn::Expr| {
+            let var = var_id(e);
+            let ident = quote!(#e).to_string();
+            b.var_map.insert(
+                var,
+                Var::This(VarInner {
+                    base: var,
+                    ident: ident.clone(),
+                }),
+            );
+            b.scope.push((ident, Scoped::Var(var)));
+            var
+        };
+
+        let scope = self.scope.len();
+        let key = var(self, idents.pop().expect("key"));
+        let index = idents.pop().map(|x| var(self, x));
+        if let Some(last) = idents.pop() {
+            let ident = quote!(#last).to_string();
+            self.var_map
+                .insert(var_id(last), Var::Local(id, ident.clone()));
+            self.scope.push((ident, Scoped::Local(BTreeSet::new())));
+        }
+        let body = self.document(body, false)?;
+        self.scope.truncate(scope);
+
+        let els = els.map(|x| self.document(x, false)).transpose()?;
+
+        Ok(Expression::Each(
+            id,
+            Box::new(Each {
+                args,
+                body,
+                expr,
+                var: (key, index),
+                els,
+            }),
+        ))
+    }
+
+    fn if_else(&mut self, if_else: HIfElse) -> Result<Expression, String> {
+        let HIfElse { ifs, if_else, els } = if_else;
+        let id = self.next_id();
+        let mut vars = self.vars(&ifs.0);
+        for (e, _) in &if_else {
+            vars.extend(self.vars(e));
+        }
+        self.tree_map.insert(id, vars);
+
+        let ifs = IfBlock {
+            block: self.document(ifs.1, false)?,
+            expr: ifs.0,
+        };
+        let if_else = if_else
+            .into_iter()
+            .map(|(expr, block)| {
+                self.document(block, false)
+                    .map(|block| IfBlock { expr, block })
+            })
+            .collect::<Result<_, _>>()?;
+        let els = els.map(|x| self.document(x, false)).transpose()?;
+
+        Ok(Expression::IfElse(
+            id,
+            Box::new(IfElse { ifs, if_else, els }),
+        ))
+    }
+
+    fn local(&mut self, local: Box<syn::Local>) -> Expression {
+        let id = self.next_id();
+        let vars = local
+            .init
+            .as_ref()
+            .map(|(_, e)| self.vars(e))
+            .unwrap_or_default();
+        self.tree_map.insert(id, vars.clone());
+
+        let pat = &local.pat;
+        let var = var_id(pat);
+        let mut idents = PatIdents(vec![]);
+        idents.visit_pat(pat);
+        for ident in idents.0 {
+            self.var_map
+                .insert(var_id(&ident), Var::Local(id, ident.to_string()));
+            self.scope
+                .push((ident.to_string(), Scoped::Local(vars.clone())));
+        }
+
+        Expression::Local(id, var, local)
+    }
+
+    fn lookup(&self, ident: &str) -> Option<&Scoped> {
+        self.scope
+            .iter()
+            .rev()
+            .find_map(|(i, x)| if i == ident { Some(x) } else { None })
+    }
+}
+
+/// Variables of an expression
+struct Collector<'a> {
+    b: &'a mut DOMBuilder,
+    vars: BTreeSet<VarId>,
+}
+
+impl<'a, 'ast> Visit<'ast> for Collector<'a> {
+    fn visit_expr_field(&mut self, i: &'ast syn::ExprField) {
+        let base = match &*i.base {
+            syn::Expr::Path(p) => match p.path.get_ident() {
+                Some(ident) if ident == "self" => Some(calculate_hash(&"self")),
+                Some(ident) => match self.b.lookup(&ident.to_string()) {
+                    Some(Scoped::Var(base)) => Some(*base),
+                    Some(Scoped::Local(vars)) => {
+                        self.vars.extend(vars);
+                        return;
+                    }
+                    None => None,
+                },
+                None => None,
+            },
+            _ => None,
+        };
+
+        if let Some(base) = base {
+            let var = var_id(i);
+            let ident = match &i.member {
+                syn::Member::Named(ident) => ident.to_string(),
+                syn::Member::Unnamed(index) => index.index.to_string(),
+            };
+            self.b
+                .var_map
+                .insert(var, Var::This(VarInner { base, ident }));
+            self.vars.insert(var);
+        } else {
+            visit::visit_expr_field(self, i);
+        }
+    }
+
+    fn visit_expr_path(&mut self, i: &'ast syn::ExprPath) {
+        if let Some(ident) = i.path.get_ident() {
+            match self.b.lookup(&ident.to_string()) {
+                Some(Scoped::Var(var)) => {
+                    self.vars.insert(*var);
+                }
+                Some(Scoped::Local(vars)) => self.vars.extend(vars),
+                None => (),
+            }
+        }
+    }
+}
+
+/// Identifiers bound by a pattern
+struct PatIdents(Vec<syn::Ident>);
+
+impl<'ast> Visit<'ast> for PatIdents {
+    fn visit_pat_ident(&mut self, i: &'ast syn::PatIdent) {
+        self.0.push(i.ident.clone());
+        visit::visit_pat_ident(self, i);
+    }
+}
+
+struct Open {
+    ns: Namespace,
+    name: ExprOrText,
+    attrs: Vec<Attribute>,
+    children: Document,
+}
+
+impl Open {
+    fn is(&self, names: &[&str]) -> bool {
+        matches!(&self.name, ExprOrText::Text(name) if names.contains(&name.as_str()))
+    }
+}
+
+/// Builds the tree of a balanced sequence of tokens
+struct TreeBuilder {
+    root: bool,
+    doc: Document,
+    open: Vec<Open>,
+}
+
+impl TreeBuilder {
+    fn new(root: bool) -> Self {
+        TreeBuilder {
+            root,
+            doc: vec![],
+            open: vec![],
+        }
+    }
+
+    fn tokens(&mut self, tokens: Vec<Token>, doctype: &mut Option<String>) {
+        for token in tokens {
+            match token {
+                Token::Doctype(s) => {
+                    if self.root && doctype.is_none() {
+                        *doctype = Some(s);
+                    }
+                }
+                Token::StartTag {
+                    name,
+                    attrs,
+                    self_closing,
+                } => self.start(name, attrs, self_closing),
+                Token::EndTag(name) => self.end_tag(&name),
+                Token::Text(s) => self.text(s),
+            }
+        }
+    }
+
+    fn push(&mut self, node: Node) {
+        match self.open.last_mut() {
+            Some(open) => open.children.push(node),
+            None => self.doc.push(node),
+        }
+    }
+
+    fn text(&mut self, s: String) {
+        if self.root && self.open.is_empty() && s.chars().all(char::is_whitespace) {
+            return;
+        }
+        let children = match self.open.last_mut() {
+            Some(open) => &mut open.children,
+            None => &mut self.doc,
+        };
+        match children.last_mut() {
+            Some(Node::Elem(Element::Text(last))) => last.push_str(&s),
+            _ => children.push(Node::Elem(Element::Text(s))),
+        }
+    }
+
+    fn start(&mut self, name: ExprOrText, attrs: Vec<Attribute>, self_closing: bool) {
+        let tag = match &name {
+            ExprOrText::Text(name) => name.as_str(),
+            ExprOrText::Expr(_) => "",
+        };
+        while let Some(top) = self.open.last() {
+            let close = (top.is(&["p"]) && P_CLOSERS.contains(&tag))
+                || (top.is(&["li"]) && tag == "li")
+                || (top.is(&["dt", "dd"]) && ["dt", "dd"].contains(&tag))
+                || (top.is(&["td", "th"])
+                    && ["td", "th", "tr", "thead", "tbody", "tfoot"].contains(&tag))
+                || (top.is(&["tr"]) && ["tr", "thead", "tbody", "tfoot"].contains(&tag))
+                || (top.is(&["thead", "tbody", "tfoot"])
+                    && ["thead", "tbody", "tfoot"].contains(&tag))
+                || (top.is(&["option"]) && ["option", "optgroup"].contains(&tag))
+                || (top.is(&["optgroup"]) && tag == "optgroup")
+                || (top.is(&["head"]) && tag == "body");
+            if !close {
+                break;
+            }
+            self.close();
+        }
+
+        let ns = match tag {
+            "svg" => Namespace::Svg,
+            "math" => Namespace::MathMl,
+            _ => self.open.last().map_or(Namespace::Html, |x| x.ns),
+        };
+        let void = VOID.contains(&tag) || (self_closing && ns != Namespace::Html);
+        let open = Open {
+            ns,
+            name,
+            attrs,
+            children: vec![],
+        };
+        if void {
+            self.open.push(open);
+            self.close();
+        } else {
+            self.open.push(open);
+        }
+    }
+
+    fn end_tag(&mut self, name: &str) {
+        if let Some(i) = self.open.iter().rposition(|x| x.is(&[name])) {
+            while self.open.len() > i {
+                self.close();
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        let Open {
+            ns,
+            name,
+            attrs,
+            children,
+        } = self.open.pop().expect("open element");
+        self.push(Node::Elem(Element::Node {
+            name: (ns, name),
+            attrs,
+            children,
+        }));
+    }
+
+    fn end(mut self) -> Document {
+        while !self.open.is_empty() {
+            self.close();
+        }
+        self.doc
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SPAN: Span = Span { lo: 0, hi: 0 };
+
+    use syn::parse_str;
+
+    fn text(s: &str) -> Node {
+        Node::Elem(Element::Text(s.into()))
+    }
+
+    fn elem(name: &str, attrs: Vec<Attribute>, children: Document) -> Node {
+        Node::Elem(Element::Node {
+            name: (Namespace::Html, ExprOrText::Text(name.into())),
+            attrs,
+            children,
+        })
+    }
+
+    #[test]
+    fn test_implied_end_tags() {
+        let dom = DOM::new(vec![HIR::Lit(
+            "<!DOCTYPE html>\n<ul><li>a<li>b</ul><p>c<div>d</div><br>".into(),
+        )])
+        .unwrap();
+
+        assert_eq!(dom.doctype.as_deref(), Some("DOCTYPE html"));
+        assert_eq!(
+            dom.doc,
+            vec![
+                elem(
+                    "ul",
+                    vec![],
+                    vec![
+                        elem("li", vec![], vec![text("a")]),
+                        elem("li", vec![], vec![text("b")]),
+                    ]
+                ),
+                elem("p", vec![], vec![text("c")]),
+                elem("div", vec![], vec![text("d")]),
+                elem("br", vec![], vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        let foo: syn::Expr = parse_str("self.foo").unwrap();
+        let bar: syn::Expr = parse_str("self.bar.len()").unwrap();
+        let dom = DOM::new(vec![
+            HIR::Lit("<a href=\"/".into()),
+            HIR::Expr(Box::new(foo.clone()), SPAN),
+            HIR::Lit("\" class=x>".into()),
+            HIR::Safe(Box::new(bar.clone())),
+            HIR::Lit("<!-- ".into()),
+            HIR::Expr(Box::new(foo.clone()), SPAN),
+            HIR::Lit(" --></a>".into()),
+        ])
+        .unwrap();
+
+        let attrs = vec![
+            Attribute {
+                name: ExprOrText::Text("href".into()),
+                value: vec![
+                    ExprOrText::Text("/".into()),
+                    ExprOrText::Expr(Expression::Unsafe(0, Box::new(foo), SPAN)),
+                ],
+            },
+            Attribute {
+                name: ExprOrText::Text("class".into()),
+                value: vec![ExprOrText::Text("x".into())],
+            },
+        ];
+        assert_eq!(
+            dom.doc,
+            vec![elem(
+                "a",
+                attrs,
+                vec![Node::Expr(Expression::Safe(1, Box::new(bar)))]
+            )]
+        );
+
+        let foo_id = calculate_hash(&"self . foo");
+        let bar_id = calculate_hash(&"self . bar");
+        assert_eq!(dom.tree_map[&0], BTreeSet::from([foo_id]));
+        assert_eq!(dom.tree_map[&1], BTreeSet::from([bar_id]));
+        assert_eq!(
+            dom.var_map[&bar_id],
+            Var::This(VarInner {
+                base: calculate_hash(&"self"),
+                ident: "bar".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_each_vars() {
+        let args: syn::Expr = parse_str("((&(self.items)).__into_citer())").unwrap();
+        let key: syn::Expr = parse_str("__key___0x00000000").unwrap();
+        let id: syn::Expr = parse_str("__key___0x00000000.id").unwrap();
+        let dom = DOM::new(vec![
+            HIR::Lit("<ul>".into()),
+            HIR::Each(Box::new(HEach {
+                args,
+                body: vec![
+                    HIR::Lit("<li>".into()),
+                    HIR::Expr(Box::new(id), SPAN),
+                    HIR::Lit("</li>".into()),
+                ],
+                expr: key,
+                els: None,
+            })),
+            HIR::Lit("</ul>".into()),
+        ])
+        .unwrap();
+
+        let key_id = calculate_hash(&"__key___0x00000000");
+        let id_id = calculate_hash(&"__key___0x00000000 . id");
+        assert_eq!(
+            dom.tree_map[&0],
+            BTreeSet::from([calculate_hash(&"self . items")])
+        );
+        assert_eq!(dom.tree_map[&1], BTreeSet::from([id_id]));
+        assert_eq!(
+            dom.var_map[&id_id],
+            Var::This(VarInner {
+                base: key_id,
+                ident: "id".into()
+            })
+        );
+        match &dom.doc[0] {
+            Node::Elem(Element::Node { children, .. }) => match &children[0] {
+                Node::Expr(Expression::Each(0, each)) => assert_eq!(each.var, (key_id, None)),
+                _ => panic!("each"),
+            },
+            _ => panic!("ul"),
+        }
+    }
+
+    #[test]
+    fn test_block_in_tag() {
+        let ifs = HIR::IfElse(Box::new(HIfElse {
+            ifs: (parse_str("self.a").unwrap(), vec![HIR::Lit("b".into())]),
+            if_else: vec![],
+            els: None,
+        }));
+        assert!(DOM::new(vec![HIR::Lit("<a ".into()), ifs, HIR::Lit(">".into())]).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Serializer of the DOM back to HIR
+use quote::quote;
+
+use yarte_hir::{Each as HEach, IfElse as HIfElse, Struct, HIR};
+
+use crate::dom::{
+    Attribute, Document, Each, Element, ExprOrText, Expression, IfBlock, IfElse, Namespace, Node,
+    DOM,
+};
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// HIR of a DOM, with adjacent literals merged
+pub struct DOMFmt(pub Vec<HIR>);
+
+impl From<DOM> for DOMFmt {
+    fn from(dom: DOM) -> Self {
+        let mut buf = vec![];
+        if let Some(doctype) = dom.doctype {
+            write_lit(&mut buf, &format!("<!{doctype}>"));
+        }
+        write_doc(&mut buf, dom.doc);
+        DOMFmt(buf)
+    }
+}
+
+/// Html of a wasm application rendered in the server
+///
+/// Appends to `<body>` the initial state of the application
+/// and the module script of the client, if any.
+pub fn to_wasmfmt(ir: Vec<HIR>, s: &Struct) -> Result<Vec<HIR>, String> {
+    let mut dom = DOM::new(ir)?;
+    let body = dom
+        .doc
+        .iter_mut()
+        .find_map(|x| children_of(x, "html"))
+        .and_then(|x| x.iter_mut().find_map(|x| children_of(x, "body")))
+        .ok_or("Need <html> and <body> tags")?;
+
+    let state = syn::parse2(quote!(yarte::EscapedJson((&(self).__as_json()), false))).unwrap();
+    body.push(script(
+        vec![],
+        vec![
+            Node::Elem(Element::Text(
+                "function get_state(){return JSON.stringify(".into(),
+            )),
+            // Not registered in the tree map, only rendered in the server
+            Node::Expr(Expression::Safe(usize::MAX, Box::new(state))),
+            Node::Elem(Element::Text(");}".into())),
+        ],
+    ));
+    if let Some(src) = &s.script {
+        body.push(script(
+            vec![Attribute {
+                name: ExprOrText::Text("type".into()),
+                value: vec![ExprOrText::Text("module".into())],
+            }],
+            vec![Node::Elem(Element::Text(format!(
+                "import init from '{src}';async function run(){{await init()}}run()"
+            )))],
+        ));
+    }
+
+    Ok(DOMFmt::from(dom).0)
+}
+
+fn children_of<'a>(node: &'a mut Node, tag: &str) -> Option<&'a mut Document> {
+    match node {
+        Node::Elem(Element::Node {
+            name: (_, ExprOrText::Text(name)),
+            children,
+            ..
+        }) if name == tag => Some(children),
+        _ => None,
+    }
+}
+
+fn script(attrs: Vec<Attribute>, children: Document) -> Node {
+    Node::Elem(Element::Node {
+        name: (Namespace::Html, ExprOrText::Text("script".into())),
+        attrs,
+        children,
+    })
+}
+
+fn write_lit(buf: &mut Vec<HIR>, s: &str) {
+    match buf.last_mut() {
+        Some(HIR::Lit(last)) => last.push_str(s),
+        _ => buf.push(HIR::Lit(s.into())),
+    }
+}
+
+fn write_doc(buf: &mut Vec<HIR>, doc: Document) {
+    for node in doc {
+        match node {
+            Node::Elem(Element::Text(s)) => write_lit(buf, &s),
+            Node::Elem(Element::Node {
+                name: (ns, name),
+                attrs,
+                children,
+            }) => {
+                write_lit(buf, "<");
+                write_expr_or_text(buf, name.clone());
+                for attr in attrs {
+                    write_attr(buf, attr);
+                }
+                let void = matches!(&name, ExprOrText::Text(name) if VOID.contains(&name.as_str()));
+                if children.is_empty() && (void || ns != Namespace::Html) {
+                    write_lit(buf, if void { ">" } else { "/>" });
+                } else {
+                    write_lit(buf, ">");
+                    write_doc(buf, children);
+                    write_lit(buf, "</");
+                    write_expr_or_text(buf, name);
+                    write_lit(buf, ">");
+                }
+            }
+            Node::Expr(e) => write_expr(buf, e),
+        }
+    }
+}
+
+fn write_attr(buf: &mut Vec<HIR>, Attribute { name, value }: Attribute) {
+    write_lit(buf, " ");
+    write_expr_or_text(buf, name);
+    if value.is_empty() {
+        return;
+    }
+    let quote = if value
+        .iter()
+        .any(|x| matches!(x, ExprOrText::Text(s) if s.contains('"')))
+    {
+        "'"
+    } else {
+        "\""
+    };
+    write_lit(buf, "=");
+    write_lit(buf, quote);
+    for x in value {
+        write_expr_or_text(buf, x);
+    }
+    write_lit(buf, quote);
+}
+
+fn write_expr_or_text(buf: &mut Vec<HIR>, x: ExprOrText) {
+    match x {
+        ExprOrText::Text(s) => write_lit(buf, &s),
+        ExprOrText::Expr(e) => write_expr(buf, e),
+    }
+}
+
+fn to_hir(doc: Document) -> Vec<HIR> {
+    let mut buf = vec![];
+    write_doc(&mut buf, doc);
+    buf
+}
+
+fn write_expr(buf: &mut Vec<HIR>, e: Expression) {
+    buf.push(match e {
+        Expression::Unsafe(_, e, span) => HIR::Expr(e, span),
+        Expression::Safe(_, e) => HIR::Safe(e),
+        Expression::Each(_, each) => {
+            let Each {
+                args,
+                body,
+                expr,
+                els,
+                ..
+            } = *each;
+            HIR::Each(Box::new(HEach {
+                args,
+                body: to_hir(body),
+                expr,
+                els: els.map(to_hir),
+            }))
+        }
+        Expression::IfElse(_, if_else) => {
+            let IfElse { ifs, if_else, els } = *if_else;
+            let block = |IfBlock { expr, block }| (expr, to_hir(block));
+            HIR::IfElse(Box::new(HIfElse {
+                ifs: block(ifs),
+                if_else: if_else.into_iter().map(block).collect(),
+                els: els.map(to_hir),
+            }))
+        }
+        Expression::Local(_, _, local) => HIR::Local(local),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use yarte_hir::Span;
+
+    const SPAN: Span = Span { lo: 0, hi: 0 };
+
+    #[test]
+    fn test_round_trip() {
+        let e: syn::Expr = syn::parse_str("self.a").unwrap();
+        let ir = vec![
+            HIR::Lit("<!DOCTYPE html><html><body><p class='\"' hidden>".into()),
+            HIR::Expr(Box::new(e.clone()), SPAN),
+            HIR::Lit("<br></p><svg><path/></svg></body></html>".into()),
+        ];
+        let DOMFmt(ir) = DOMFmt::from(DOM::new(ir).unwrap());
+        assert_eq!(
+            ir,
+            vec![
+                HIR::Lit("<!DOCTYPE html><html><body><p class='\"' hidden>".into()),
+                HIR::Expr(Box::new(e), SPAN),
+                HIR::Lit("<br></p><svg><path/></svg></body></html>".into()),
+            ]
+        );
+    }
+}
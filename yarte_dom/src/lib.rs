@@ -0,0 +1,4 @@
+//! Html DOM of yarte templates, used by the wasm code generators
+pub mod dom;
+pub mod dom_fmt;
+mod tokenizer;
@@ -0,0 +1,316 @@
+//! Html tokenizer over the literals of the HIR
+//!
+//! The state is kept between literals, so expressions can be placed
+//! in text, tag names, attribute names and attribute values.
+use std::mem;
+
+use crate::dom::{Attribute, ExprOrText, Expression};
+
+/// Elements with text content until their end tag
+const RAW_TEXT: &[&str] = &["script", "style", "textarea", "title"];
+
+#[derive(Debug)]
+pub(crate) enum Token {
+    Doctype(String),
+    StartTag {
+        name: ExprOrText,
+        attrs: Vec<Attribute>,
+        self_closing: bool,
+    },
+    EndTag(String),
+    Text(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum State {
+    Data,
+    RawText(String),
+    Comment,
+    /// `<!...>` or `<?...>`
+    Bogus,
+    TagName,
+    BeforeAttr,
+    AttrName,
+    AfterAttrName,
+    BeforeValue,
+    /// Quoted or unquoted attribute value
+    Value(Option<char>),
+}
+
+pub(crate) struct Tokenizer {
+    state: State,
+    text: String,
+    tokens: Vec<Token>,
+    end: bool,
+    name: Option<ExprOrText>,
+    attrs: Vec<Attribute>,
+    self_closing: bool,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Tokenizer {
+            state: State::Data,
+            text: String::new(),
+            tokens: vec![],
+            end: false,
+            name: None,
+            attrs: vec![],
+            self_closing: false,
+        }
+    }
+}
+
+impl Tokenizer {
+    /// Tokens of a literal
+    pub(crate) fn feed(&mut self, s: &str) -> Vec<Token> {
+        let mut i = 0;
+        while let Some(c) = s[i..].chars().next() {
+            i += self.next(c, &s[i..]);
+        }
+        mem::take(&mut self.tokens)
+    }
+
+    /// Text written as is
+    pub(crate) fn raw(&mut self, s: &str) -> Result<(), String> {
+        match self.state {
+            State::Data | State::RawText(_) => self.text.push_str(s),
+            State::Comment => (),
+            _ => return Err("raw block in a tag".into()),
+        }
+        Ok(())
+    }
+
+    /// Places an expression, returns it when it's a node
+    pub(crate) fn expr(&mut self, e: Expression) -> Result<Option<Expression>, String> {
+        let e = ExprOrText::Expr(e);
+        match self.state {
+            State::Data | State::RawText(_) => {
+                let ExprOrText::Expr(e) = e else {
+                    unreachable!()
+                };
+                return Ok(Some(e));
+            }
+            State::Comment => (),
+            State::TagName if self.name.is_none() && !self.end => self.name = Some(e),
+            State::BeforeAttr | State::AfterAttrName => {
+                self.attrs.push(Attribute {
+                    name: e,
+                    value: vec![],
+                });
+                self.state = State::AttrName;
+            }
+            State::BeforeValue => {
+                self.push_value(e);
+                self.state = State::Value(None);
+            }
+            State::Value(_) => self.push_value(e),
+            State::Bogus | State::TagName | State::AttrName => {
+                return Err("expression in the middle of a tag name or an attribute name".into())
+            }
+        }
+        Ok(None)
+    }
+
+    pub(crate) fn in_comment(&self) -> bool {
+        self.state == State::Comment
+    }
+
+    /// Pending tokens before a node, it must be out of any tag
+    pub(crate) fn take(&mut self) -> Result<Vec<Token>, String> {
+        match self.state {
+            State::Data | State::RawText(_) => {
+                self.text();
+                Ok(mem::take(&mut self.tokens))
+            }
+            State::Comment => Ok(vec![]),
+            _ => Err("block in a tag".into()),
+        }
+    }
+
+    /// Pending tokens at the end of a block or the template
+    pub(crate) fn end(mut self) -> Result<Vec<Token>, String> {
+        match self.state {
+            State::Data | State::RawText(_) => self.take(),
+            State::Comment => Err("unclosed comment".into()),
+            _ => Err("unclosed tag".into()),
+        }
+    }
+
+    fn text(&mut self) {
+        if !self.text.is_empty() {
+            self.tokens.push(Token::Text(mem::take(&mut self.text)));
+        }
+    }
+
+    fn push_value(&mut self, v: ExprOrText) {
+        let value = &mut self.attrs.last_mut().expect("attribute").value;
+        match (value.last_mut(), v) {
+            (Some(ExprOrText::Text(a)), ExprOrText::Text(b)) => a.push_str(&b),
+            (_, v) => value.push(v),
+        }
+    }
+
+    fn push_name(&mut self, c: char) {
+        match &mut self.name {
+            Some(ExprOrText::Text(name)) => name.push(c.to_ascii_lowercase()),
+            Some(ExprOrText::Expr(_)) => (),
+            None => self.name = Some(ExprOrText::Text(c.to_ascii_lowercase().to_string())),
+        }
+    }
+
+    fn push_attr(&mut self, c: char) {
+        match &mut self.attrs.last_mut().expect("attribute").name {
+            ExprOrText::Text(name) => name.push(c),
+            ExprOrText::Expr(_) => (),
+        }
+    }
+
+    fn start_tag(&mut self, end: bool) {
+        self.text();
+        self.end = end;
+        self.name = None;
+        self.attrs.clear();
+        self.self_closing = false;
+        self.state = State::TagName;
+    }
+
+    fn emit_tag(&mut self) {
+        let name = self
+            .name
+            .take()
+            .unwrap_or_else(|| ExprOrText::Text(String::new()));
+        let attrs = mem::take(&mut self.attrs);
+        self.state = State::Data;
+        match name {
+            ExprOrText::Text(name) if self.end => self.tokens.push(Token::EndTag(name)),
+            ExprOrText::Text(name) if RAW_TEXT.contains(&name.as_str()) => {
+                self.state = State::RawText(name.clone());
+                self.tokens.push(Token::StartTag {
+                    name: ExprOrText::Text(name),
+                    attrs,
+                    self_closing: self.self_closing,
+                });
+            }
+            name => self.tokens.push(Token::StartTag {
+                name,
+                attrs,
+                self_closing: self.self_closing,
+            }),
+        }
+    }
+
+    /// Consumes the start of `rest`, being `c` its first char, returns the consumed length
+    fn next(&mut self, c: char, rest: &str) -> usize {
+        let len = c.len_utf8();
+        match &self.state {
+            State::Data => {
+                if rest.starts_with("<!--") {
+                    self.text();
+                    self.state = State::Comment;
+                    return 4;
+                }
+                let mut chars = rest.chars().skip(1);
+                match (c, chars.next(), chars.next()) {
+                    ('<', Some('/'), Some(n)) if n.is_ascii_alphabetic() => {
+                        self.start_tag(true);
+                        return 2;
+                    }
+                    ('<', Some(n), _) if n.is_ascii_alphabetic() => self.start_tag(false),
+                    ('<', Some('!' | '?'), _) => {
+                        self.text();
+                        self.text.push(c);
+                        self.state = State::Bogus;
+                    }
+                    _ => self.text.push(c),
+                }
+            }
+            State::RawText(name) => {
+                let is_end = rest.starts_with("</")
+                    && rest
+                        .get(2..2 + name.len())
+                        .is_some_and(|x| x.eq_ignore_ascii_case(name));
+                if is_end {
+                    let name = name.len();
+                    self.start_tag(true);
+                    self.name = Some(ExprOrText::Text(rest[2..2 + name].to_ascii_lowercase()));
+                    self.state = State::BeforeAttr;
+                    return 2 + name;
+                }
+                self.text.push(c);
+            }
+            State::Comment => {
+                if rest.starts_with("-->") {
+                    self.state = State::Data;
+                    return 3;
+                }
+            }
+            State::Bogus => {
+                if c == '>' {
+                    let s = mem::take(&mut self.text);
+                    if s.get(..9)
+                        .is_some_and(|x| x.eq_ignore_ascii_case("<!doctype"))
+                    {
+                        self.tokens.push(Token::Doctype(s[2..].into()));
+                    }
+                    self.state = State::Data;
+                } else {
+                    self.text.push(c);
+                }
+            }
+            State::TagName => match c {
+                '>' => self.emit_tag(),
+                '/' => self.self_closing = true,
+                c if c.is_whitespace() => self.state = State::BeforeAttr,
+                c => self.push_name(c),
+            },
+            State::BeforeAttr | State::AfterAttrName => match c {
+                '>' => self.emit_tag(),
+                '/' => self.self_closing = true,
+                '=' if self.state == State::AfterAttrName => self.state = State::BeforeValue,
+                c if c.is_whitespace() => (),
+                c => {
+                    self.self_closing = false;
+                    self.attrs.push(Attribute {
+                        name: ExprOrText::Text(c.to_string()),
+                        value: vec![],
+                    });
+                    self.state = State::AttrName;
+                }
+            },
+            State::AttrName => match c {
+                '>' => self.emit_tag(),
+                '/' => {
+                    self.self_closing = true;
+                    self.state = State::BeforeAttr;
+                }
+                '=' => self.state = State::BeforeValue,
+                c if c.is_whitespace() => self.state = State::AfterAttrName,
+                c => self.push_attr(c),
+            },
+            State::BeforeValue => match c {
+                '>' => self.emit_tag(),
+                '"' | '\'' => self.state = State::Value(Some(c)),
+                c if c.is_whitespace() => (),
+                c => {
+                    self.push_value(ExprOrText::Text(c.to_string()));
+                    self.state = State::Value(None);
+                }
+            },
+            State::Value(Some(q)) => {
+                if c == *q {
+                    self.state = State::BeforeAttr;
+                } else {
+                    self.push_value(ExprOrText::Text(c.to_string()));
+                }
+            }
+            State::Value(None) => match c {
+                '>' => self.emit_tag(),
+                c if c.is_whitespace() => self.state = State::BeforeAttr,
+                c => self.push_value(ExprOrText::Text(c.to_string())),
+            },
+        }
+
+        len
+    }
+}
@@ -4,6 +4,9 @@
 //!   - **`dir`**: name of template directory. If no value is given, a default directory
 //! **`templates`** will be used. If the defined directory is not found, an error
 //! will prompt.
+//!   - **`dirs`**: template directories searched in order after `dir`. An entry
+//!     starting with `$` is read from the environment, like the directory exported by
+//!     a dependency with [`export_templates`](crate::recompile::export_templates).
 //!   - **`debug`**: type of output of debug mode. The code and/or  ast generated by  Yarte
 //! can be visualize, to do so, at most one of three possible values has to be given:
 //! `code`, `ast`, or `all`.
@@ -13,14 +16,56 @@
 //! must exist, or error will be prompt. If the tag `partials` doesn't exist no aliasing
 //! will be possible.
 //!
+//! - **`helpers`** (block helpers - optional): each entry must be of the type
+//!   `name = "path::to::function"`. A block `{{#name arg}}...{{/name}}` calls the
+//!   function with a reference to the argument and the rendered body.
+//!
+//! - **`at_helpers`** (expression helpers - optional): each entry must be of the type
+//!   `name = { path = "path::to::function", args = 1 }`. An expression `{{ @name a }}`
+//!   calls the function with a reference to each argument, the number of arguments must
+//!   be `args`. Output is escaped unless `safe = true`.
+//!
+//! - **`extensions`** (escape modes - optional): each entry must be of the type
+//!   `extension = "mode"`, with mode `html`, `text`, `json` or `xml`. A template
+//!   `name.<extension>.hbs` escapes its expressions with the mode instead of the
+//!   default of the derive. `html`, `txt`, `md`, `json` and `xml` are mapped by default.
+//!
+//! - **`consts`** (compile-time constants - optional): each entry must be of the type
+//!   `NAME = value`, with a boolean, number, string or array of them. The evaluator of
+//!   the templates resolves `NAME`, so `{{#if NAME}}` and `{{#each NAME}}` are
+//!   unrolled at compile time.
+//!
+//! - **`profile.<name>`** (overlays - optional): tables with the sections above,
+//!   merged over the base configuration when the profile is selected by the
+//!   `YARTE_PROFILE` environment variable or by the cargo feature `profile-<name>`.
+//!   Values of the profile replace the ones of the base and the tables are merged
+//!   by entry.
+//!
 //! ### Example of a config file
 //! ```toml
 //! [main]
 //! dir = "templates"
+//! dirs = ["../shared/templates", "$DEP_UI_TEMPLATES"]
 //! debug = "all"
 //!
 //! [partials]
 //! alias = "./deep/more/deep"
+//!
+//! [helpers]
+//! card = "crate::helpers::card"
+//!
+//! [at_helpers]
+//! url = { path = "crate::helpers::url", args = 1 }
+//!
+//! [extensions]
+//! svg = "xml"
+//!
+//! [consts]
+//! FEATURE_X = true
+//! LOCALES = ["en", "es"]
+//!
+//! [profile.prod.partials]
+//! alias = "./deep/more/deep/min"
 //! ```
 //!
 //! With this configuration, the user can call `alias` in a partial instance with
@@ -28,36 +73,211 @@
 //!
 use std::{
     collections::BTreeMap,
-    env, fs,
+    env, error, fmt, fs,
     path::{Path, PathBuf},
     rc::Rc,
 };
 
 use serde::Deserialize;
 
+/// Error of the configuration or the template files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Template `path` not found in `dirs`, with the similar files of the directories
+    NotFound {
+        dirs: Vec<PathBuf>,
+        path: PathBuf,
+        suggestions: Vec<String>,
+    },
+    /// Template file can't be read
+    Read(PathBuf, String),
+    /// Configuration file can't be read
+    ReadConfig(PathBuf, String),
+    /// Configuration file isn't valid
+    Toml(String),
+    /// Environment variable of a template directory isn't defined
+    Env(String),
+    /// Constant isn't a boolean, number, string or array
+    Const(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NotFound {
+                path, suggestions, ..
+            } => {
+                write!(f, "template `{}` not found", path.display())?;
+                match suggestions.as_slice() {
+                    [] => Ok(()),
+                    [a] => write!(f, ", did you mean `{a}`?"),
+                    [init @ .., last] => {
+                        f.write_str(", did you mean ")?;
+                        for s in init {
+                            write!(f, "`{s}`, ")?;
+                        }
+                        write!(f, "or `{last}`?")
+                    }
+                }
+            }
+            ConfigError::Read(path, e) => {
+                write!(f, "unable to open template file {}: {e}", path.display())
+            }
+            ConfigError::ReadConfig(path, e) => write!(f, "unable to read {}: {e}", path.display()),
+            ConfigError::Toml(e) => write!(f, "invalid TOML in {CONFIG_FILE_NAME}: {e}"),
+            ConfigError::Env(name) => write!(
+                f,
+                "environment variable `{name}` of template directory in {CONFIG_FILE_NAME} is not defined"
+            ),
+            ConfigError::Const(name) => write!(
+                f,
+                "constant `{name}` in {CONFIG_FILE_NAME} must be a boolean, number, string or array"
+            ),
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+/// Max number of suggestions of a not found template
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Files of `dirs` similar to `path`, relative to its directory
+fn suggestions(dirs: &[PathBuf], path: &Path) -> Vec<String> {
+    let wanted = path.to_string_lossy();
+    let max = (wanted.chars().count() / 3).max(1);
+    let mut found = vec![];
+    for dir in dirs {
+        let mut stack = vec![dir.to_path_buf()];
+        while let Some(d) = stack.pop() {
+            let Ok(entries) = fs::read_dir(d) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let p = entry.path();
+                if p.is_dir() {
+                    stack.push(p);
+                } else if let Ok(rel) = p.strip_prefix(dir) {
+                    let rel = rel.to_string_lossy().into_owned();
+                    let d = distance(&wanted, &rel);
+                    if d <= max {
+                        found.push((d, rel));
+                    }
+                }
+            }
+        }
+    }
+    found.sort();
+    found.dedup();
+    found
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, s)| s)
+        .collect()
+}
+
+/// Levenshtein distance
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                prev.min(cur).min(row[j]) + 1
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+/// Template directories in lookup order, the first one is the main directory
 #[derive(Debug)]
-pub struct Dir(PathBuf);
+pub struct Dir(Vec<PathBuf>);
 
 impl Dir {
-    pub fn get_template(&self, path: Rc<Path>) -> Rc<Path> {
-        let template = self.0.join(path);
+    fn new(dir: Option<String>, dirs: Vec<String>) -> Result<Dir, ConfigError> {
+        let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let paths = Some(dir.unwrap_or_else(|| DEFAULT_DIR.into()))
+            .into_iter()
+            .chain(dirs)
+            .map(|d| expand(&d).map(|d| root.join(d)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Dir(paths))
+    }
 
-        if template.exists() {
-            template.into()
-        } else {
-            // TODO: error
-            panic!("template not found in directory {template:?}")
+    pub fn get_template(&self, path: Rc<Path>) -> Result<Rc<Path>, ConfigError> {
+        exists(&self.0, &path)
+    }
+}
+
+/// Replaces the environment variable of a directory starting with `$`
+fn expand(dir: &str) -> Result<PathBuf, ConfigError> {
+    match dir.strip_prefix('$') {
+        Some(var) => {
+            let (name, rest) = var.split_once('/').unwrap_or((var, ""));
+            let value = env::var(name).map_err(|_| ConfigError::Env(name.into()))?;
+            Ok(PathBuf::from(value).join(rest))
         }
+        None => Ok(dir.into()),
     }
 }
 
-impl From<Option<String>> for Dir {
-    fn from(p: Option<String>) -> Self {
-        let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
-        Dir(p.map_or_else(|| root.join(DEFAULT_DIR), |v| root.join(v)))
+/// Path of `path` in the first directory of `dirs` containing it
+fn exists(dirs: &[PathBuf], path: &Path) -> Result<Rc<Path>, ConfigError> {
+    match dirs.iter().map(|dir| dir.join(path)).find(|t| t.exists()) {
+        Some(template) => Ok(template.into()),
+        None => Err(ConfigError::NotFound {
+            dirs: dirs.to_vec(),
+            path: path.to_path_buf(),
+            suggestions: suggestions(dirs, path),
+        }),
+    }
+}
+
+/// Escape of the expressions of a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Context aware html escape
+    Html,
+    /// Without escape
+    Text,
+    /// Json string escape
+    Json,
+    /// Xml escape
+    Xml,
+}
+
+impl TryFrom<&str> for Mode {
+    type Error = String;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "html" => Ok(Mode::Html),
+            "text" => Ok(Mode::Text),
+            "json" => Ok(Mode::Json),
+            "xml" => Ok(Mode::Xml),
+            v => Err(format!(
+                "invalid escape mode: {v}, expected `html`, `text`, `json` or `xml`"
+            )),
+        }
     }
 }
 
+/// Escape modes of the extensions without configuration
+const DEFAULT_EXTENSIONS: &[(&str, Mode)] = &[
+    ("html", Mode::Html),
+    ("txt", Mode::Text),
+    ("md", Mode::Text),
+    ("json", Mode::Json),
+    ("xml", Mode::Xml),
+];
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum PrintConfig {
     All,
@@ -81,35 +301,107 @@ impl From<Option<String>> for PrintConfig {
 pub struct Config {
     dir: Dir,
     alias: BTreeMap<String, String>,
+    helpers: BTreeMap<String, String>,
+    at_helpers: BTreeMap<String, AtHelper>,
+    extensions: BTreeMap<String, Mode>,
+    consts: BTreeMap<String, String>,
     pub print_override: PrintConfig,
     pub debug: PrintOption,
 }
 
 impl Config {
-    pub fn new(s: &str) -> Config {
-        let raw: RawConfig =
-            toml::from_str(s).unwrap_or_else(|_| panic!("invalid TOML in {CONFIG_FILE_NAME}"));
-        let (dir, print) = raw.main.map(|x| (x.dir, x.debug)).unwrap_or((None, None));
+    /// Configuration of the profile in `YARTE_PROFILE`
+    pub fn new(s: &str) -> Result<Config, ConfigError> {
+        Config::with_profile(s, env::var(PROFILE_ENV).ok().as_deref())
+    }
+
+    /// Configuration with the sections of `profile` merged over the base
+    ///
+    /// A profile not defined in the file selects the base, the selector can be
+    /// shared by the crates of a workspace
+    pub fn with_profile(s: &str, profile: Option<&str>) -> Result<Config, ConfigError> {
+        let RawConfig { base, profiles } = parse(s)?;
+        let raw = match profile.and_then(|name| profiles?.remove(name)) {
+            Some(overlay) => base.overlay(overlay),
+            None => base,
+        };
+        let (dir, dirs, print) = raw
+            .main
+            .map(|x| (x.dir, x.dirs.unwrap_or_default(), x.debug))
+            .unwrap_or_default();
 
-        Config {
-            dir: Dir::from(dir),
+        Ok(Config {
+            dir: Dir::new(dir, dirs)?,
             print_override: PrintConfig::from(print),
             debug: raw.debug.unwrap_or_default(),
             alias: raw.partials.unwrap_or_default(),
-        }
+            helpers: raw.helpers.unwrap_or_default(),
+            at_helpers: raw.at_helpers.unwrap_or_default(),
+            extensions: DEFAULT_EXTENSIONS
+                .iter()
+                .map(|(ext, mode)| (ext.to_string(), *mode))
+                .chain(raw.extensions.unwrap_or_default())
+                .collect(),
+            consts: raw
+                .consts
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, value)| match const_expr(&value) {
+                    Some(expr) => Ok((name, expr)),
+                    None => Err(ConfigError::Const(name)),
+                })
+                .collect::<Result<_, _>>()?,
+        })
     }
 
+    /// Main template directory
     pub fn get_dir(&self) -> &PathBuf {
+        &self.dir.0[0]
+    }
+
+    /// Template directories in lookup order
+    pub fn get_dirs(&self) -> &[PathBuf] {
         &self.dir.0
     }
 
-    pub fn get_template(&self, path: Rc<Path>) -> (Rc<Path>, String) {
-        let path = self.dir.get_template(Rc::clone(&path));
-        let src = get_source(Rc::clone(&path));
-        (path, src)
+    pub fn get_template(&self, path: Rc<Path>) -> Result<(Rc<Path>, String), ConfigError> {
+        let path = self.dir.get_template(Rc::clone(&path))?;
+        let src = get_source(Rc::clone(&path))?;
+        Ok((path, src))
+    }
+
+    /// Rust path of the registered block helper
+    pub fn resolve_helper(&self, ident: &str) -> Option<&str> {
+        self.helpers.get(ident).map(String::as_str)
+    }
+
+    /// Registered @ helper
+    pub fn resolve_at_helper(&self, ident: &str) -> Option<&AtHelper> {
+        self.at_helpers.get(ident)
+    }
+
+    /// Rust expressions of the compile-time constants
+    pub fn get_consts(&self) -> &BTreeMap<String, String> {
+        &self.consts
+    }
+
+    /// Escape mode of the extension
+    pub fn resolve_extension(&self, ext: &str) -> Option<Mode> {
+        self.extensions.get(ext).copied()
+    }
+
+    /// Escape mode of a template by the extension before the last one, `name.<ext>.hbs`
+    pub fn resolve_mode<P: AsRef<Path>>(&self, path: P) -> Option<Mode> {
+        Path::new(path.as_ref().file_stem()?)
+            .extension()
+            .and_then(|ext| self.resolve_extension(ext.to_str()?))
     }
 
-    pub fn resolve_partial<P: AsRef<Path>>(&self, parent: P, ident: &str) -> Rc<Path> {
+    pub fn resolve_partial<P: AsRef<Path>>(
+        &self,
+        parent: P,
+        ident: &str,
+    ) -> Result<Rc<Path>, ConfigError> {
         let (mut buf, is_alias) = self
             .alias
             .iter()
@@ -132,41 +424,149 @@ impl Config {
         };
 
         if is_alias {
-            normalize(self.dir.get_template(buf.into()))
+            normalize(self.dir.get_template(buf.into())?)
         } else {
+            // Relative to the parent, then the template directories
             let mut parent = parent.as_ref().to_owned();
             parent.pop();
-            parent.push(buf);
-            normalize(parent.into())
+            let dirs: Vec<PathBuf> = Some(parent)
+                .into_iter()
+                .chain(self.dir.0.iter().cloned())
+                .collect();
+            normalize(exists(&dirs, &buf)?)
         }
     }
 }
 
 #[cfg(not(target_os = "windows"))]
-fn normalize(p: Rc<Path>) -> Rc<Path> {
-    // TODO: error
-    p.canonicalize().expect("Correct template path").into()
+fn normalize(p: Rc<Path>) -> Result<Rc<Path>, ConfigError> {
+    p.canonicalize()
+        .map(Into::into)
+        .map_err(|e| ConfigError::Read(p.to_path_buf(), e.to_string()))
 }
 
 #[cfg(target_os = "windows")]
-fn normalize(p: Rc<Path>) -> Rc<Path> {
+fn normalize(p: Rc<Path>) -> Result<Rc<Path>, ConfigError> {
     // TODO check canonicalize in windows
-    p
+    Ok(p)
+}
+
+/// Rust expression of a constant value
+fn const_expr(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) if f.is_finite() => Some(format!("{f:?}")),
+        toml::Value::String(s) => Some(format!("{s:?}")),
+        toml::Value::Array(v) => v
+            .iter()
+            .map(const_expr)
+            .collect::<Option<Vec<_>>>()
+            .map(|v| format!("[{}]", v.join(", "))),
+        _ => None,
+    }
+}
+
+fn parse(s: &str) -> Result<RawConfig, ConfigError> {
+    toml::from_str(s).map_err(|e| ConfigError::Toml(e.to_string()))
+}
+
+/// Profile selected by a cargo feature `profile-<name>` of the build script
+///
+/// `is_set` looks up an environment variable, the build script uses `env::var_os`
+pub fn feature_profile(
+    s: &str,
+    is_set: impl Fn(&str) -> bool,
+) -> Result<Option<String>, ConfigError> {
+    Ok(parse(s)?.profiles.and_then(|profiles| {
+        profiles.into_keys().find(|name| {
+            let feature = name.to_uppercase().replace('-', "_");
+            is_set(&format!("CARGO_FEATURE_PROFILE_{feature}"))
+        })
+    }))
 }
 
 #[derive(Deserialize)]
 struct RawConfig {
+    #[serde(flatten)]
+    base: Sections,
+    #[serde(rename = "profile")]
+    profiles: Option<BTreeMap<String, Sections>>,
+}
+
+/// Sections of the configuration file, of the base or of a profile
+#[derive(Deserialize)]
+struct Sections {
     main: Option<Main>,
     debug: Option<PrintOption>,
     partials: Option<BTreeMap<String, String>>,
+    helpers: Option<BTreeMap<String, String>>,
+    at_helpers: Option<BTreeMap<String, AtHelper>>,
+    extensions: Option<BTreeMap<String, Mode>>,
+    consts: Option<BTreeMap<String, toml::Value>>,
+}
+
+impl Sections {
+    /// Values of `profile` over the ones of `self`
+    fn overlay(self, profile: Sections) -> Sections {
+        Sections {
+            main: merge_with(self.main, profile.main, Main::overlay),
+            debug: merge_with(self.debug, profile.debug, PrintOption::overlay),
+            partials: merge(self.partials, profile.partials),
+            helpers: merge(self.helpers, profile.helpers),
+            at_helpers: merge(self.at_helpers, profile.at_helpers),
+            extensions: merge(self.extensions, profile.extensions),
+            consts: merge(self.consts, profile.consts),
+        }
+    }
+}
+
+fn merge_with<T>(base: Option<T>, profile: Option<T>, f: fn(T, T) -> T) -> Option<T> {
+    match (base, profile) {
+        (Some(base), Some(profile)) => Some(f(base, profile)),
+        (base, profile) => profile.or(base),
+    }
+}
+
+fn merge<V>(
+    base: Option<BTreeMap<String, V>>,
+    profile: Option<BTreeMap<String, V>>,
+) -> Option<BTreeMap<String, V>> {
+    merge_with(base, profile, |mut base, profile| {
+        base.extend(profile);
+        base
+    })
+}
+
+/// User defined @ helper
+#[derive(Debug, Clone, Deserialize)]
+pub struct AtHelper {
+    /// Rust path of the function
+    pub path: String,
+    /// Number of arguments
+    pub args: usize,
+    /// Write output without escape
+    #[serde(default)]
+    pub safe: bool,
 }
 
 #[derive(Deserialize)]
 struct Main {
     dir: Option<String>,
+    dirs: Option<Vec<String>>,
     debug: Option<String>,
 }
 
+impl Main {
+    fn overlay(self, profile: Main) -> Main {
+        Main {
+            dir: profile.dir.or(self.dir),
+            dirs: profile.dirs.or(self.dirs),
+            debug: profile.debug.or(self.debug),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Default)]
 pub struct PrintOption {
     pub theme: Option<String>,
@@ -178,15 +578,28 @@ pub struct PrintOption {
     pub short: Option<bool>,
 }
 
+impl PrintOption {
+    #[allow(deprecated)]
+    fn overlay(self, profile: PrintOption) -> PrintOption {
+        PrintOption {
+            theme: profile.theme.or(self.theme),
+            number_line: profile.number_line.or(self.number_line),
+            grid: profile.grid.or(self.grid),
+            paging: profile.paging.or(self.paging),
+            header: profile.header.or(self.header),
+            short: profile.short.or(self.short),
+        }
+    }
+}
+
 #[allow(deprecated)]
 
-pub fn read_config_file() -> String {
+pub fn read_config_file() -> Result<String, ConfigError> {
     let filename = config_file_path();
     if filename.exists() {
-        fs::read_to_string(&filename)
-            .unwrap_or_else(|_| panic!("unable to read {}", filename.to_str().unwrap()))
+        fs::read_to_string(&filename).map_err(|e| ConfigError::ReadConfig(filename, e.to_string()))
     } else {
-        String::new()
+        Ok(String::new())
     }
 }
 
@@ -196,23 +609,155 @@ pub fn config_file_path() -> PathBuf {
     PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join(CONFIG_FILE_NAME)
 }
 
-pub fn get_source(path: Rc<Path>) -> String {
+pub fn get_source(path: Rc<Path>) -> Result<String, ConfigError> {
     match fs::read_to_string(Rc::clone(&path)) {
-        Ok(mut source) => match source
-            .as_bytes()
-            .iter()
-            .rposition(|x| !x.is_ascii_whitespace())
-        {
-            Some(j) => {
-                source.drain(j + 1..);
-                source
-            }
-            None => source,
-        },
-        // TODO: error
-        _ => panic!("unable to open template file '{path:?}'"),
+        Ok(mut source) => Ok(
+            match source
+                .as_bytes()
+                .iter()
+                .rposition(|x| !x.is_ascii_whitespace())
+            {
+                Some(j) => {
+                    source.drain(j + 1..);
+                    source
+                }
+                None => source,
+            },
+        ),
+        Err(e) => Err(ConfigError::Read(path.to_path_buf(), e.to_string())),
     }
 }
 
 static CONFIG_FILE_NAME: &str = "yarte.toml";
+/// Environment variable of the selected profile
+pub static PROFILE_ENV: &str = "YARTE_PROFILE";
 static DEFAULT_DIR: &str = "templates";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("hello", "hello"), 0);
+        assert_eq!(distance("helo", "hello"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_not_found() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src");
+        let dirs = [dir.clone()];
+        let err = exists(&dirs, Path::new("confg.rs")).unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::NotFound {
+                dirs: dirs.to_vec(),
+                path: "confg.rs".into(),
+                suggestions: vec!["config.rs".into()],
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "template `confg.rs` not found, did you mean `config.rs`?"
+        );
+        assert!(exists(&dirs, Path::new("config.rs")).is_ok());
+    }
+
+    #[test]
+    fn test_mode() {
+        let config = Config::new("[extensions]\nsvg = \"xml\"\nmd = \"html\"").unwrap();
+        assert_eq!(config.resolve_mode("a/page.html.hbs"), Some(Mode::Html));
+        assert_eq!(config.resolve_mode("page.json.hbs"), Some(Mode::Json));
+        assert_eq!(config.resolve_mode("page.svg.hbs"), Some(Mode::Xml));
+        assert_eq!(config.resolve_mode("page.md.hbs"), Some(Mode::Html));
+        assert_eq!(config.resolve_mode("page.hbs"), None);
+        assert_eq!(config.resolve_mode("page.rs.hbs"), None);
+        assert!(Config::new("[extensions]\nsvg = \"svg\"").is_err());
+    }
+
+    #[test]
+    fn test_profile() {
+        let src = "[main]\ndebug = \"ast\"\n\n\
+                   [partials]\nalias = \"./a\"\nbase = \"./b\"\n\n\
+                   [profile.prod.main]\ndebug = \"code\"\n\n\
+                   [profile.prod.partials]\nalias = \"./prod/a\"\n";
+        let base = Config::with_profile(src, None).unwrap();
+        assert_eq!(base.print_override, PrintConfig::Ast);
+        assert_eq!(base.alias["alias"], "./a");
+
+        let prod = Config::with_profile(src, Some("prod")).unwrap();
+        assert_eq!(prod.print_override, PrintConfig::Code);
+        assert_eq!(prod.alias["alias"], "./prod/a");
+        assert_eq!(prod.alias["base"], "./b");
+
+        let dev = Config::with_profile(src, Some("dev")).unwrap();
+        assert_eq!(dev.print_override, PrintConfig::Ast);
+        assert_eq!(dev.alias["alias"], "./a");
+        assert!(Config::with_profile("", Some("prod")).is_ok());
+    }
+
+    #[test]
+    fn test_feature_profile() {
+        let src = "[profile.prod.main]\ndebug = \"code\"\n\n\
+                   [profile.staging-eu.main]\ndebug = \"ast\"\n";
+        let staging = |var: &str| var == "CARGO_FEATURE_PROFILE_STAGING_EU";
+        assert_eq!(feature_profile(src, |_| false).unwrap(), None);
+        assert_eq!(
+            feature_profile(src, staging).unwrap().as_deref(),
+            Some("staging-eu")
+        );
+        assert_eq!(feature_profile("", staging).unwrap(), None);
+    }
+
+    #[test]
+    fn test_consts() {
+        let src = "[consts]\nA = true\nB = [\"en\", \"e\\\"s\"]\nC = -1\nD = 2.0\n\n\
+                   [profile.prod.consts]\nA = false\n";
+        let config = Config::with_profile(src, None).unwrap();
+        let consts = config.get_consts();
+        assert_eq!(consts["A"], "true");
+        assert_eq!(consts["B"], r#"["en", "e\"s"]"#);
+        assert_eq!(consts["C"], "-1");
+        assert_eq!(consts["D"], "2.0");
+        let prod = Config::with_profile(src, Some("prod")).unwrap();
+        assert_eq!(prod.get_consts()["A"], "false");
+        assert_eq!(
+            Config::new("[consts]\nA = { b = 1 }").unwrap_err(),
+            ConfigError::Const("A".into())
+        );
+    }
+
+    #[test]
+    fn test_invalid_toml() {
+        assert!(matches!(Config::new("[main"), Err(ConfigError::Toml(_))));
+    }
+
+    #[test]
+    fn test_dirs() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let dir = Dir::new(
+            Some("src".into()),
+            vec!["$CARGO_MANIFEST_DIR/src/helpers".into()],
+        )
+        .unwrap();
+        assert_eq!(dir.0, [root.join("src"), root.join("src/helpers")]);
+        assert_eq!(
+            &*dir.get_template(Path::new("mod.rs").into()).unwrap(),
+            root.join("src/helpers/mod.rs")
+        );
+        assert_eq!(
+            &*dir.get_template(Path::new("lib.rs").into()).unwrap(),
+            root.join("src/lib.rs")
+        );
+        assert_eq!(
+            Dir::new(None, vec!["src".into()]).unwrap().0,
+            [root.join(DEFAULT_DIR), root.join("src")]
+        );
+        assert_eq!(
+            Dir::new(None, vec!["$YARTE_NOT_DEFINED".into()]).unwrap_err(),
+            ConfigError::Env("YARTE_NOT_DEFINED".into())
+        );
+    }
+}
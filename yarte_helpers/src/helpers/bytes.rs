@@ -1,11 +1,13 @@
 #![allow(clippy::transmute_ptr_to_ptr)]
 
+use std::fmt::{self, Display, Write};
 use std::io;
 use std::slice::from_raw_parts_mut;
 
 use buf_min::Buffer;
 use v_htmlescape::b_escape;
 
+use super::escape::Escaped;
 use super::ryu::{Sealed, MAX_SIZE_FLOAT};
 
 /// Render trait, used for wrap  expressions `{{ ... }}` when it's in a html template
@@ -237,6 +239,39 @@ impl RenderBytesSafe for bool {
     }
 }
 
+impl<'a, T: Display + ?Sized> RenderBytes for Escaped<'a, T> {
+    #[inline]
+    fn render<B: Buffer>(self, buf: &mut B) {
+        render_display(&self, buf)
+    }
+}
+
+impl<'a, T: Display + ?Sized> RenderBytesSafe for Escaped<'a, T> {
+    #[inline]
+    fn render<B: Buffer>(self, buf: &mut B) {
+        render_display(&self, buf)
+    }
+}
+
+/// Render a `Display` value in buffer without escape, used by block helpers
+#[inline]
+pub fn render_display<D: Display + ?Sized, B: Buffer>(d: &D, buf: &mut B) {
+    // Buffer writes never fail
+    let _ = write!(FmtWriter { buf }, "{d}");
+}
+
+struct FmtWriter<'a, B> {
+    buf: &'a mut B,
+}
+
+impl<'a, B: Buffer> fmt::Write for FmtWriter<'a, B> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.buf.extend(s);
+        Ok(())
+    }
+}
+
 // TODO dead code
 #[allow(dead_code)]
 struct UnsafeWriter<'a, B> {
@@ -260,6 +295,7 @@ impl<'a, B: Buffer> io::Write for UnsafeWriter<'a, B> {
 mod json {
     use super::*;
     use crate::at_helpers::Json;
+    use crate::helpers::escape::{escape_json, EscapedJson};
     use crate::helpers::json::{self, to_mut_bytes};
 
     impl<'a, S: json::Serialize> RenderBytes for Json<'a, S> {
@@ -269,6 +305,16 @@ mod json {
         }
     }
 
+    impl<'a, 'b, S: json::Serialize> RenderBytes for EscapedJson<'a, Json<'b, S>> {
+        #[inline]
+        fn render<B: Buffer>(self, buf: &mut B) {
+            let mut json = String::new();
+            to_mut_bytes(self.0 .0, &mut json);
+            // Buffer writes never fail
+            let _ = escape_json(&json, self.1, &mut FmtWriter { buf });
+        }
+    }
+
     impl<'a, S: json::Serialize> RenderBytesSafe for Json<'a, S> {
         #[inline(always)]
         fn render<B: Buffer>(self, buf: &mut B) {
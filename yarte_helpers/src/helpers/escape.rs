@@ -0,0 +1,277 @@
+//! Escapers of expressions by their context in a html template
+//!
+//! The derives inspect the html around an expression and wrap it in
+//! [`Escaped`] or [`EscapedJson`] when the default html escape isn't enough.
+//! Templates with another escape mode, like `.xml.hbs`, wrap every expression.
+use std::fmt::{self, Display, Write};
+
+/// Replace of urls with an unsafe scheme, like `javascript:`
+pub const INVALID_URL: &str = "about:invalid";
+/// Replace of unsafe css property values
+pub const INVALID_CSS: &str = "invalid";
+
+/// Context of an expression in a html template
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Escaper {
+    /// Start of a url attribute value, unsafe schemes are replaced
+    Url,
+    /// Path of a url attribute value
+    UrlPath,
+    /// Query or fragment of a url attribute value
+    UrlQuery,
+    /// Javascript string literal
+    JsString,
+    /// Css string literal
+    CssString,
+    /// Css property value, only keywords, numbers and colors are allowed
+    CssValue,
+    /// Html text or attribute value
+    Html,
+    /// Xml text or attribute value
+    Xml,
+    /// Json string literal
+    JsonString,
+}
+
+/// Expression escaped by its context
+pub struct Escaped<'a, T: ?Sized>(pub &'a T, pub Escaper);
+
+impl<'a, T: ?Sized> Clone for Escaped<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: ?Sized> Copy for Escaped<'a, T> {}
+
+impl<'a, T: Display + ?Sized> Display for Escaped<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.1 {
+            Escaper::Url => {
+                let s = self.0.to_string();
+                if is_safe_url(&s) {
+                    Escape(f, Escaper::UrlPath).write_str(&s)
+                } else {
+                    f.write_str(INVALID_URL)
+                }
+            }
+            Escaper::CssValue => {
+                let s = self.0.to_string();
+                if is_safe_css(&s) {
+                    f.write_str(&s)
+                } else {
+                    f.write_str(INVALID_CSS)
+                }
+            }
+            e => write!(Escape(f, e), "{}", self.0),
+        }
+    }
+}
+
+/// `@json` in javascript code, escaped for a `<script>` element or for an attribute
+pub struct EscapedJson<'a, T: ?Sized>(pub &'a T, pub bool);
+
+impl<'a, T: ?Sized> Clone for EscapedJson<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: ?Sized> Copy for EscapedJson<'a, T> {}
+
+impl<'a, T: Display + ?Sized> Display for EscapedJson<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(EscapeJson(f, self.1), "{}", self.0)
+    }
+}
+
+/// Escape json for javascript code of a `<script>` element or of an attribute
+pub fn escape_json<W: Write>(s: &str, attr: bool, w: &mut W) -> fmt::Result {
+    EscapeJson(w, attr).write_str(s)
+}
+
+fn is_safe_url(s: &str) -> bool {
+    match s.find([':', '/', '?', '#']) {
+        Some(i) if s.as_bytes()[i] == b':' => {
+            let scheme = &s[..i];
+            ["http", "https", "mailto"]
+                .iter()
+                .any(|x| scheme.eq_ignore_ascii_case(x))
+        }
+        _ => true,
+    }
+}
+
+fn is_safe_css(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars().all(|c| {
+            c.is_alphanumeric() || matches!(c, ' ' | '#' | '%' | '.' | ',' | '_' | '+' | '-')
+        })
+}
+
+struct Escape<'a, W>(&'a mut W, Escaper);
+
+impl<'a, W: Write> Write for Escape<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut last = 0;
+        for (i, c) in s.char_indices() {
+            let keep = match self.1 {
+                Escaper::Url | Escaper::UrlPath => {
+                    is_unreserved(c) || "%:/?#[]@!$()*+,;=".contains(c)
+                }
+                Escaper::UrlQuery => is_unreserved(c),
+                Escaper::JsString => {
+                    !c.is_control()
+                        && !matches!(
+                            c,
+                            '\\' | '\'' | '"' | '`' | '<' | '>' | '&' | '\u{2028}' | '\u{2029}'
+                        )
+                }
+                Escaper::CssString => !c.is_ascii() || c.is_ascii_alphanumeric() || c == ' ',
+                Escaper::CssValue => true,
+                Escaper::Html => !matches!(c, '&' | '<' | '>' | '"' | '\'' | '/'),
+                Escaper::Xml => !matches!(c, '&' | '<' | '>' | '"' | '\''),
+                Escaper::JsonString => !c.is_control() && c != '"' && c != '\\',
+            };
+            if keep {
+                continue;
+            }
+
+            self.0.write_str(&s[last..i])?;
+            last = i + c.len_utf8();
+            match self.1 {
+                Escaper::Url | Escaper::UrlPath | Escaper::UrlQuery => {
+                    if c == '&' && self.1 != Escaper::UrlQuery {
+                        self.0.write_str("&amp;")?;
+                    } else {
+                        for b in c.encode_utf8(&mut [0; 4]).bytes() {
+                            write!(self.0, "%{b:02X}")?;
+                        }
+                    }
+                }
+                Escaper::JsString => write!(self.0, "\\u{:04x}", c as u32)?,
+                Escaper::CssString => write!(self.0, "\\{:x} ", c as u32)?,
+                Escaper::CssValue => unreachable!(),
+                Escaper::Html => self.0.write_str(match c {
+                    '&' => "&amp;",
+                    '<' => "&lt;",
+                    '>' => "&gt;",
+                    '"' => "&quot;",
+                    '\'' => "&#x27;",
+                    _ => "&#x2f;",
+                })?,
+                Escaper::Xml => self.0.write_str(match c {
+                    '&' => "&amp;",
+                    '<' => "&lt;",
+                    '>' => "&gt;",
+                    '"' => "&quot;",
+                    _ => "&apos;",
+                })?,
+                Escaper::JsonString => match c {
+                    '"' => self.0.write_str("\\\"")?,
+                    '\\' => self.0.write_str("\\\\")?,
+                    '\n' => self.0.write_str("\\n")?,
+                    '\r' => self.0.write_str("\\r")?,
+                    '\t' => self.0.write_str("\\t")?,
+                    _ => write!(self.0, "\\u{:04x}", c as u32)?,
+                },
+            }
+        }
+        self.0.write_str(&s[last..])
+    }
+}
+
+#[inline]
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')
+}
+
+struct EscapeJson<'a, W>(&'a mut W, bool);
+
+impl<'a, W: Write> Write for EscapeJson<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut last = 0;
+        for (i, c) in s.char_indices() {
+            let esc = match c {
+                '<' => "\\u003c",
+                '>' => "\\u003e",
+                '&' => "\\u0026",
+                '\u{2028}' => "\\u2028",
+                '\u{2029}' => "\\u2029",
+                '"' if self.1 => "&#34;",
+                '\'' if self.1 => "&#39;",
+                _ => continue,
+            };
+            self.0.write_str(&s[last..i])?;
+            self.0.write_str(esc)?;
+            last = i + c.len_utf8();
+        }
+        self.0.write_str(&s[last..])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn e(s: &str, escaper: Escaper) -> String {
+        Escaped(s, escaper).to_string()
+    }
+
+    #[test]
+    fn test_url() {
+        assert_eq!(
+            e("/a b?x=1&y='2'", Escaper::Url),
+            "/a%20b?x=1&amp;y=%272%27"
+        );
+        assert_eq!(e("HTTPS://x.org/ñ", Escaper::Url), "HTTPS://x.org/%C3%B1");
+        assert_eq!(e("javascript:alert(1)", Escaper::Url), INVALID_URL);
+        assert_eq!(e(" javascript:alert(1)", Escaper::Url), INVALID_URL);
+        assert_eq!(e("a/b", Escaper::UrlPath), "a/b");
+        assert_eq!(e("a/b&c=d e", Escaper::UrlQuery), "a%2Fb%26c%3Dd%20e");
+    }
+
+    #[test]
+    fn test_js() {
+        assert_eq!(
+            e("</script>'\"\\\n", Escaper::JsString),
+            "\\u003c/script\\u003e\\u0027\\u0022\\u005c\\u000a"
+        );
+        assert_eq!(
+            EscapedJson("{\"a\":\"</script>\"}", false).to_string(),
+            "{\"a\":\"\\u003c/script\\u003e\"}"
+        );
+        assert_eq!(
+            EscapedJson("{\"a\":\"&'\"}", true).to_string(),
+            "{&#34;a&#34;:&#34;\\u0026&#39;&#34;}"
+        );
+    }
+
+    #[test]
+    fn test_css() {
+        assert_eq!(
+            e("a\"b</style>", Escaper::CssString),
+            "a\\22 b\\3c \\2f style\\3e "
+        );
+        assert_eq!(e("#fff", Escaper::CssValue), "#fff");
+        assert_eq!(e("1.5em", Escaper::CssValue), "1.5em");
+        assert_eq!(e("red;background:url(x)", Escaper::CssValue), INVALID_CSS);
+        assert_eq!(e("", Escaper::CssValue), INVALID_CSS);
+    }
+
+    #[test]
+    fn test_modes() {
+        assert_eq!(
+            e("<a href='/'>&\"", Escaper::Html),
+            "&lt;a href=&#x27;&#x2f;&#x27;&gt;&amp;&quot;"
+        );
+        assert_eq!(
+            e("<a b='c'>&\"", Escaper::Xml),
+            "&lt;a b=&apos;c&apos;&gt;&amp;&quot;"
+        );
+        assert_eq!(
+            e("a\"b\\c\nd\u{1}é", Escaper::JsonString),
+            "a\\\"b\\\\c\\nd\\u0001é"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::{io, mem, ptr};
+
+/// Default capacity, the same as `io::BufWriter`
+pub const IO_BUF_CAPACITY: usize = 8 * 1024;
+
+/// # Not Use is internal library
+/// Buffer written to a `io::Write` when it's full
+///
+/// It doesn't implement `Buffer`, so it can't be created without a writer.
+/// The code generator wraps it in a type private to the render.
+///
+/// The first `io::Error` is kept and returned by `freeze`,
+/// later output is discarded.
+pub struct IoBuffer<W: io::Write> {
+    buf: Vec<u8>,
+    w: W,
+    err: Option<io::Error>,
+}
+
+impl<W: io::Write> IoBuffer<W> {
+    pub fn new(w: W) -> Self {
+        IoBuffer {
+            buf: Vec::with_capacity(IO_BUF_CAPACITY),
+            w,
+            err: None,
+        }
+    }
+
+    fn write_buf(&mut self) {
+        if self.err.is_none() && !self.buf.is_empty() {
+            self.err = self.w.write_all(&self.buf).err();
+        }
+        self.buf.clear();
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// # Safety
+    /// Same as `Buffer::extend_from_slice`
+    #[inline]
+    pub unsafe fn extend_from_slice(&mut self, src: &[u8]) {
+        self.reserve(src.len());
+        ptr::copy_nonoverlapping(src.as_ptr(), self.buf_ptr(), src.len());
+        self.advance(src.len())
+    }
+
+    /// Writes the buffer when `additional` doesn't fit
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        if self.buf.capacity() - self.buf.len() < additional {
+            self.write_buf();
+            self.buf.reserve(additional);
+        }
+    }
+
+    pub fn freeze(mut self) -> io::Result<()> {
+        self.write_buf();
+        match mem::take(&mut self.err) {
+            Some(err) => Err(err),
+            None => self.w.flush(),
+        }
+    }
+
+    /// # Safety
+    /// Same as `Buffer::advance`
+    #[inline]
+    pub unsafe fn advance(&mut self, cnt: usize) {
+        self.buf.set_len(self.buf.len() + cnt);
+    }
+
+    /// # Safety
+    /// Same as `Buffer::buf_ptr`
+    #[inline]
+    pub unsafe fn buf_ptr(&mut self) -> *mut u8 {
+        self.buf.as_mut_ptr().add(self.buf.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn extend<W: io::Write>(buf: &mut IoBuffer<W>, src: &str) {
+        unsafe { buf.extend_from_slice(src.as_bytes()) }
+    }
+
+    struct Fail(usize);
+
+    impl io::Write for Fail {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            self.0 += 1;
+            Err(io::Error::other("fail"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write() {
+        let mut out = vec![];
+        let mut buf = IoBuffer::new(&mut out);
+        let chunk = "a".repeat(IO_BUF_CAPACITY - 1);
+        extend(&mut buf, &chunk);
+        extend(&mut buf, "bc");
+        extend(&mut buf, "d");
+        buf.freeze().unwrap();
+        assert_eq!(out, format!("{chunk}bcd").as_bytes());
+    }
+
+    #[test]
+    fn test_error() {
+        let mut w = Fail(0);
+        let mut buf = IoBuffer::new(&mut w);
+        extend(&mut buf, &"a".repeat(IO_BUF_CAPACITY));
+        extend(&mut buf, "b");
+        extend(&mut buf, &"c".repeat(IO_BUF_CAPACITY));
+        assert_eq!(buf.freeze().unwrap_err().to_string(), "fail");
+        assert_eq!(w.0, 1);
+    }
+}
@@ -3,6 +3,8 @@ use std::fmt::{self, Display};
 
 use v_htmlescape::escape;
 
+use super::escape::{Escaped, EscapedJson};
+
 /// Render trait, used for wrap unsafe expressions `{{ ... }}` when it's in a html template
 pub trait Render {
     fn render(&self, f: &mut fmt::Formatter) -> fmt::Result;
@@ -105,6 +107,20 @@ raw_display! {
     bool
 }
 
+impl<'a, T: Display + ?Sized> Render for Escaped<'a, T> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt(f)
+    }
+}
+
+impl<'a, T: Display + ?Sized> Render for EscapedJson<'a, T> {
+    #[inline(always)]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt(f)
+    }
+}
+
 #[cfg(feature = "json")]
 mod json {
     use super::*;
@@ -1,3 +1,5 @@
+use std::iter::Peekable;
+
 pub mod cow;
 pub mod integers;
 pub mod io_fmt;
@@ -12,22 +14,30 @@ pub struct Aligned256<T>(pub T);
 #[cfg(feature = "big-num-32")]
 pub mod big_num_32;
 pub mod display_fn;
+pub mod escape;
 
 #[cfg(any(feature = "bytes-buf", feature = "json"))]
 mod bytes;
+#[cfg(feature = "bytes-buf")]
+#[doc(hidden)]
+pub mod io_buf;
 #[cfg(feature = "markup")]
 mod markup;
 #[cfg(feature = "json")]
 mod ser_json;
 
 #[cfg(feature = "bytes-buf")]
-pub use self::bytes::{RenderBytes, RenderBytesA, RenderBytesSafe, RenderBytesSafeA};
+pub use self::bytes::{
+    render_display, RenderBytes, RenderBytesA, RenderBytesSafe, RenderBytesSafeA,
+};
 #[cfg(feature = "fixed")]
 pub use self::fixed::{RenderFixed, RenderFixedA, RenderSafe, RenderSafeA};
 #[cfg(feature = "markup")]
 pub use self::markup::{Render, RenderA};
 #[cfg(feature = "ryu-ad")]
 pub mod ryu;
+#[cfg(feature = "stream")]
+pub mod stream;
 
 #[cfg(feature = "json")]
 pub mod json {
@@ -47,3 +57,37 @@ impl<I: IntoIterator + Sized> IntoCopyIterator for I {
         self.into_iter()
     }
 }
+
+/// Iterator adapter for `last` loop variable, yields `(is_last, item)`
+pub struct WithLast<I: Iterator> {
+    iter: Peekable<I>,
+}
+
+impl<I: Iterator> Iterator for WithLast<I> {
+    type Item = (bool, I::Item);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|item| (self.iter.peek().is_none(), item))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+pub trait WithLastIterator: Iterator + Sized {
+    fn __with_last(self) -> WithLast<Self>;
+}
+
+impl<I: Iterator> WithLastIterator for I {
+    #[inline]
+    fn __with_last(self) -> WithLast<Self> {
+        WithLast {
+            iter: self.peekable(),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Stream of rendered chunks
+//!
+//! The derive renders inside an `async` block which sends a chunk to
+//! the [`StreamSink`] at every flush point and yields until it's polled again.
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+pub use futures_core::Stream;
+
+/// Stream of the chunks sent by a render
+pub struct RenderStream<T, F> {
+    chunk: Arc<Mutex<Option<T>>>,
+    render: Option<Pin<Box<F>>>,
+}
+
+impl<T, F: Future<Output = ()>> RenderStream<T, F> {
+    /// Stream of the chunks sent to the sink by the render
+    pub fn new<R: FnOnce(StreamSink<T>) -> F>(render: R) -> Self {
+        let chunk = Arc::new(Mutex::new(None));
+        let render = render(StreamSink(Arc::clone(&chunk)));
+        RenderStream {
+            chunk,
+            render: Some(Box::pin(render)),
+        }
+    }
+}
+
+impl<T, F: Future<Output = ()>> Stream for RenderStream<T, F> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        let render = match this.render.as_mut() {
+            Some(render) => render,
+            None => return Poll::Ready(None),
+        };
+
+        match render.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                this.render = None;
+                Poll::Ready(take(&this.chunk))
+            }
+            Poll::Pending => {
+                let chunk = take(&this.chunk);
+                // The render is only pending at a `Flush`, after sending a chunk
+                debug_assert!(chunk.is_some(), "render pending without a chunk");
+                match chunk {
+                    Some(chunk) => Poll::Ready(Some(chunk)),
+                    None => Poll::Pending,
+                }
+            }
+        }
+    }
+}
+
+fn take<T>(chunk: &Mutex<Option<T>>) -> Option<T> {
+    chunk.lock().expect("not poisoned").take()
+}
+
+/// # Not Use is internal library
+/// Sender of the chunks of a render
+pub struct StreamSink<T>(Arc<Mutex<Option<T>>>);
+
+impl<T> StreamSink<T> {
+    /// Sends a chunk and yields to the stream
+    pub fn send(&self, chunk: T) -> Flush {
+        *self.0.lock().expect("not poisoned") = Some(chunk);
+        Flush(false)
+    }
+}
+
+/// Future pending until the sent chunk is taken by the stream
+pub struct Flush(bool);
+
+impl Future for Flush {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            // The waker isn't registered: `RenderStream` returns the sent chunk
+            // from this same poll, so the consumer polls the stream again
+            // and resumes the render without a wake up
+            self.0 = true;
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::task::Waker;
+
+    fn collect<S: Stream + Unpin>(mut s: S) -> Vec<S::Item> {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut items = vec![];
+        while let Poll::Ready(Some(x)) = Pin::new(&mut s).poll_next(&mut cx) {
+            items.push(x);
+        }
+        items
+    }
+
+    #[test]
+    fn test_chunks() {
+        let s = RenderStream::new(|sink| async move {
+            for i in 0..3 {
+                sink.send(i).await;
+            }
+        });
+        assert_eq!(collect(s), [0, 1, 2]);
+    }
+}
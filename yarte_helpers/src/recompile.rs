@@ -1,23 +1,58 @@
-use std::fs;
+use std::{env, fs, path::Path, process};
 
-use crate::config::{config_file_path, read_config_file, Config};
+use crate::config::{config_file_path, feature_profile, read_config_file, Config, PROFILE_ENV};
 
 /// Recompile when changed. Put me on your `build.rs`
+///
+/// The template directories exported by the dependencies, `DEP_<LINKS>_TEMPLATES`,
+/// are forwarded to the compilation of the crate. The profile of the configuration,
+/// `YARTE_PROFILE` or the first one with its cargo feature `profile-<name>` enabled,
+/// is forwarded too.
+///
+/// Exits with an error when the configuration file is not valid.
 pub fn when_changed() {
     // rerun when config file change
     println!(
         "cargo:rerun-if-changed={}",
         config_file_path().to_str().unwrap()
     );
+    // rerun when selected profile change
+    println!("cargo:rerun-if-env-changed={PROFILE_ENV}");
 
-    let file = read_config_file();
-    let config = Config::new(&file);
+    for (key, value) in env::vars() {
+        if key.starts_with("DEP_") && key.ends_with("_TEMPLATES") {
+            println!("cargo:rustc-env={key}={value}");
+        }
+    }
 
-    let mut stack = vec![config.get_dir().clone()];
+    let config = read_config_file()
+        .and_then(|file| {
+            let profile = match env::var(PROFILE_ENV) {
+                Ok(profile) => Some(profile),
+                Err(_) => feature_profile(&file, |var| env::var_os(var).is_some())?,
+            };
+            if let Some(profile) = &profile {
+                println!("cargo:rustc-env={PROFILE_ENV}={profile}");
+            }
+            Config::with_profile(&file, profile.as_deref())
+        })
+        .unwrap_or_else(|e| {
+            // a panic of the build script hides the message in its backtrace
+            for line in e.to_string().lines() {
+                println!("cargo:warning={line}");
+            }
+            process::exit(1)
+        });
+
+    let mut stack = config.get_dirs().to_vec();
     while let Some(dir) = stack.pop() {
         // rerun when dir change
         println!("cargo:rerun-if-changed={}", dir.to_str().unwrap());
-        for entry in fs::read_dir(dir).expect("valid directory") {
+        // the default directory is searched even if it doesn't exist
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries {
             let path = entry.expect("valid directory entry").path();
             if path.is_dir() {
                 stack.push(path);
@@ -28,3 +63,12 @@ pub fn when_changed() {
         }
     }
 }
+
+/// Exports the template directory `dir` to the dependent crates. Put me on your `build.rs`
+///
+/// The crate needs the `links` key in its manifest, the dependents add
+/// `"$DEP_<LINKS>_TEMPLATES"` to `dirs` in its configuration file.
+pub fn export_templates<P: AsRef<Path>>(dir: P) {
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join(dir);
+    println!("cargo:templates={}", dir.to_str().unwrap());
+}
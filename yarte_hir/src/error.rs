@@ -2,6 +2,7 @@ use derive_more::Display;
 use std::path::Path;
 use std::rc::Rc;
 
+use yarte_helpers::config::ConfigError;
 use yarte_parser::{source_map::Span, ErrorMessage};
 
 pub type GResult<T> = Result<T, GError>;
@@ -51,6 +52,8 @@ pub enum GError {
     ValidatorUnlessNegate,
     #[display(fmt = "Not available Rust expression in a template `unless helper` expression")]
     ValidatorUnless,
+    #[display(fmt = "Not available Rust expression in a template block helper argument")]
+    ValidatorHelper,
     #[display(fmt = "Not available Rust expression in partial scope argument")]
     ValidatorPartialScope,
     #[display(fmt = "Not available Rust expression in partial assign argument")]
@@ -75,8 +78,34 @@ pub enum GError {
     UserCompileError(String),
     #[display(fmt = "Internal")]
     Internal,
+    #[display(fmt = "Not exist block helper, register it at `helpers` in the config file")]
+    HelperNotExist,
+    #[display(fmt = "Not valid Rust path for block helper `{_0}`")]
+    HelperPath(String),
+    #[display(fmt = "Not exist @ helper, register it at `at_helpers` in the config file")]
+    AtHelperNotExist,
+    #[display(fmt = "Not valid Rust path for @ helper `{_0}`")]
+    AtHelperPath(String),
+    #[display(fmt = "@ helper need {_0} arguments")]
+    AtHelperArgsLen(usize),
+    #[display(fmt = "Not exist slot `{_0}` in the partial block")]
+    SlotNotExist(String),
+    #[display(fmt = "Partial `{_0}` isn't parsed, an inline partial may be out of scope")]
+    PartialNotParsed(String),
+    #[display(fmt = "Use `@` loop variables inside an `each` helper")]
+    LoopVar,
+    #[display(fmt = "Use `@super` inside an overridden block")]
+    SuperBlockNoParent,
     #[display(fmt = "use super without any parent")]
     SuperWithoutParent,
+    #[display(fmt = "Use fragments outside of `each`, `with` and partials with arguments")]
+    FragmentScope,
+    #[display(fmt = "Fragment `{_0}` is already defined")]
+    FragmentDuplicate(String),
+    #[display(fmt = "Not valid identifier for fragment `{_0}`")]
+    FragmentName(String),
+    #[display(fmt = "{_0}")]
+    Config(ConfigError),
     // TODO: implement display
     #[display(fmt = "Can't open file")]
     FileNotOpen(Rc<Path>),
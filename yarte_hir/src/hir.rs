@@ -1,16 +1,24 @@
+use yarte_parser::source_map::Span;
+
 /// High level intermediate representation after lowering Ast
 // TODO: serialize and deserialize for container builder
 #[derive(Debug, Clone, PartialEq)]
 pub enum HIR {
     Lit(String),
-    Expr(Box<syn::Expr>),
+    /// Content of a `{{R}}` block, written as is
+    Raw(String),
+    /// Escaped expression with its span in the template
+    Expr(Box<syn::Expr>, Span),
     Safe(Box<syn::Expr>),
     Each(Box<Each>),
     IfElse(Box<IfElse>),
     Local(Box<syn::Local>),
+    BlockHelper(Box<BlockHelper>),
+    Match(Box<Match>),
+    /// Sends the rendered chunk, only in stream renders
+    Flush,
 }
 
-// TODO: to switch
 #[derive(Debug, Clone, PartialEq)]
 pub struct IfElse {
     pub ifs: (syn::Expr, Vec<HIR>),
@@ -19,9 +27,27 @@ pub struct IfElse {
 }
 
 /// for expr in args { body }
+///
+/// With `els`, it's rendered when the loop produces zero items
 #[derive(Debug, Clone, PartialEq)]
 pub struct Each {
     pub args: syn::Expr,
     pub body: Vec<HIR>,
     pub expr: syn::Expr,
+    pub els: Option<Vec<HIR>>,
+}
+
+/// path(&args, body)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockHelper {
+    pub path: syn::Path,
+    pub args: syn::Expr,
+    pub body: Vec<HIR>,
+}
+
+/// match expr { pat if guard => { body } }
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub expr: syn::Expr,
+    pub arms: Vec<(syn::Pat, Option<syn::Expr>, Vec<HIR>)>,
 }
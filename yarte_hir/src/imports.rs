@@ -1,9 +1,9 @@
-use std::{fs::read_to_string, mem, path::Path, rc::Rc};
+use std::{collections::HashSet, fs::read_to_string, mem, path::Path, rc::Rc};
 
-use yarte_helpers::config::Config;
+use yarte_helpers::config::{Config, ConfigError};
 use yarte_parser::{
-    parse, ErrorMessage, Helper, MResult, Node, OwnParsed, PError, Parsed, Partial, PartialBlock,
-    SNode,
+    parse, ErrorMessage, Helper, MResult, Node, OwnParsed, PError, Partial, PartialBlock, SNode,
+    SStr,
 };
 
 // TODO: Error
@@ -15,31 +15,97 @@ fn get_nodes(src: &str, path: Rc<Path>) -> MResult<Vec<SNode<'static>>, PError>
 }
 
 // TODO: Error
-fn get_nodes_from_path(path: Rc<Path>) -> MResult<(String, Vec<SNode<'static>>), PError> {
-    // TODO: error message
-    let src = read_to_string(Rc::clone(&path)).expect("exist file");
+fn get_nodes_from_path(
+    path: Rc<Path>,
+    tag: &SStr,
+) -> MResult<(String, Vec<SNode<'static>>), PError> {
+    let src = read_to_string(Rc::clone(&path)).map_err(|e| ErrorMessage {
+        message: PError::Config(ConfigError::Read(path.to_path_buf(), e.to_string())),
+        span: tag.span(),
+    })?;
 
     let nodes = get_nodes(&src, path)?;
     Ok((src, nodes))
 }
 
-fn add_nodes(
-    on_path: Rc<Path>,
-    path: &str,
-    ctx: Parsed,
-    c: &Config,
-    stack: &mut Vec<(Rc<Path>, String, Vec<SNode<'static>>)>,
-    errors: &mut Vec<ErrorMessage<PError>>,
+/// Files to scan with the inline partials in scope of its call
+type Stack = Vec<(Rc<Path>, Vec<&'static str>)>;
+
+/// Calls of `nodes` to file partials, the inline partials are in scope
+/// of the block declaring them, like in the lowering
+fn partials(
+    nodes: &[SNode<'static>],
+    inline: &mut Vec<&'static str>,
+    out: &mut Vec<(SStr<'static>, Vec<&'static str>)>,
 ) {
-    let path = c.resolve_partial(Rc::clone(&on_path), path);
-    if ctx.get(&path).is_none() {
-        match get_nodes_from_path(Rc::clone(&path)) {
-            Ok((src, nodes)) => {
-                stack.push((path, src, nodes));
+    fn declared<'n>(nodes: &'n [SNode<'static>]) -> impl Iterator<Item = &'static str> + 'n {
+        nodes.iter().filter_map(|n| match n.t() {
+            Node::Inline(_, name, _) => Some(*name.t()),
+            _ => None,
+        })
+    }
+    fn call(
+        path: &SStr<'static>,
+        inline: &[&'static str],
+        out: &mut Vec<(SStr<'static>, Vec<&'static str>)>,
+    ) {
+        // Inline partials are lowered from the nodes of its parent
+        if !inline.contains(path.t()) {
+            out.push((path.clone(), inline.to_vec()));
+        }
+    }
+
+    let len = inline.len();
+    inline.extend(declared(nodes));
+    for node in nodes {
+        match node.t() {
+            Node::Helper(helper) => match helper.as_ref() {
+                Helper::Each(_, _, nodes, else_) => {
+                    partials(nodes, inline, out);
+                    if let Some((_, nodes)) = else_ {
+                        partials(nodes, inline, out);
+                    }
+                }
+                Helper::If((_, _, if_nodes), else_ifs, else_) => {
+                    partials(if_nodes, inline, out);
+                    for (_, _, nodes) in else_ifs {
+                        partials(nodes, inline, out);
+                    }
+                    if let Some((_, nodes)) = else_ {
+                        partials(nodes, inline, out);
+                    }
+                }
+                Helper::With(_, _, nodes) => partials(nodes, inline, out),
+                Helper::Unless(_, _, nodes) => partials(nodes, inline, out),
+                Helper::Match(_, _, arms) => {
+                    for (_, _, nodes) in arms {
+                        partials(nodes, inline, out);
+                    }
+                }
+                Helper::Defined(_, _, _, nodes) => partials(nodes, inline, out),
+            },
+            Node::Partial(Partial(_, path, _)) => call(path, inline, out),
+            Node::PartialBlock(PartialBlock(_, path, _, ref nodes)) => {
+                partials(nodes, inline, out);
+                call(path, inline, out);
+            }
+            Node::Inline(_, _, nodes) => partials(nodes, inline, out),
+            Node::Extends(_, path, nodes) => {
+                // The inline partials of the child are in scope of the parent
+                let len = inline.len();
+                inline.extend(declared(nodes));
+                out.push((path.clone(), inline.clone()));
+                partials(nodes, inline, out);
+                inline.truncate(len);
             }
-            Err(e) => errors.push(e),
+            Node::NamedBlock(_, _, nodes) => partials(nodes, inline, out),
+            Node::Fragment(_, _, nodes) => partials(nodes, inline, out),
+            Node::SlotDef(_, _, nodes) => partials(nodes, inline, out),
+            Node::Slot(_, _, Some((_, nodes))) => partials(nodes, inline, out),
+            _ => {}
         }
     }
+    inline.truncate(len);
 }
 
 pub fn resolve_imports(
@@ -55,57 +121,45 @@ pub fn resolve_imports(
             return Err(e);
         }
     };
-    let mut stack = vec![(path, src, nodes)];
+    ctx.insert(Rc::clone(&path), (src, nodes));
+    let mut seen = HashSet::new();
+    seen.insert((Rc::clone(&path), vec![]));
+    let mut stack: Stack = vec![(path, vec![])];
     let mut errors = vec![];
 
-    while let Some((on_path, src, nodes)) = stack.pop() {
-        let mut stack_nodes = vec![&nodes];
-        while let Some(nodes) = stack_nodes.pop() {
-            for node in nodes {
-                match node.t() {
-                    Node::Helper(helper) => match helper.as_ref() {
-                        Helper::Each(_, _, nodes) => {
-                            stack_nodes.push(nodes);
-                        }
-                        Helper::If((_, _, if_nodes), else_ifs, else_) => {
-                            stack_nodes.push(if_nodes);
-                            for (_, _, nodes) in else_ifs {
-                                stack_nodes.push(nodes)
-                            }
-                            if let Some((_, nodes)) = else_ {
-                                stack_nodes.push(nodes)
-                            }
-                        }
-                        Helper::With(_, _, nodes) => stack_nodes.push(nodes),
-                        Helper::Unless(_, _, nodes) => stack_nodes.push(nodes),
-                        Helper::Defined(_, _, _, nodes) => stack_nodes.push(nodes),
-                    },
-                    Node::Partial(Partial(_, path, _)) => {
-                        add_nodes(
-                            Rc::clone(&on_path),
-                            path.t(),
-                            ctx,
-                            c,
-                            &mut stack,
-                            &mut errors,
-                        );
+    while let Some((on_path, mut inline)) = stack.pop() {
+        let mut calls = vec![];
+        partials(&ctx[&on_path].1, &mut inline, &mut calls);
+        for (tag, mut inline) in calls {
+            let path = match c.resolve_partial(Rc::clone(&on_path), tag.t()) {
+                Ok(path) => path,
+                Err(e) => {
+                    errors.push(ErrorMessage {
+                        message: PError::Config(e),
+                        span: tag.span(),
+                    });
+                    continue;
+                }
+            };
+            inline.sort_unstable();
+            inline.dedup();
+            // A file is scanned again for each set of inline partials in scope
+            if !seen.insert((Rc::clone(&path), inline.clone())) {
+                continue;
+            }
+            if !ctx.contains_key(&path) {
+                match get_nodes_from_path(Rc::clone(&path), &tag) {
+                    Ok((src, nodes)) => {
+                        ctx.insert(Rc::clone(&path), (src, nodes));
                     }
-                    Node::PartialBlock(PartialBlock(_, path, _, ref nodes)) => {
-                        stack_nodes.push(nodes);
-                        add_nodes(
-                            Rc::clone(&on_path),
-                            path.t(),
-                            ctx,
-                            c,
-                            &mut stack,
-                            &mut errors,
-                        );
+                    Err(e) => {
+                        errors.push(e);
+                        continue;
                     }
-                    _ => {}
                 }
             }
+            stack.push((path, inline));
         }
-        ctx.insert(on_path, (src, nodes));
     }
 
     if errors.is_empty() {
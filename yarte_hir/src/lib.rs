@@ -1,5 +1,11 @@
 #![allow(unknown_lints, clippy::type_complexity, clippy::match_on_vec_items)]
-use std::{collections::BTreeMap, mem, path::Path, rc::Rc, str};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+    path::Path,
+    rc::Rc,
+    str,
+};
 
 use quote::{format_ident, quote};
 use syn::{
@@ -12,10 +18,13 @@ use syn::{
 use v_eval::{eval, Value};
 use v_htmlescape::escape;
 
-use yarte_helpers::config::Config;
+use yarte_helpers::{
+    config::{Config, Mode},
+    helpers::escape::{Escaped, Escaper},
+};
 use yarte_parser::{
-    source_map::Span, AtHelperKind, ErrorMessage, Helper, Node, Parsed, Partial, PartialBlock,
-    SExpr, SNode, SVExpr, Ws,
+    AtHelperKind, ErrorMessage, Helper, Node, Parsed, Partial, PartialBlock, SArm, SExpr, SNode,
+    SVExpr, Ws, LOOP_VAR_PREFIX,
 };
 
 #[macro_use]
@@ -34,6 +43,8 @@ use self::{
     scope::Scope,
     visit_partial::visit_partial,
 };
+pub use yarte_parser::source_map::Span;
+
 pub use self::{
     hir::*,
     imports::resolve_imports,
@@ -45,6 +56,10 @@ pub struct HIROptions {
     pub is_text: bool,
     pub resolve_to_self: bool,
     pub parent: &'static str,
+    /// Lowers `{{@flush}}` and flushes at the iterations of the outer `{{#each}}`
+    pub stream: bool,
+    /// Binds `@index`, `@first`, `@last` and `@key` in the `{{#each}}` loops
+    pub loop_var: bool,
 }
 
 impl Default for HIROptions {
@@ -53,6 +68,8 @@ impl Default for HIROptions {
             resolve_to_self: true,
             is_text: false,
             parent: "yarte",
+            stream: false,
+            loop_var: true,
         }
     }
 }
@@ -63,6 +80,21 @@ pub fn generate(
     parsed: Parsed,
     opt: HIROptions,
 ) -> Result<Vec<HIR>, Vec<ErrorMessage<GError>>> {
+    LoweringContext::new(c, s, parsed, opt)
+        .build()
+        .map(|(ir, _)| ir)
+}
+
+/// Sub-trees marked with `{{#fragment "name"}}`, lowered as templates of the same struct
+pub type Fragments = Vec<(String, Vec<HIR>)>;
+
+/// Like `generate`, with the fragments of the template
+pub fn generate_with_fragments(
+    c: &Config,
+    s: &Struct,
+    parsed: Parsed,
+    opt: HIROptions,
+) -> Result<(Vec<HIR>, Fragments), Vec<ErrorMessage<GError>>> {
     LoweringContext::new(c, s, parsed, opt).build()
 }
 
@@ -75,8 +107,9 @@ enum On {
 #[derive(Debug)]
 enum Writable<'a> {
     Lit(&'a str),
+    Raw(&'a str),
     LitP(String),
-    Expr(Box<syn::Expr>, bool),
+    Expr(Box<syn::Expr>, bool, Span),
 }
 
 /// lowering from `SNode` to `HIR`
@@ -100,7 +133,7 @@ struct LoweringContext<'a> {
     // TODO: remove in favor of anything
     pub(self) partial: Option<(BTreeMap<String, syn::Expr>, usize)>,
     // TODO: remove LoweringContext in favor of reference to state
-    block: Vec<(Ws, &'a [SNode<'a>], LoweringContext<'a>)>,
+    block: Vec<(Ws, &'a [SNode<'a>], Slots<'a>, LoweringContext<'a>)>,
     /// current file path
     on_path: Rc<Path>,
     /// buffer for writable
@@ -125,6 +158,41 @@ struct LoweringContext<'a> {
     skip_ws: bool,
     // Copiable
     recursion: usize,
+    /// `last` loop variables used in each bodies
+    // UnAlloc init
+    last: BTreeSet<String>,
+    /// inline partials in scope
+    // UnAlloc init
+    inline: Vec<Inline<'a>>,
+    /// overrides of named blocks, most derived first
+    // UnAlloc init
+    blocks: BTreeMap<&'a str, Vec<Named<'a>>>,
+    /// named blocks in render with its position for `@super`
+    // UnAlloc init
+    supers: Vec<(Vec<Named<'a>>, usize)>,
+    /// lowered fragments
+    // UnAlloc init
+    fragments: Fragments,
+}
+
+/// Inline partial declared with `{{#*inline "name"}}`
+#[derive(Clone)]
+struct Inline<'a> {
+    name: &'a str,
+    ws: (Ws, Ws),
+    path: Rc<Path>,
+    nodes: &'a [SNode<'a>],
+}
+
+/// Slots of partial block declared with `{{#*slot name}}`
+type Slots<'a> = BTreeMap<&'a str, ((Ws, Ws), &'a [SNode<'a>])>;
+
+/// Named block declared with `{{#block name}}`
+#[derive(Clone)]
+struct Named<'a> {
+    ws: (Ws, Ws),
+    path: Rc<Path>,
+    nodes: &'a [SNode<'a>],
 }
 
 // TODO: remove
@@ -147,6 +215,11 @@ impl<'a> Clone for LoweringContext<'a> {
             recursion: self.recursion,
             next_ws: self.next_ws,
             skip_ws: self.skip_ws,
+            last: BTreeSet::new(),
+            inline: self.inline.clone(),
+            blocks: self.blocks.clone(),
+            supers: self.supers.clone(),
+            fragments: vec![],
         }
     }
 }
@@ -174,18 +247,27 @@ impl<'a> LoweringContext<'a> {
             skip_ws: false,
             errors: vec![],
             recursion: 0,
+            last: BTreeSet::new(),
+            inline: vec![],
+            blocks: BTreeMap::new(),
+            supers: vec![],
+            fragments: vec![],
             buf_err: vec![],
             spans: vec![],
         }
     }
 
-    fn build(mut self) -> Result<Vec<HIR>, Vec<ErrorMessage<GError>>> {
+    fn build(mut self) -> Result<(Vec<HIR>, Fragments), Vec<ErrorMessage<GError>>> {
         let mut buf = vec![];
 
         let nodes: &[SNode] = &self.ctx.get(&self.on_path).expect("No nodes parsed").1;
 
         self.handle(nodes, &mut buf);
         self.write_buf_writable(&mut buf);
+        // The lowering stops at the errors, the state can be unbalanced
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
         debug_assert_eq!(self.scp.len(), 1);
         debug_assert_eq!(self.scp.root(), &parse_str::<syn::Expr>("self").unwrap());
         debug_assert!(self.on.is_empty());
@@ -198,14 +280,23 @@ impl<'a> LoweringContext<'a> {
         assert!((0..buf.len() - 1)
             .all(|i| !matches!((&buf[i], &buf[i + 1]), (HIR::Lit(..), HIR::Lit(..)))));
 
-        if self.errors.is_empty() {
-            Ok(buf)
-        } else {
-            Err(self.errors)
-        }
+        Ok((buf, self.fragments))
     }
 
-    fn handle(&mut self, nodes: &'a [SNode], buf: &mut Vec<HIR>) {
+    fn handle(&mut self, nodes: &'a [SNode<'a>], buf: &mut Vec<HIR>) {
+        // Inline partials are visible in the whole block
+        let inline = self.inline.len();
+        for n in nodes {
+            if let Node::Inline(ws, name, nodes) = n.t() {
+                self.inline.push(Inline {
+                    name: name.t(),
+                    ws: *ws,
+                    path: Rc::clone(&self.on_path),
+                    nodes,
+                });
+            }
+        }
+
         for n in nodes {
             match n.t() {
                 Node::Local(expr) => {
@@ -226,7 +317,8 @@ impl<'a> LoweringContext<'a> {
                         && self.const_eval(&expr, true).is_none()
                     {
                         validator::expression(sexpr, &mut self.errors);
-                        self.buf_w.push(Writable::Expr(Box::new(expr), true));
+                        self.buf_w
+                            .push(Writable::Expr(Box::new(expr), true, sexpr.span()));
                     }
                 }
                 Node::Expr(ws, sexpr) => {
@@ -238,10 +330,11 @@ impl<'a> LoweringContext<'a> {
 
                     if self.const_eval(&expr, false).is_none() {
                         validator::expression(sexpr, &mut self.errors);
-                        self.buf_w.push(Writable::Expr(Box::new(expr), false));
+                        self.buf_w
+                            .push(Writable::Expr(Box::new(expr), false, sexpr.span()));
                     }
                 }
-                Node::Lit(l, lit, r) => self.visit_lit(l, lit.t(), r),
+                Node::Lit(l, lit, r) => self.visit_lit(l, lit.t(), r, false),
                 Node::Helper(h) => {
                     self.spans.push(n.span());
                     self.visit_helper(buf, h);
@@ -253,37 +346,122 @@ impl<'a> LoweringContext<'a> {
                             message,
                             span: n.span(),
                         });
-                        return;
+                        break;
                     }
                 }
                 // TODO
                 Node::Comment(_) => self.skip_ws(),
+                Node::Inline(ws, ..) => {
+                    self.flush_ws(ws.0);
+                    self.prepare_ws(ws.1);
+                }
+                Node::Extends(ws, path, nodes) => {
+                    if let Err(message) = self.visit_extends(buf, *ws, path.t(), nodes) {
+                        self.errors.push(ErrorMessage {
+                            message,
+                            span: n.span(),
+                        });
+                        break;
+                    }
+                }
+                Node::NamedBlock(ws, name, nodes) => {
+                    let mut chain = self.blocks.get(name.t()).cloned().unwrap_or_default();
+                    chain.push(Named {
+                        ws: *ws,
+                        path: Rc::clone(&self.on_path),
+                        nodes,
+                    });
+                    self.flush_ws(ws.0);
+                    if let Err(message) = self.visit_named(buf, chain, 0) {
+                        self.errors.push(ErrorMessage {
+                            message,
+                            span: n.span(),
+                        });
+                        break;
+                    }
+                    self.prepare_ws(ws.1);
+                }
+                Node::Fragment(ws, name, nodes) => {
+                    if let Err(message) = self.visit_fragment(*ws, name.t(), nodes) {
+                        self.errors.push(ErrorMessage {
+                            message,
+                            span: name.span(),
+                        });
+                    }
+                    self.handle_ws(ws.0);
+                    self.scp.push_scope(vec![]);
+                    self.handle(nodes, buf);
+                    self.scp.pop();
+                    self.handle_ws(ws.1);
+                }
+                Node::Super(ws) => match self.supers.last() {
+                    Some((chain, i)) if i + 1 < chain.len() => {
+                        let (chain, i) = (chain.clone(), i + 1);
+                        self.flush_ws(*ws);
+                        if let Err(message) = self.visit_named(buf, chain, i) {
+                            self.errors.push(ErrorMessage {
+                                message,
+                                span: n.span(),
+                            });
+                            break;
+                        }
+                        self.prepare_ws(*ws);
+                    }
+                    _ => {
+                        self.flush_ws(*ws);
+                        self.errors.push(ErrorMessage {
+                            message: GError::SuperBlockNoParent,
+                            span: n.span(),
+                        });
+                        self.prepare_ws(*ws);
+                    }
+                },
                 Node::Raw(ws, l, v, r) => {
                     self.handle_ws(ws.0);
-                    self.visit_lit(l, v.t(), r);
+                    self.visit_lit(l, v.t(), r, true);
                     self.handle_ws(ws.1);
                 }
+                Node::SlotDef(ws, ..) => {
+                    self.flush_ws(ws.0);
+                    self.prepare_ws(ws.1);
+                }
+                Node::Slot(ws, name, default) => {
+                    let o_ws = default.as_ref().map_or(*ws, |(c_ws, _)| (ws.0, c_ws.1));
+                    if let Some((i_ws, block, slots, mut old)) = self.block.pop() {
+                        match (slots.get(name.t()), default) {
+                            (Some(&(s_ws, nodes)), _) => {
+                                self.visit_block(buf, o_ws, (s_ws.0 .1, s_ws.1 .0), nodes, &mut old)
+                            }
+                            (None, Some((c_ws, nodes))) => {
+                                self.handle_ws(*ws);
+                                self.scp.push_scope(vec![]);
+                                self.handle(nodes, buf);
+                                self.scp.pop();
+                                self.handle_ws(*c_ws);
+                            }
+                            (None, None) => {
+                                self.flush_ws(o_ws);
+                                self.errors.push(ErrorMessage {
+                                    message: GError::SlotNotExist(name.t().to_string()),
+                                    span: n.span(),
+                                });
+                                self.prepare_ws(o_ws);
+                            }
+                        }
+                        self.block.push((i_ws, block, slots, old));
+                    } else {
+                        self.flush_ws(o_ws);
+                        self.errors.push(ErrorMessage {
+                            message: GError::PartialBlockNoParent,
+                            span: n.span(),
+                        });
+                        self.prepare_ws(o_ws);
+                    }
+                }
                 Node::Block(ws) => {
-                    if let Some((i_ws, block, mut old)) = self.block.pop() {
-                        old.next_ws = self.next_ws.take();
-                        old.skip_ws = self.skip_ws;
-                        old.scp.count = self.scp.count;
-                        old.buf_w.append(&mut self.buf_w);
-
-                        old.handle_ws((ws.0, i_ws.0));
-
-                        old.handle(block, buf);
-
-                        self.errors.append(&mut old.errors);
-                        self.buf_w.append(&mut old.buf_w);
-
-                        self.scp.count = old.scp.count;
-                        self.next_ws = old.next_ws.take();
-                        self.skip_ws = old.skip_ws;
-
-                        self.handle_ws((i_ws.1, ws.1));
-
-                        self.block.push((i_ws, block, old));
+                    if let Some((i_ws, block, slots, mut old)) = self.block.pop() {
+                        self.visit_block(buf, *ws, i_ws, block, &mut old);
+                        self.block.push((i_ws, block, slots, old));
                     } else {
                         self.flush_ws(*ws);
                         self.errors.push(ErrorMessage {
@@ -320,20 +498,31 @@ impl<'a> LoweringContext<'a> {
                             let mut arg = (*args.t()[0]).clone();
                             self.visit_expr_mut(&mut arg);
                             let expr = parse2(quote!((&(#arg).__as_json()))).unwrap();
-                            self.buf_w.push(Writable::Expr(Box::new(expr), false))
+                            self.buf_w
+                                .push(Writable::Expr(Box::new(expr), false, n.span()))
                         }
                         JsonPretty => {
                             let mut arg = (*args.t()[0]).clone();
                             self.visit_expr_mut(&mut arg);
                             let expr = parse2(quote!(&(#arg).__as_json_pretty())).unwrap();
-                            self.buf_w.push(Writable::Expr(Box::new(expr), false))
+                            self.buf_w
+                                .push(Writable::Expr(Box::new(expr), false, n.span()))
+                        }
+                        Flush => {
+                            if self.opt.stream {
+                                self.write_buf_writable(buf);
+                                buf.push(HIR::Flush);
+                            }
                         }
+                        Defined(name) => self.visit_at_helper(name, args, n.span()),
                     }
                 }
                 #[allow(unreachable_patterns)]
                 _ => (),
             }
         }
+
+        self.inline.truncate(inline);
     }
 
     // TODO:
@@ -354,7 +543,7 @@ impl<'a> LoweringContext<'a> {
         }
     }
 
-    fn visit_lit(&mut self, lws: &'a str, lit: &'a str, rws: &'a str) {
+    fn visit_lit(&mut self, lws: &'a str, lit: &'a str, rws: &'a str, raw: bool) {
         debug_assert!(self.next_ws.is_none(), "{:?} {:?} ", self.next_ws, lit);
         if !lws.is_empty() {
             if self.skip_ws {
@@ -368,7 +557,11 @@ impl<'a> LoweringContext<'a> {
         }
 
         if !lit.is_empty() {
-            self.buf_w.push(Writable::Lit(lit));
+            self.buf_w.push(if raw {
+                Writable::Raw(lit)
+            } else {
+                Writable::Lit(lit)
+            });
         }
 
         if !rws.is_empty() {
@@ -379,14 +572,214 @@ impl<'a> LoweringContext<'a> {
     fn visit_helper(&mut self, buf: &mut Vec<HIR>, h: &'a Helper<'a>) {
         use yarte_parser::Helper::*;
         match h {
-            Each(ws, e, b) => self.visit_each(buf, *ws, e, b),
+            Each(ws, e, b, els) => self.visit_each(buf, *ws, e, b, els),
             If(ifs, elsif, els) => self.visit_if(buf, ifs, elsif, els),
             With(ws, e, b) => self.visit_with(buf, *ws, e, b),
             Unless(ws, e, b) => self.visit_unless(buf, *ws, e, b),
-            Defined(..) => unimplemented!(),
+            Match(ws, e, arms) => self.visit_match(buf, *ws, e, arms),
+            Defined(ws, name, e, b) => self.visit_defined(buf, *ws, name, e, b),
+        }
+    }
+
+    fn visit_match(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        sexpr: &SExpr,
+        arms: &'a [(Ws, SArm, Vec<SNode<'a>>)],
+    ) {
+        let mut expr = (***sexpr.t()).clone();
+        self.visit_expr_mut(&mut expr);
+        self.write_errors(sexpr.span());
+        validator::expression(sexpr, &mut self.errors);
+
+        self.handle_ws(ws.0);
+        self.write_buf_writable(buf);
+
+        let mut hir_arms: Vec<(syn::Pat, Option<syn::Expr>, Vec<HIR>)> = vec![];
+        for (a_ws, sarm, nodes) in arms {
+            self.handle_ws(*a_ws);
+            if let Some((_, _, body)) = hir_arms.last_mut() {
+                self.write_buf_writable(body);
+            }
+
+            self.scp.push_scope(vec![]);
+            let mut pat = sarm.t().pat.clone();
+            self.visit_arm_pat(&mut pat, true);
+            let guard = sarm.t().guard.as_ref().map(|(_, guard)| {
+                let mut guard = (**guard).clone();
+                self.visit_expr_mut(&mut guard);
+                guard
+            });
+            self.write_errors(sarm.span());
+
+            let mut body = Vec::new();
+            self.handle(nodes, &mut body);
+            self.scp.pop();
+            hir_arms.push((pat, guard, body));
+        }
+
+        self.handle_ws(ws.1);
+        if let Some((_, _, body)) = hir_arms.last_mut() {
+            self.write_buf_writable(body);
+        }
+
+        // Match by reference, never move out of the template struct
+        let expr = parse2(quote!(&(#expr))).unwrap();
+        buf.push(HIR::Match(Box::new(Match {
+            expr,
+            arms: hir_arms,
+        })))
+    }
+
+    /// Bind the lowercase identifiers of the pattern to the scope,
+    /// the others are paths like `None` or constants
+    fn visit_arm_pat(&mut self, pat: &mut syn::Pat, bind: bool) {
+        use syn::Pat::*;
+        match pat {
+            Ident(syn::PatIdent { ident, subpat, .. }) => {
+                let name = ident.to_string();
+                if name.starts_with(|c: char| c.is_lowercase() || c == '_') {
+                    let bound = if bind {
+                        None
+                    } else {
+                        self.scp
+                            .get_by(&name)
+                            .and_then(|e| parse2::<syn::Ident>(quote!(#e)).ok())
+                    };
+                    *ident = bound.unwrap_or_else(|| self.scp.push_ident(&name));
+                }
+                if let Some((_, pat)) = subpat {
+                    self.visit_arm_pat(pat, bind);
+                }
+            }
+            Or(syn::PatOr { cases, .. }) => {
+                for (i, pat) in cases.iter_mut().enumerate() {
+                    self.visit_arm_pat(pat, bind && i == 0);
+                }
+            }
+            Tuple(syn::PatTuple { elems, .. })
+            | TupleStruct(syn::PatTupleStruct {
+                pat: syn::PatTuple { elems, .. },
+                ..
+            })
+            | Slice(syn::PatSlice { elems, .. }) => {
+                for pat in elems.iter_mut() {
+                    self.visit_arm_pat(pat, bind);
+                }
+            }
+            Struct(syn::PatStruct { fields, .. }) => {
+                for syn::FieldPat {
+                    colon_token, pat, ..
+                } in fields.iter_mut()
+                {
+                    // shorthand `Foo { bar }` need the renamed binding
+                    *colon_token = Some(<Token![:]>::default());
+                    self.visit_arm_pat(pat, bind);
+                }
+            }
+            Reference(syn::PatReference { pat, .. }) | Box(syn::PatBox { pat, .. }) => {
+                self.visit_arm_pat(pat, bind)
+            }
+            Lit(..) | Path(..) | Range(..) | Rest(..) | Wild(..) => (),
+            _ => self.buf_err.push((GError::NotAvailable, pat.span())),
         }
     }
 
+    fn visit_defined(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        name: &str,
+        sargs: &SExpr,
+        nodes: &'a [SNode],
+    ) {
+        let path = match self.c.resolve_helper(name) {
+            Some(path) => match parse_str::<syn::Path>(path) {
+                Ok(path) => path,
+                Err(_) => {
+                    self.errors.push(ErrorMessage {
+                        message: GError::HelperPath(path.into()),
+                        span: *self.spans.last().expect("helper span"),
+                    });
+                    return;
+                }
+            },
+            None => {
+                self.errors.push(ErrorMessage {
+                    message: GError::HelperNotExist,
+                    span: *self.spans.last().expect("helper span"),
+                });
+                return;
+            }
+        };
+
+        let mut args = (***sargs.t()).clone();
+        self.visit_expr_mut(&mut args);
+        self.write_errors(sargs.span());
+        validator::helper(sargs, &mut self.errors);
+
+        self.handle_ws(ws.0);
+        self.write_buf_writable(buf);
+
+        self.scp.push_scope(vec![]);
+        let mut body = Vec::new();
+        self.handle(nodes, &mut body);
+        self.scp.pop();
+
+        self.handle_ws(ws.1);
+        self.write_buf_writable(&mut body);
+
+        buf.push(HIR::BlockHelper(Box::new(BlockHelper { path, args, body })))
+    }
+
+    fn visit_at_helper(&mut self, name: &str, args: &SVExpr, span: Span) {
+        let helper = match self
+            .s
+            .at_helpers
+            .get(name)
+            .or_else(|| self.c.resolve_at_helper(name))
+        {
+            Some(helper) => helper,
+            None => {
+                self.errors.push(ErrorMessage {
+                    message: GError::AtHelperNotExist,
+                    span,
+                });
+                return;
+            }
+        };
+        let path = match parse_str::<syn::Path>(&helper.path) {
+            Ok(path) => path,
+            Err(_) => {
+                self.errors.push(ErrorMessage {
+                    message: GError::AtHelperPath(helper.path.clone()),
+                    span,
+                });
+                return;
+            }
+        };
+        if args.t().len() != helper.args {
+            self.errors.push(ErrorMessage {
+                message: GError::AtHelperArgsLen(helper.args),
+                span: args.span(),
+            });
+            return;
+        }
+
+        validator::at_helper_args(args, &mut self.errors);
+        let safe = helper.safe;
+        let mut exprs = Vec::with_capacity(args.t().len());
+        for arg in args.t() {
+            let mut arg = (**arg).clone();
+            self.visit_expr_mut(&mut arg);
+            exprs.push(arg);
+        }
+        self.write_errors(args.span());
+        let expr = parse2(quote!(#path(#(&(#exprs)),*))).unwrap();
+        self.buf_w.push(Writable::Expr(Box::new(expr), safe, span))
+    }
+
     fn visit_unless(
         &mut self,
         buf: &mut Vec<HIR>,
@@ -460,16 +853,31 @@ impl<'a> LoweringContext<'a> {
         ws: (Ws, Ws),
         sargs: &'a SExpr,
         nodes: &'a [SNode<'a>],
+        els: &'a Option<(Ws, Vec<SNode<'a>>)>,
     ) {
         self.spans.push(sargs.span());
-        // TODO
-        let loop_var = true;
+        let loop_var = self.opt.loop_var;
         let mut args = (***sargs.t()).clone();
         self.visit_expr_mut(&mut args);
         self.write_errors(sargs.span());
 
         if let Some(args) = self.eval_iter(&args) {
-            self.const_iter(buf, ws, args, nodes, loop_var);
+            let args: Vec<Value> = args.into_iter().collect();
+            match els {
+                Some((e_ws, els)) if args.is_empty() => {
+                    self.flush_ws(ws.0);
+                    self.prepare_ws(*e_ws);
+                    self.scp.push_scope(vec![]);
+                    self.handle(els, buf);
+                    self.scp.pop();
+                    self.handle_ws(ws.1);
+                }
+                Some((e_ws, _)) => {
+                    self.const_iter(buf, (ws.0, *e_ws), args, nodes, loop_var);
+                    self.prepare_ws(ws.1);
+                }
+                None => self.const_iter(buf, ws, args, nodes, loop_var),
+            }
             self.spans.pop();
             return;
         }
@@ -484,32 +892,75 @@ impl<'a> LoweringContext<'a> {
         let v = self.scp.push_ident("__key_");
         let (args, expr) = if loop_var {
             let i = self.scp.push_ident("__index_");
+            let l = self.scp.push_ident("__last_");
             let args = if let syn::Expr::Range(..) = args {
-                syn::parse2::<syn::Expr>(quote!(((#args).enumerate()))).unwrap()
+                quote!(((#args).enumerate()))
             } else {
-                syn::parse2::<syn::Expr>(quote!(((&(#args)).__into_citer().enumerate()))).unwrap()
+                quote!(((&(#args)).__into_citer().enumerate()))
             };
-            (args, syn::parse2::<syn::Expr>(quote!((#i, #v))).unwrap())
+            (args, Some((i, l)))
         } else {
             let args = if let syn::Expr::Range(..) = args {
                 args
             } else {
                 syn::parse2::<syn::Expr>(quote!(((&(#args)).__into_citer()))).unwrap()
             };
-            (args, syn::parse2::<syn::Expr>(quote!(#v)).unwrap())
+            (quote!(#args), None)
         };
+        // Stream renders send a chunk at the end of each iteration of the outer each
+        let flush = self.opt.stream && !self.on.iter().any(|x| matches!(x, On::Each(_)));
         self.on.push(On::Each(id));
 
         let mut body = Vec::new();
         self.handle(nodes, &mut body);
-        self.handle_ws(ws.1);
-        self.write_buf_writable(&mut body);
 
         self.on.pop();
         self.scp.pop();
+
+        // Only pay for the peekable adapter when `last` is used
+        let (args, expr) = match expr {
+            Some((i, l)) if self.last.remove(&l.to_string()) => (
+                syn::parse2::<syn::Expr>(quote!((#args.__with_last()))).unwrap(),
+                syn::parse2::<syn::Expr>(quote!((#l, (#i, #v)))).unwrap(),
+            ),
+            Some((i, _)) => (
+                syn::parse2::<syn::Expr>(args).unwrap(),
+                syn::parse2::<syn::Expr>(quote!((#i, #v))).unwrap(),
+            ),
+            None => (
+                syn::parse2::<syn::Expr>(args).unwrap(),
+                syn::parse2::<syn::Expr>(quote!(#v)).unwrap(),
+            ),
+        };
+
+        let els = if let Some((e_ws, els)) = els {
+            self.handle_ws(*e_ws);
+            self.write_buf_writable(&mut body);
+
+            self.scp.push_scope(vec![]);
+            let mut buf_els = Vec::new();
+            self.handle(els, &mut buf_els);
+            self.scp.pop();
+
+            self.handle_ws(ws.1);
+            self.write_buf_writable(&mut buf_els);
+            Some(buf_els)
+        } else {
+            self.handle_ws(ws.1);
+            self.write_buf_writable(&mut body);
+            None
+        };
+        if flush {
+            body.push(HIR::Flush);
+        }
         self.spans.pop();
 
-        buf.push(HIR::Each(Box::new(Each { args, body, expr })))
+        buf.push(HIR::Each(Box::new(Each {
+            args,
+            body,
+            expr,
+            els,
+        })))
     }
 
     fn visit_if(
@@ -646,9 +1097,37 @@ impl<'a> LoweringContext<'a> {
             return Err(GError::RecursionLimit);
         }
 
-        // TODO: identifiers
-        let p = self.c.resolve_partial(&self.on_path, path);
-        let nodes = self.ctx.get(&p).expect("partial parsed").1.as_slice();
+        let (p, nodes, i_ws) = match self.inline.iter().rev().find(|x| x.name == path) {
+            Some(Inline {
+                ws, path, nodes, ..
+            }) => (Rc::clone(path), *nodes, Some(*ws)),
+            None => {
+                // TODO: identifiers
+                let p = self
+                    .c
+                    .resolve_partial(&self.on_path, path)
+                    .map_err(GError::Config)?;
+                let nodes = self
+                    .ctx
+                    .get(&p)
+                    .ok_or_else(|| GError::PartialNotParsed(path.into()))?
+                    .1
+                    .as_slice();
+                (p, nodes, None)
+            }
+        };
+
+        macro_rules! handle_nodes {
+            () => {
+                if let Some(ws) = i_ws {
+                    self.prepare_ws(ws.0);
+                }
+                self.handle(nodes, buf);
+                if let Some(ws) = i_ws {
+                    self.flush_ws(ws.1);
+                }
+            };
+        }
 
         // TODO: to on path stack without duplicates
         let p = mem::replace(&mut self.on_path, p);
@@ -656,7 +1135,15 @@ impl<'a> LoweringContext<'a> {
         let block = if let Some((ws, block)) = block {
             self.flush_ws((a_ws.0, false));
             // TODO: heritage
-            self.block.push(((a_ws.1, ws.0), block, self.clone()));
+            let slots = block
+                .iter()
+                .filter_map(|n| match n.t() {
+                    Node::SlotDef(ws, name, nodes) => Some((*name.t(), (*ws, nodes.as_slice()))),
+                    _ => None,
+                })
+                .collect();
+            self.block
+                .push(((a_ws.1, ws.0), block, slots, self.clone()));
             Some(ws.1)
         } else {
             self.flush_ws(a_ws);
@@ -664,7 +1151,7 @@ impl<'a> LoweringContext<'a> {
         };
         if exprs.t().is_empty() {
             self.scp.push_scope(vec![]);
-            self.handle(nodes, buf);
+            handle_nodes!();
             self.scp.pop();
         } else {
             let (no_visited, scope) = visit_partial(exprs, &mut self.errors);
@@ -688,7 +1175,7 @@ impl<'a> LoweringContext<'a> {
 
                 let on = mem::take(&mut self.on);
 
-                self.handle(nodes, buf);
+                handle_nodes!();
 
                 parent.count = self.scp.count;
                 self.scp = parent;
@@ -700,7 +1187,7 @@ impl<'a> LoweringContext<'a> {
                 let last = mem::replace(&mut self.partial, Some((cur, self.on.len())));
                 self.scp.push_scope(vec![]);
 
-                self.handle(nodes, buf);
+                handle_nodes!();
 
                 self.scp.pop();
                 self.partial = last;
@@ -718,6 +1205,158 @@ impl<'a> LoweringContext<'a> {
         Ok(())
     }
 
+    /// Lowering nodes of the partial block call in the context of the caller
+    fn visit_block(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: Ws,
+        i_ws: Ws,
+        nodes: &'a [SNode<'a>],
+        old: &mut LoweringContext<'a>,
+    ) {
+        old.next_ws = self.next_ws.take();
+        old.skip_ws = self.skip_ws;
+        old.scp.count = self.scp.count;
+        old.buf_w.append(&mut self.buf_w);
+
+        old.handle_ws((ws.0, i_ws.0));
+
+        old.handle(nodes, buf);
+
+        self.errors.append(&mut old.errors);
+        self.buf_w.append(&mut old.buf_w);
+        self.last.append(&mut old.last);
+
+        self.scp.count = old.scp.count;
+        self.next_ws = old.next_ws.take();
+        self.skip_ws = old.skip_ws;
+
+        self.handle_ws((i_ws.1, ws.1));
+    }
+
+    fn visit_extends(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        path: &str,
+        nodes: &'a [SNode<'a>],
+    ) -> GResult<()> {
+        self.recursion += 1;
+        if self.s.recursion_limit < self.recursion {
+            return Err(GError::RecursionLimit);
+        }
+
+        let p = self
+            .c
+            .resolve_partial(&self.on_path, path)
+            .map_err(GError::Config)?;
+        let parent = self
+            .ctx
+            .get(&p)
+            .ok_or_else(|| GError::PartialNotParsed(path.into()))?
+            .1
+            .as_slice();
+
+        // Overrides and inline partials of the child
+        let inline = self.inline.len();
+        let mut overrides = vec![];
+        for n in nodes {
+            match n.t() {
+                Node::NamedBlock(ws, name, nodes) => {
+                    self.blocks.entry(name.t()).or_default().push(Named {
+                        ws: *ws,
+                        path: Rc::clone(&self.on_path),
+                        nodes,
+                    });
+                    overrides.push(*name.t());
+                }
+                Node::Inline(ws, name, nodes) => self.inline.push(Inline {
+                    name: name.t(),
+                    ws: *ws,
+                    path: Rc::clone(&self.on_path),
+                    nodes,
+                }),
+                _ => (),
+            }
+        }
+
+        let p = mem::replace(&mut self.on_path, p);
+        self.flush_ws(ws.0);
+        self.scp.push_scope(vec![]);
+        self.handle(parent, buf);
+        self.scp.pop();
+        self.prepare_ws(ws.1);
+        self.on_path = p;
+
+        for name in overrides {
+            if let Some(chain) = self.blocks.get_mut(name) {
+                chain.pop();
+                if chain.is_empty() {
+                    self.blocks.remove(name);
+                }
+            }
+        }
+        self.inline.truncate(inline);
+        self.recursion -= 1;
+        Ok(())
+    }
+
+    fn visit_named(&mut self, buf: &mut Vec<HIR>, chain: Vec<Named<'a>>, i: usize) -> GResult<()> {
+        self.recursion += 1;
+        if self.s.recursion_limit < self.recursion {
+            return Err(GError::RecursionLimit);
+        }
+
+        let Named { ws, path, nodes } = chain[i].clone();
+        let p = mem::replace(&mut self.on_path, path);
+        self.prepare_ws(ws.0);
+        self.supers.push((chain, i));
+        self.scp.push_scope(vec![]);
+        self.handle(nodes, buf);
+        self.scp.pop();
+        self.supers.pop();
+        self.flush_ws(ws.1);
+        self.on_path = p;
+
+        self.recursion -= 1;
+        Ok(())
+    }
+
+    /// Lowers the fragment by itself, in a copy of the context
+    fn visit_fragment(
+        &mut self,
+        ws: (Ws, Ws),
+        name: &'a str,
+        nodes: &'a [SNode<'a>],
+    ) -> GResult<()> {
+        if !self.on.is_empty() || self.partial.is_some() {
+            return Err(GError::FragmentScope);
+        }
+        if parse_str::<syn::Ident>(name).is_err() {
+            return Err(GError::FragmentName(name.into()));
+        }
+        if self.fragments.iter().any(|(x, _)| x == name) {
+            return Err(GError::FragmentDuplicate(name.into()));
+        }
+
+        let mut ctx = self.clone();
+        let mut buf = vec![];
+        ctx.next_ws = None;
+        ctx.prepare_ws(ws.0);
+        ctx.scp.push_scope(vec![]);
+        ctx.handle(nodes, &mut buf);
+        ctx.scp.pop();
+        ctx.flush_ws(ws.1);
+        ctx.write_buf_writable(&mut buf);
+        if buf.is_empty() {
+            buf.push(HIR::Lit("".into()));
+        }
+        // Errors are reported by the lowering in place
+        self.fragments.push((name.into(), buf));
+
+        Ok(())
+    }
+
     fn const_eval(&mut self, expr: &syn::Expr, safe: bool) -> Option<()> {
         macro_rules! push_some {
             ($expr:expr) => {{
@@ -733,8 +1372,13 @@ impl<'a> LoweringContext<'a> {
             Value::Int(a) => push_some!(a),
             Value::Float(a) => push_some!(a),
             Value::Bool(a) => push_some!(a),
-            Value::Str(a) if safe || self.opt.is_text => push_some!(a),
-            Value::Str(a) => push_some!(escape(&a)),
+            Value::Str(a) if safe => push_some!(a),
+            Value::Str(a) => match self.s.resolve_mode(self.opt.is_text) {
+                Mode::Html => push_some!(escape(&a)),
+                Mode::Text => push_some!(a),
+                Mode::Json => push_some!(Escaped(&a, Escaper::JsonString)),
+                Mode::Xml => push_some!(Escaped(&a, Escaper::Xml)),
+            },
             _ => None,
         })
     }
@@ -761,11 +1405,20 @@ impl<'a> LoweringContext<'a> {
         self.on.push(On::Each(id));
         self.flush_ws(ws.0);
         if loop_var {
-            for (i, v) in args.into_iter().enumerate() {
-                handle!(vec![
+            let mut args = args.into_iter().enumerate().peekable();
+            while let Some((i, v)) = args.next() {
+                let mut ctx = vec![
                     parse_str(&v.to_string()).unwrap(),
-                    parse_str(&i.to_string()).unwrap()
-                ]);
+                    parse_str(&i.to_string()).unwrap(),
+                    parse_str(&args.peek().is_none().to_string()).unwrap(),
+                ];
+                if let Value::Vec(kv) = &v {
+                    if let [key, value] = kv.as_slice() {
+                        ctx.push(parse_str(&key.to_string()).unwrap());
+                        ctx.push(parse_str(&value.to_string()).unwrap());
+                    }
+                }
+                handle!(ctx);
             }
         } else {
             for v in args.into_iter() {
@@ -779,7 +1432,7 @@ impl<'a> LoweringContext<'a> {
 
     #[inline]
     fn eval_expr(&self, expr: &syn::Expr) -> Option<Value> {
-        eval(&BTreeMap::new(), expr)
+        eval(&self.s.consts, expr)
     }
 
     fn eval_bool(&mut self, expr: &syn::Expr) -> Option<bool> {
@@ -834,7 +1487,7 @@ impl<'a> LoweringContext<'a> {
     }
 
     fn resolve_path(
-        &self,
+        &mut self,
         syn::ExprPath { attrs, qself, path }: &syn::ExprPath,
     ) -> GResult<syn::Expr> {
         if qself.is_some() || !attrs.is_empty() {
@@ -862,7 +1515,12 @@ impl<'a> LoweringContext<'a> {
             ($ident:expr, $j:expr) => {{
                 debug_assert!(self.scp.get($j).is_some(), "each var {} {:?}", $j, self.scp);
                 debug_assert!(!self.scp[$j].is_empty());
-                match $ident {
+                // `@name` loop variables, `last`, `key` and `value` are fields of the item
+                let (ident, reserved) = match $ident.strip_prefix(LOOP_VAR_PREFIX) {
+                    Some(ident) => (ident, true),
+                    None => ($ident, false),
+                };
+                match ident {
                     "index0" => return Ok(self.scp[$j][1].clone()),
                     "index" => {
                         let ident = &self.scp[$j][1];
@@ -872,6 +1530,25 @@ impl<'a> LoweringContext<'a> {
                         let ident = &self.scp[$j][1];
                         writes!((#ident == 0))
                     },
+                    "last" if reserved => {
+                        let ident = &self.scp[$j][2];
+                        self.last.insert(quote!(#ident).to_string());
+                        return Ok(ident.clone());
+                    },
+                    "key" if reserved => match self.scp[$j].get(3) {
+                        Some(key) => return Ok(key.clone()),
+                        None => {
+                            let ident = &self.scp[$j][0];
+                            writes!(#ident.0)
+                        }
+                    },
+                    "value" if reserved => match self.scp[$j].get(4) {
+                        Some(value) => return Ok(value.clone()),
+                        None => {
+                            let ident = &self.scp[$j][0];
+                            writes!(#ident.1)
+                        }
+                    },
                     "this" => return Ok(self.scp[$j][0].clone()),
                     ident => {
                         index_var!(ident, $j);
@@ -887,6 +1564,9 @@ impl<'a> LoweringContext<'a> {
             ($ident:expr, $j:expr) => {{
                 debug_assert!(self.scp.get($j).is_some());
                 debug_assert!(!self.scp[$j].is_empty());
+                if $ident.starts_with(LOOP_VAR_PREFIX) {
+                    return Err(GError::LoopVar);
+                }
                 index_var!($ident, $j);
                 let ident = &self.scp[$j][0];
                 let field = format_ident!("{}", $ident);
@@ -896,6 +1576,9 @@ impl<'a> LoweringContext<'a> {
 
         macro_rules! self_var {
             ($ident:ident) => {{
+                if $ident.starts_with(LOOP_VAR_PREFIX) {
+                    return Err(GError::LoopVar);
+                }
                 index_var!($ident, 0);
                 let field = format_ident!("{}", $ident);
                 if self.opt.resolve_to_self {
@@ -929,6 +1612,10 @@ impl<'a> LoweringContext<'a> {
                 .map(|x| x.is_uppercase())
                 .unwrap_or(false)
             {
+                // compile-time constant, also for the expressions evaluated at runtime
+                if let Some(expr) = self.s.consts.get(ident) {
+                    writes!((#expr))
+                }
                 let ident = &path.segments[0].ident;
                 writes!(#ident)
             }
@@ -991,11 +1678,21 @@ impl<'a> LoweringContext<'a> {
             match s {
                 Writable::Lit(s) => buf_lit.push_str(s),
                 Writable::LitP(ref s) => buf_lit.push_str(s),
-                Writable::Expr(s, wrapped) => {
+                Writable::Raw(s) => {
+                    if !buf_lit.is_empty() {
+                        buf.push(HIR::Lit(mem::take(&mut buf_lit)));
+                    }
+                    buf.push(HIR::Raw(s.into()))
+                }
+                Writable::Expr(s, wrapped, span) => {
                     if !buf_lit.is_empty() {
                         buf.push(HIR::Lit(mem::take(&mut buf_lit)));
                     }
-                    buf.push(if wrapped { HIR::Safe(s) } else { HIR::Expr(s) })
+                    buf.push(if wrapped {
+                        HIR::Safe(s)
+                    } else {
+                        HIR::Expr(s, span)
+                    })
                 }
             }
         }
@@ -1,22 +1,50 @@
 use syn::spanned::Spanned;
 
-use yarte_parser::{source_map::Span, ErrorMessage, SExpr};
+use yarte_parser::{source_map::Span, ErrorMessage, SExpr, SVExpr};
 
 use crate::error::{GError, MiddleError};
 
 pub(super) fn expression(e: &SExpr, out: &mut Vec<ErrorMessage<GError>>) {
-    use syn::Expr::*;
-    match ***e.t() {
-        Binary(..) | Call(..) | MethodCall(..) | Index(..) | Field(..) | Path(..) | Paren(..)
-        | Macro(..) | Lit(..) | Try(..) | Unary(..) | Unsafe(..) | If(..) | Loop(..)
-        | Match(..) | Block(..) => (),
-        _ => out.push(ErrorMessage {
+    if !is_expression(e.t()) {
+        out.push(ErrorMessage {
             message: GError::ValidatorExpression,
             span: e.span(),
-        }),
+        })
     }
 }
 
+pub(super) fn at_helper_args(e: &SVExpr, out: &mut Vec<ErrorMessage<GError>>) {
+    if !e.t().iter().all(|arg| is_expression(arg)) {
+        out.push(ErrorMessage {
+            message: GError::ValidatorExpression,
+            span: e.span(),
+        })
+    }
+}
+
+fn is_expression(e: &syn::Expr) -> bool {
+    use syn::Expr::*;
+    matches!(
+        e,
+        Binary(..)
+            | Call(..)
+            | MethodCall(..)
+            | Index(..)
+            | Field(..)
+            | Path(..)
+            | Paren(..)
+            | Macro(..)
+            | Lit(..)
+            | Try(..)
+            | Unary(..)
+            | Unsafe(..)
+            | If(..)
+            | Loop(..)
+            | Match(..)
+            | Block(..)
+    )
+}
+
 pub(super) fn ifs(e: &SExpr, out: &mut Vec<ErrorMessage<GError>>) {
     use syn::Expr::*;
     match ***e.t() {
@@ -42,6 +70,18 @@ pub(super) fn each(e: &SExpr, out: &mut Vec<ErrorMessage<GError>>) {
     }
 }
 
+pub(super) fn helper(e: &SExpr, out: &mut Vec<ErrorMessage<GError>>) {
+    use syn::Expr::*;
+    match ***e.t() {
+        Binary(..) | Call(..) | MethodCall(..) | Index(..) | Field(..) | Path(..) | Paren(..)
+        | Macro(..) | Lit(..) | Try(..) | Unary(..) | Tuple(..) | Reference(..) => (),
+        _ => out.push(ErrorMessage {
+            message: GError::ValidatorHelper,
+            span: e.span(),
+        }),
+    }
+}
+
 pub(super) fn unless(e: &SExpr, out: &mut Vec<ErrorMessage<GError>>) {
     use syn::Expr::*;
     match ***e.t() {
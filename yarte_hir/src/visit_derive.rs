@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     convert::{TryFrom, TryInto},
     path::{Path, PathBuf},
     rc::Rc,
@@ -6,9 +7,11 @@ use std::{
 
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{parse_str, visit::Visit, Data, Error, ItemEnum};
+use syn::{
+    parse::Parser, parse_str, punctuated::Punctuated, visit::Visit, Data, Error, ItemEnum, Token,
+};
 
-use yarte_helpers::config::Config;
+use yarte_helpers::config::{AtHelper, Config, Mode};
 
 // TODO:
 const RECURSION_LIMIT: usize = 128;
@@ -23,10 +26,19 @@ pub fn visit_derive<'a>(
 #[derive(Debug)]
 pub struct Struct<'a> {
     pub path: Rc<Path>,
+    /// Source is read from `path`, not an inline `src`
+    pub from_file: bool,
     pub print: Print,
     pub recursion_limit: usize,
     pub msgs: Option<ItemEnum>,
     pub script: Option<String>,
+    /// Error type of the fallible renders
+    pub error: Option<String>,
+    /// Escape mode of the attribute `mode` or of the extension of `path`
+    pub mode: Option<Mode>,
+    pub at_helpers: BTreeMap<String, AtHelper>,
+    /// Compile-time constants of the config file and of the attribute `consts`
+    pub consts: BTreeMap<String, syn::Expr>,
     pub fields: Vec<syn::Field>,
     pub ident: &'a syn::Ident,
     generics: &'a syn::Generics,
@@ -41,6 +53,22 @@ impl<'a> Struct<'a> {
 
         quote!(impl #impl_generics #t for #ident #orig_ty_generics #where_clause { #body })
     }
+
+    /// Escape mode of the template, `is_text` gives the default of the derive
+    pub fn resolve_mode(&self, is_text: bool) -> Mode {
+        self.mode
+            .unwrap_or(if is_text { Mode::Text } else { Mode::Html })
+    }
+
+    /// Inherent implementation
+    pub fn implement(&self, body: &TokenStream) -> TokenStream {
+        let Struct {
+            ident, generics, ..
+        } = *self;
+        let (impl_generics, orig_ty_generics, where_clause) = generics.split_for_impl();
+
+        quote!(impl #impl_generics #ident #orig_ty_generics #where_clause { #body })
+    }
 }
 
 struct StructBuilder<'a> {
@@ -48,8 +76,14 @@ struct StructBuilder<'a> {
     path: Option<Rc<Path>>,
     print: Option<Print>,
     script: Option<String>,
+    error: Option<String>,
+    mode: Option<Mode>,
+    path_mode: Option<Mode>,
     recursion_limit: Option<usize>,
     src: Option<String>,
+    inline: bool,
+    at_helpers: BTreeMap<String, AtHelper>,
+    consts: BTreeMap<String, syn::Expr>,
     err: Vec<Error>,
     ident: String,
     config: &'a Config,
@@ -64,8 +98,18 @@ impl<'a> StructBuilder<'a> {
             path: None,
             print: None,
             script: None,
+            error: None,
+            mode: None,
+            path_mode: None,
             recursion_limit: None,
             src: None,
+            inline: false,
+            at_helpers: BTreeMap::new(),
+            consts: config
+                .get_consts()
+                .iter()
+                .map(|(name, expr)| (name.clone(), parse_str(expr).expect("valid constant")))
+                .collect(),
             err: vec![],
         }
     }
@@ -106,6 +150,7 @@ impl<'a> StructBuilder<'a> {
             }
         }
 
+        let from_file = !self.inline;
         let (path, src) = match (self.path, self.src) {
             (Some(path), Some(src)) => (path, src),
             _ => {
@@ -121,6 +166,7 @@ impl<'a> StructBuilder<'a> {
             Ok((
                 Struct {
                     path,
+                    from_file,
                     recursion_limit: self.recursion_limit.unwrap_or(RECURSION_LIMIT),
                     fields: self.fields,
                     generics,
@@ -128,6 +174,10 @@ impl<'a> StructBuilder<'a> {
                     msgs,
                     print: self.print.unwrap_or(Print::None),
                     script: self.script,
+                    error: self.error,
+                    mode: self.mode.or(self.path_mode),
+                    at_helpers: self.at_helpers,
+                    consts: self.consts,
                 },
                 src,
             ))
@@ -135,6 +185,70 @@ impl<'a> StructBuilder<'a> {
             Err(self.err.iter().flat_map(Error::to_compile_error).collect())
         }
     }
+
+    fn visit_at_helper(&mut self, i: &syn::MetaList) {
+        let mut name = None;
+        let mut path = None;
+        let mut args = None;
+        let mut safe = false;
+        for nested in &i.nested {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path: p,
+                    lit: syn::Lit::Str(s),
+                    ..
+                })) if p.is_ident("name") => name = Some(s.value()),
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path: p,
+                    lit: syn::Lit::Str(s),
+                    ..
+                })) if p.is_ident("path") => path = Some(s.value()),
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path: p,
+                    lit: syn::Lit::Int(s),
+                    ..
+                })) if p.is_ident("args") => args = s.base10_parse().ok(),
+                syn::NestedMeta::Meta(syn::Meta::Path(p)) if p.is_ident("safe") => safe = true,
+                _ => self.err.push(Error::new_spanned(
+                    nested,
+                    "invalid argument for 'at_helper', expected `name = \"..\"`, `path = \"..\"`, `args = N` or `safe`",
+                )),
+            }
+        }
+
+        match (name, path, args) {
+            (Some(name), Some(path), Some(args)) => {
+                self.at_helpers.insert(name, AtHelper { path, args, safe });
+            }
+            _ => self.err.push(Error::new_spanned(
+                i,
+                "attribute 'at_helper' must specify 'name', 'path' and 'args'",
+            )),
+        }
+    }
+
+    /// Constants of `consts = "NAME = value, .."` over the ones of the config file
+    fn visit_consts(&mut self, i: &syn::MetaNameValue, s: &syn::LitStr) {
+        let exprs = match Punctuated::<syn::Expr, Token![,]>::parse_terminated.parse_str(&s.value())
+        {
+            Ok(exprs) => exprs,
+            Err(e) => return self.err.push(Error::new_spanned(i, e)),
+        };
+        for expr in exprs {
+            if let syn::Expr::Assign(syn::ExprAssign { left, right, .. }) = &expr {
+                if let syn::Expr::Path(syn::ExprPath { path, .. }) = &**left {
+                    if let Some(name) = path.get_ident() {
+                        self.consts.insert(name.to_string(), (**right).clone());
+                        continue;
+                    }
+                }
+            }
+            self.err.push(Error::new_spanned(
+                i,
+                "attribute 'consts' must be a list of `NAME = value`",
+            ));
+        }
+    }
 }
 
 impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
@@ -142,6 +256,14 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
         self.fields.push(e.clone());
     }
 
+    fn visit_meta_list(&mut self, i: &'a syn::MetaList) {
+        if i.path.is_ident("at_helper") {
+            self.visit_at_helper(i);
+        } else {
+            syn::visit::visit_meta_list(self, i);
+        }
+    }
+
     fn visit_meta_name_value(&mut self, i: &'a syn::MetaNameValue) {
         let syn::MetaNameValue { path, lit, .. } = i;
         if path.is_ident("path") {
@@ -153,19 +275,33 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
                     ))
                 }
                 let mut path = PathBuf::from(s.value());
-                if let Some(ext) = path.extension() {
-                    if ext != DEFAULT_EXTENSION {
-                        self.err.push(Error::new_spanned(
-                            i,
-                            "Default extension for yarte templates is `.hbs`",
-                        ))
+                match path.extension().and_then(|ext| ext.to_str()) {
+                    Some(DEFAULT_EXTENSION) => (),
+                    // `name.<ext>` of a mode is `name.<ext>.hbs`
+                    Some(ext) if self.config.resolve_extension(ext).is_some() => {
+                        let mut p = path.into_os_string();
+                        p.push(".");
+                        p.push(DEFAULT_EXTENSION);
+                        path = p.into();
                     }
-                } else {
-                    path = path.with_extension(DEFAULT_EXTENSION);
-                };
-                let (path, src) = self.config.get_template(path.into());
-                self.path = Some(path);
-                self.src = Some(src);
+                    Some(_) => self.err.push(Error::new_spanned(
+                        i,
+                        "Default extension for yarte templates is `.hbs`",
+                    )),
+                    None => path = path.with_extension(DEFAULT_EXTENSION),
+                }
+                self.path_mode = self.config.resolve_mode(&path);
+                match self.config.get_template(path.into()) {
+                    Ok((path, src)) => {
+                        self.path = Some(path);
+                        self.src = Some(src);
+                    }
+                    Err(e) => {
+                        self.err.push(Error::new_spanned(i, e));
+                        self.path = Some(PathBuf::new().into());
+                        self.src = Some(String::new());
+                    }
+                }
             } else {
                 self.err.push(Error::new_spanned(
                     i,
@@ -188,6 +324,7 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
                         .into(),
                 );
                 self.src = Some(s.value().trim_end().to_owned());
+                self.inline = true;
             } else {
                 self.err.push(Error::new_spanned(
                     i,
@@ -217,6 +354,38 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
                     "attribute 'script' must be string literal",
                 ));
             }
+        } else if path.is_ident("error") {
+            match lit {
+                syn::Lit::Str(s) => match parse_str::<syn::Type>(&s.value()) {
+                    Ok(_) => self.error = Some(s.value()),
+                    Err(e) => self.err.push(Error::new_spanned(i, e)),
+                },
+                _ => self.err.push(Error::new_spanned(
+                    i,
+                    "attribute 'error' must be string literal",
+                )),
+            }
+        } else if path.is_ident("mode") {
+            if let syn::Lit::Str(ref s) = lit {
+                match Mode::try_from(s.value().as_str()) {
+                    Ok(mode) => self.mode = Some(mode),
+                    Err(e) => self.err.push(Error::new_spanned(i, e)),
+                }
+            } else {
+                self.err.push(Error::new_spanned(
+                    i,
+                    "attribute 'mode' must be string literal",
+                ));
+            }
+        } else if path.is_ident("consts") {
+            if let syn::Lit::Str(ref s) = lit {
+                self.visit_consts(i, s);
+            } else {
+                self.err.push(Error::new_spanned(
+                    i,
+                    "attribute 'consts' must be string literal",
+                ));
+            }
         } else if path.is_ident("recursion") {
             if let syn::Lit::Int(s) = lit {
                 self.recursion_limit = Some(s.base10_parse().unwrap());
@@ -255,4 +424,4 @@ impl TryFrom<String> for Print {
     }
 }
 
-static DEFAULT_EXTENSION: &str = "hbs";
+const DEFAULT_EXTENSION: &str = "hbs";
@@ -2,8 +2,9 @@ use std::fmt::{self, Debug, Display, Write};
 
 use annotate_snippets::{Level, Renderer, Snippet};
 use derive_more::Display;
+use proc_macro2::TokenStream;
 
-use yarte_helpers::config::Config;
+use yarte_helpers::config::{Config, ConfigError};
 
 use crate::{source_map::Span, strnom::LexError, Parsed};
 
@@ -51,6 +52,18 @@ pub enum PError {
     Helpers,
     #[display(fmt = "partial block")]
     PartialBlock,
+    #[display(fmt = "inline partial")]
+    Inline,
+    #[display(fmt = "extends")]
+    Extends,
+    #[display(fmt = "block")]
+    NamedBlock,
+    #[display(fmt = "fragment")]
+    Fragment,
+    #[display(fmt = "slot")]
+    Slot,
+    #[display(fmt = "match")]
+    Match,
     #[display(fmt = "partial path")]
     PartialPath,
     #[display(fmt = "identifier")]
@@ -59,10 +72,10 @@ pub enum PError {
     EndExpression,
     #[display(fmt = "argument{_0}")]
     Argument(DOption),
-    #[display(fmt = "Not exist @ helper")]
-    AtHelperNotExist,
     #[display(fmt = "@ helper need only {_0} argument")]
     AtHelperArgsLen(usize),
+    #[display(fmt = "{_0}")]
+    Config(ConfigError),
 }
 
 impl From<LexError> for ErrorMessage<PError> {
@@ -87,13 +100,17 @@ pub struct ErrorMessage<T: Display + Debug> {
 pub type MResult<T, E> = Result<T, ErrorMessage<E>>;
 
 // TODO: Accumulate by priority
-pub fn emitter<I, T>(sources: Parsed, config: &Config, errors: I) -> !
+/// `compile_error!` with the errors annotated in the template sources
+pub fn emitter<I, T>(sources: Parsed, config: &Config, errors: I) -> TokenStream
 where
     I: IntoIterator<Item = ErrorMessage<T>>,
     T: Display + Debug,
 {
-    let mut prefix = config.get_dir().clone();
-    prefix.pop();
+    let prefixes: Vec<_> = config
+        .get_dirs()
+        .iter()
+        .filter_map(|dir| dir.parent())
+        .collect();
     let mut errors: Vec<ErrorMessage<T>> = errors.into_iter().collect();
 
     errors.sort_unstable_by(|a, b| a.span.lo.cmp(&b.span.lo));
@@ -114,13 +131,23 @@ where
             .unwrap()
             .trim_end();
 
-        let origin = origin.strip_prefix(&prefix).unwrap().to_str().unwrap();
+        let origin = prefixes
+            .iter()
+            .find_map(|prefix| origin.strip_prefix(prefix).ok())
+            .unwrap_or(origin)
+            .to_str()
+            .unwrap();
         Snippet::source(source)
             .line_start(start.line)
             .origin(origin)
             .annotation(Level::Error.span(lo..hi).label(label))
     });
-    let message = Level::Error.title("").snippets(snippets);
+    let title = data.first().map_or("", |(label, _, _)| label.as_str());
+    let message = Level::Error.title(title).snippets(snippets);
 
-    panic!("{}", Renderer::styled().render(message))
+    let message = Renderer::plain().render(message).to_string();
+    // `compile_error!` adds the level
+    let message = message.strip_prefix("error: ").unwrap_or(&message);
+
+    syn::Error::new(proc_macro2::Span::call_site(), message).to_compile_error()
 }
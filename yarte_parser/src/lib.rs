@@ -123,6 +123,51 @@ impl Parse for Expr {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct Arm(#[serde(deserialize_with = "de_arm")] syn::Arm);
+
+impl Deref for Arm {
+    type Target = syn::Arm;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Arm {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl AsRef<syn::Arm> for Arm {
+    fn as_ref(&self) -> &syn::Arm {
+        &self.0
+    }
+}
+
+impl AsMut<syn::Arm> for Arm {
+    fn as_mut(&mut self) -> &mut syn::Arm {
+        &mut self.0
+    }
+}
+
+fn de_arm<'de, D>(deserializer: D) -> Result<syn::Arm, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    <&str>::deserialize(deserializer).and_then(|x| {
+        syn::parse_str(&format!("{} => ()", x)).map_err(|_| serde::de::Error::custom("Parse error"))
+    })
+}
+
+impl Parse for Arm {
+    fn parse(input: &ParseBuffer<'_>) -> syn::Result<Self> {
+        Ok(Arm(input.parse()?))
+    }
+}
+
+pub type SArm = S<Box<Arm>>;
 pub type SExpr = S<Box<Expr>>;
 pub type SLocal = S<Box<Local>>;
 pub type SNode<'a> = S<Node<'a>>;
@@ -156,6 +201,38 @@ pub enum Node<'a> {
     Local(SLocal),
     Partial(#[serde(borrow)] Partial<'a>),
     PartialBlock(#[serde(borrow)] PartialBlock<'a>),
+    Inline(
+        (Ws, Ws),
+        #[serde(borrow)] SStr<'a>,
+        #[serde(borrow)] Vec<SNode<'a>>,
+    ),
+    Extends(
+        (Ws, Ws),
+        #[serde(borrow)] SStr<'a>,
+        #[serde(borrow)] Vec<SNode<'a>>,
+    ),
+    NamedBlock(
+        (Ws, Ws),
+        #[serde(borrow)] SStr<'a>,
+        #[serde(borrow)] Vec<SNode<'a>>,
+    ),
+    Super(Ws),
+    /// `{{#fragment "name"}}`, rendered in place and by its own entry point
+    Fragment(
+        (Ws, Ws),
+        #[serde(borrow)] SStr<'a>,
+        #[serde(borrow)] Vec<SNode<'a>>,
+    ),
+    SlotDef(
+        (Ws, Ws),
+        #[serde(borrow)] SStr<'a>,
+        #[serde(borrow)] Vec<SNode<'a>>,
+    ),
+    Slot(
+        Ws,
+        #[serde(borrow)] SStr<'a>,
+        #[serde(borrow)] Option<(Ws, Vec<SNode<'a>>)>,
+    ),
     Block(Ws),
     Raw(
         (Ws, Ws),
@@ -169,15 +246,30 @@ pub enum Node<'a> {
 
 pub(crate) const JSON: &str = "json";
 pub(crate) const JSON_PRETTY: &str = "json_pretty";
+pub(crate) const FLUSH: &str = "flush";
+
+/// Loop variables of `{{#each}}` written `@name`, they don't shadow the fields of the item
+pub const LOOP_VARS: [&str; 6] = ["index", "index0", "first", "last", "key", "value"];
+/// Prefix of the identifier of a loop variable `@name` in the parsed expressions
+pub const LOOP_VAR_PREFIX: &str = "__yarte_loop_";
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
 pub enum AtHelperKind {
     Json,
     JsonPretty,
+    /// Sends the rendered chunk in a stream render
+    Flush,
+    /// Registered by the user, resolved at lowering
+    Defined(String),
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 pub enum Helper<'a> {
-    Each((Ws, Ws), SExpr, #[serde(borrow)] Vec<SNode<'a>>),
+    Each(
+        (Ws, Ws),
+        SExpr,
+        #[serde(borrow)] Vec<SNode<'a>>,
+        Option<(Ws, Vec<SNode<'a>>)>,
+    ),
     If(
         ((Ws, Ws), SExpr, Vec<SNode<'a>>),
         Vec<(Ws, SExpr, Vec<SNode<'a>>)>,
@@ -185,6 +277,11 @@ pub enum Helper<'a> {
     ),
     With((Ws, Ws), SExpr, #[serde(borrow)] Vec<SNode<'a>>),
     Unless((Ws, Ws), SExpr, #[serde(borrow)] Vec<SNode<'a>>),
+    Match(
+        (Ws, Ws),
+        SExpr,
+        #[serde(borrow)] Vec<(Ws, SArm, Vec<SNode<'a>>)>,
+    ),
     // TODO:
     Defined(
         (Ws, Ws),
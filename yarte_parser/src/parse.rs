@@ -1,7 +1,8 @@
 use std::fmt::Debug;
 use std::{path::Path, rc::Rc};
 
-use syn::parse_str;
+use proc_macro2::{Group, Ident, TokenStream, TokenTree};
+use syn::{parse::Parse, parse2, parse_str};
 use unicode_xid::UnicodeXID;
 
 use crate::error::{DOption, PError};
@@ -9,8 +10,9 @@ use crate::expr_list::ExprList;
 use crate::source_map::{spanned, Span, S};
 use crate::strnom::{is_ws, skip_ws, ws, LexError, PResult};
 use crate::{
-    source_map::get_cursor, AtHelperKind, Cursor, ErrorMessage, Expr, Helper, Node, Partial,
-    PartialBlock, SExpr, SNode, SVExpr, StmtLocal, Ws, JSON, JSON_PRETTY,
+    source_map::get_cursor, Arm, AtHelperKind, Cursor, ErrorMessage, Expr, Helper, Node, Partial,
+    PartialBlock, SArm, SExpr, SNode, SVExpr, StmtLocal, Ws, FLUSH, JSON, JSON_PRETTY, LOOP_VARS,
+    LOOP_VAR_PREFIX,
 };
 
 pub fn parse(path: Rc<Path>, src: &str) -> Result<Vec<SNode>, ErrorMessage<PError>> {
@@ -115,7 +117,15 @@ macro_rules! non {
 make_eater!(eat, non);
 
 const IF: &str = "if";
+const EACH: &str = "each";
 const ELSE: &str = "else";
+const INLINE: &str = "inline";
+const EXTENDS: &str = "extends";
+const BLOCK: &str = "block";
+const SLOT: &str = "slot";
+const FRAGMENT: &str = "fragment";
+const MATCH: &str = "match";
+const WHEN: &str = "when";
 
 // Test special expression `{{ else ..` and kill eater at next brackets
 macro_rules! is_else {
@@ -129,6 +139,18 @@ macro_rules! is_else {
 // If else branch eater
 make_eater!(eat_if, is_else);
 
+// Test special expression `{{ when ..` and kill eater at next brackets
+macro_rules! is_when {
+    ($buf:ident, $i:ident, $at:ident, $j:ident, $t:expr, $ws:expr) => {
+        if keyword($i.adv($at + $j + 2 + $t), WHEN).is_some() {
+            kill!($buf, $i.adv($at + $j + 2), $i, $at + $j);
+        }
+    };
+}
+
+// Match arm eater
+make_eater!(eat_when, is_when);
+
 const PARTIAL_BLOCK: &str = "@partial-block";
 pub(crate) fn expr_partial_block(c: Cursor, lws: bool) -> PResult<Node> {
     do_parse!(
@@ -137,6 +159,47 @@ pub(crate) fn expr_partial_block(c: Cursor, lws: bool) -> PResult<Node> {
     )
 }
 
+const SLOT_BLOCK: &str = "@slot";
+pub(crate) fn expr_slot(c: Cursor, lws: bool) -> PResult<Node> {
+    do_parse!(
+        c,
+        ws >> tag!(SLOT_BLOCK)
+            >> ident: call!(spanned, identifier)
+            >> rws: end_expr
+            >> (Node::Slot((lws, rws), ident, None))
+    )
+}
+
+/// Eat slot Node with default content
+fn slot_block(i: Cursor, a_lws: bool) -> PResult<Node> {
+    do_parse!(
+        i,
+        ws >> tag!(SLOT_BLOCK)
+            >> ident: call!(spanned, identifier)
+            >> a_rws: end_expr
+            >> block: eat
+            >> lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> tag!(SLOT_BLOCK)
+            >> rws: end_expr
+            >> (Node::Slot(
+                (a_lws, a_rws),
+                ident,
+                Some(((lws.is_some(), rws), block))
+            ))
+    )
+    .map_err(|e| fail_with(e, PError::Slot, i))
+}
+
+const SUPER_BLOCK: &str = "@super";
+pub(crate) fn expr_super(c: Cursor, lws: bool) -> PResult<Node> {
+    do_parse!(
+        c,
+        ws >> tag!(SUPER_BLOCK) >> rws: end_expr >> (Node::Super((lws, rws)))
+    )
+}
+
 /// Push literal at cursor with length
 fn eat_lit<'a>(nodes: &mut Vec<SNode<'a>>, i: Cursor<'a>, len: usize) {
     let lit = &i.rest[..len];
@@ -193,7 +256,13 @@ fn res(c: Cursor, lws: bool) -> PResult<Node> {
 fn par(i: Cursor, lws: bool) -> PResult<Node> {
     match expr_partial_block(i, lws) {
         Ok(x) => Ok(x),
-        Err(_) => partial(i, lws).map(|(c, p)| (c, Node::Partial(p))),
+        Err(_) => match expr_super(i, lws) {
+            Ok(x) => Ok(x),
+            Err(_) => match expr_slot(i, lws) {
+                Ok(x) => Ok(x),
+                Err(_) => partial(i, lws).map(|(c, p)| (c, Node::Partial(p))),
+            },
+        },
     }
 }
 
@@ -243,9 +312,31 @@ fn partial_block(i: Cursor, a_lws: bool) -> PResult<PartialBlock> {
 /// Eat helper Node
 pub(crate) fn hel(i: Cursor, a_lws: bool) -> PResult<Node> {
     if i.starts_with(">") {
+        if skip_ws(i.adv(1)).starts_with(SLOT_BLOCK) {
+            return slot_block(i.adv(1), a_lws);
+        }
         return partial_block(i.adv(1), a_lws).map(|(c, x)| (c, Node::PartialBlock(x)));
     }
 
+    if i.starts_with("*") {
+        if let Some(c) = keyword(i.adv(1), SLOT) {
+            return slot_def(c, a_lws);
+        }
+        return inline(i.adv(1), a_lws);
+    }
+
+    if let Some(c) = keyword(i, EXTENDS) {
+        return extends(c, a_lws);
+    }
+
+    if let Some(c) = keyword(i, BLOCK) {
+        return named_block(c, a_lws);
+    }
+
+    if let Some(c) = keyword(i, FRAGMENT) {
+        return fragment(c, a_lws);
+    }
+
     let (i, (above_ws, ident, args)) = do_parse!(
         i,
         ws >> ident: call!(spanned, identifier)
@@ -258,6 +349,14 @@ pub(crate) fn hel(i: Cursor, a_lws: bool) -> PResult<Node> {
         return if_else(above_ws, i, args);
     }
 
+    if ident.0.eq(EACH) {
+        return each(above_ws, i, args);
+    }
+
+    if ident.0.eq(MATCH) {
+        return match_helper(above_ws, i, args);
+    }
+
     let (c, (below_ws, block, c_ident)) = do_parse!(
         i,
         block: eat
@@ -274,7 +373,6 @@ pub(crate) fn hel(i: Cursor, a_lws: bool) -> PResult<Node> {
             c,
             Node::Helper(Box::new({
                 match ident.0 {
-                    "each" => Helper::Each((above_ws, below_ws), args, block),
                     "with" => Helper::With((above_ws, below_ws), args, block),
                     "unless" => Helper::Unless((above_ws, below_ws), args, block),
                     defined => Helper::Defined((above_ws, below_ws), defined, args, block),
@@ -337,6 +435,221 @@ pub(crate) fn if_else(abode_ws: Ws, i: Cursor, args: SExpr) -> PResult<Node> {
     }
 }
 
+/// Eat each Node with optional else branch
+#[inline]
+pub(crate) fn each(above_ws: Ws, i: Cursor, args: SExpr) -> PResult<Node> {
+    let (i, block) = eat_if(i)?;
+
+    let (i, els) = if let Ok((c, lws)) = do_parse!(
+        i,
+        lws: opt!(tag!("~")) >> ws >> tag!(ELSE) >> (lws.is_some())
+    ) {
+        let (c, els) = map_fail!(do_parse!(
+            c,
+            rws: end_expr >> block: eat >> (((lws, rws), block))
+        ))?;
+        (c, Some(els))
+    } else {
+        (i, None)
+    };
+
+    let (c, below_ws) = do_parse!(
+        i,
+        lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> tag!(EACH)
+            >> rws: end_expr
+            >> ((lws.is_some(), rws))
+    )
+    .map_err(|_| LexError::Fail(PError::Helpers, Span::from(i)))?;
+
+    Ok((
+        c,
+        Node::Helper(Box::new(Helper::Each(
+            (above_ws, below_ws),
+            args,
+            block,
+            els,
+        ))),
+    ))
+}
+
+/// Eat match Node with its `{{ when .. }}` arms
+#[inline]
+pub(crate) fn match_helper(above_ws: Ws, i: Cursor, args: SExpr) -> PResult<Node> {
+    let (mut i, head) = eat_when(i)?;
+    // Only whitespace and comments are allowed before the first arm
+    if let Some(n) = head.iter().find(|x| match x.t() {
+        Node::Lit(_, v, _) => !v.t().is_empty(),
+        Node::Comment(_) => false,
+        _ => true,
+    }) {
+        return Err(LexError::Fail(PError::Match, n.span()));
+    }
+
+    let mut arms = vec![];
+    loop {
+        if let Ok((c, lws)) = do_parse!(
+            i,
+            lws: opt!(tag!("~")) >> ws >> tag!(WHEN) >> (lws.is_some())
+        ) {
+            let (c, b) = map_fail!(do_parse!(
+                c,
+                ws >> pat: arm >> rws: end_expr >> block: eat_when >> (((lws, rws), pat, block))
+            ))?;
+            arms.push(b);
+            i = c;
+        } else if let Ok((c, lws)) = do_parse!(
+            i,
+            lws: opt!(tag!("~")) >> tag!("/") >> ws >> tag!(MATCH) >> (lws.is_some())
+        ) {
+            if arms.is_empty() {
+                break Err(LexError::Fail(PError::Match, Span::from(i)));
+            }
+            let (c, rws) = end_expr(c)?;
+
+            break Ok((
+                c,
+                Node::Helper(Box::new(Helper::Match((above_ws, (lws, rws)), args, arms))),
+            ));
+        } else {
+            break Err(LexError::Fail(PError::Match, Span::from(i)));
+        }
+    }
+}
+
+/// Eat inline partial Node
+#[inline]
+pub(crate) fn inline(i: Cursor, a_lws: bool) -> PResult<Node> {
+    let (c, (ws, ident, block)) = do_parse!(
+        i,
+        tag!(INLINE)
+            >> ws
+            >> ident: call!(spanned, inline_name)
+            >> a_rws: end_expr
+            >> block: eat
+            >> lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> tag!(INLINE)
+            >> rws: end_expr
+            >> (((a_lws, a_rws), (lws.is_some(), rws)), ident, block)
+    )
+    .map_err(|e| fail_with(e, PError::Inline, i))?;
+
+    Ok((c, Node::Inline(ws, ident, block)))
+}
+
+/// Eat slot definition Node, inside of a partial block
+#[inline]
+pub(crate) fn slot_def(i: Cursor, a_lws: bool) -> PResult<Node> {
+    let (c, (ws, ident, block)) = do_parse!(
+        i,
+        ident: call!(spanned, identifier)
+            >> a_rws: end_expr
+            >> block: eat
+            >> lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> tag!(SLOT)
+            >> rws: end_expr
+            >> (((a_lws, a_rws), (lws.is_some(), rws)), ident, block)
+    )
+    .map_err(|e| fail_with(e, PError::Slot, i))?;
+
+    Ok((c, Node::SlotDef(ws, ident, block)))
+}
+
+/// Eat extends Node, the block has the overrides of the parent template
+#[inline]
+pub(crate) fn extends(i: Cursor, a_lws: bool) -> PResult<Node> {
+    let (c, (ws, ident, block)) = do_parse!(
+        i,
+        ident: call!(spanned, path)
+            >> a_rws: end_expr
+            >> block: eat
+            >> lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> tag!(EXTENDS)
+            >> rws: end_expr
+            >> (((a_lws, a_rws), (lws.is_some(), rws)), ident, block)
+    )
+    .map_err(|e| fail_with(e, PError::Extends, i))?;
+
+    Ok((c, Node::Extends(ws, ident, block)))
+}
+
+/// Eat named block Node
+#[inline]
+pub(crate) fn named_block(i: Cursor, a_lws: bool) -> PResult<Node> {
+    let (c, (ws, ident, block)) = do_parse!(
+        i,
+        ident: call!(spanned, identifier)
+            >> a_rws: end_expr
+            >> block: eat
+            >> lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> tag!(BLOCK)
+            >> rws: end_expr
+            >> (((a_lws, a_rws), (lws.is_some(), rws)), ident, block)
+    )
+    .map_err(|e| fail_with(e, PError::NamedBlock, i))?;
+
+    Ok((c, Node::NamedBlock(ws, ident, block)))
+}
+
+/// Eat fragment Node
+#[inline]
+pub(crate) fn fragment(i: Cursor, a_lws: bool) -> PResult<Node> {
+    let (c, (ws, ident, block)) = do_parse!(
+        i,
+        ws >> ident: call!(spanned, inline_name)
+            >> a_rws: end_expr
+            >> block: eat
+            >> lws: opt!(tag!("~"))
+            >> tag!("/")
+            >> ws
+            >> tag!(FRAGMENT)
+            >> rws: end_expr
+            >> (((a_lws, a_rws), (lws.is_some(), rws)), ident, block)
+    )
+    .map_err(|e| fail_with(e, PError::Fragment, i))?;
+
+    Ok((c, Node::Fragment(ws, ident, block)))
+}
+
+/// Eat keyword followed by whitespace
+fn keyword<'a>(i: Cursor<'a>, kw: &str) -> Option<Cursor<'a>> {
+    let i = skip_ws(i);
+    if i.starts_with(kw) && i.adv(kw.len()).chars().next().map(is_ws).unwrap_or(false) {
+        Some(i.adv(kw.len()))
+    } else {
+        None
+    }
+}
+
+/// Fail with the error of the node if not has inner failure
+fn fail_with(e: LexError, m: PError, i: Cursor) -> LexError {
+    match e {
+        LexError::Next(..) => LexError::Fail(m, Span::from(i)),
+        e => e,
+    }
+}
+
+/// Eat quoted name of inline partial
+fn inline_name(i: Cursor<'_>) -> PResult<'_, &str> {
+    let (c, _) = tag!(i, "\"")?;
+    let (c, name) = take_while!(c, |c| c != '"' && !is_ws(c))?;
+    if name.is_empty() {
+        return Err(LexError::Next(PError::PartialPath, Span::from(c)));
+    }
+    let (c, _) = tag!(c, "\"")?;
+    Ok((c, name))
+}
+
 /// Eat error Node
 fn error(i: Cursor) -> PResult<Node> {
     do_parse!(i, ws >> args: args_list >> end_expr >> (Node::Error(args)))
@@ -400,11 +713,11 @@ macro_rules! make_argument {
                                 })
                         };
                     }
-                    if 0 < j && i.adv_starts_with(at + j - 1, "~}}") {
-                        let (_, s, _) = trim(&i.rest[..j - 1]);
+                    if 0 < at + j && i.adv_starts_with(at + j - 1, "~}}") {
+                        let (_, s, _) = trim(&i.rest[..at + j - 1]);
                         break cl!(s, 1);
-                    } else if i.adv_starts_with(j + 1, "}") {
-                        let (_, s, _) = trim(&i.rest[..j]);
+                    } else if i.adv_starts_with(at + j + 1, "}") {
+                        let (_, s, _) = trim(&i.rest[..at + j]);
                         break cl!(s, 0);
                     }
 
@@ -426,6 +739,9 @@ make_argument!(arguments, eat_expr, PResult<SExpr>);
 // Eat arguments at partials
 make_argument!(args_list, eat_expr_list, PResult<SVExpr>);
 
+// Eat pattern at match arms
+make_argument!(arm, eat_arm, PResult<SArm>);
+
 /// Eat safe Node
 fn safe(i: Cursor, lws: bool) -> PResult<Node> {
     let mut at = 0;
@@ -462,14 +778,12 @@ fn safe(i: Cursor, lws: bool) -> PResult<Node> {
 
 #[inline]
 fn at_helper(i: Cursor, lws: bool) -> PResult<Node> {
-    let (c, (name, args, rws)) = do_parse!(
-        i,
-        ws >> tag!("@")
-            >> name: call!(spanned, identifier)
-            >> args: args_list
-            >> rws: end_expr
-            >> ((name, args, rws))
-    )?;
+    let (c, name) = do_parse!(i, ws >> tag!("@") >> name: call!(spanned, identifier) >> (name))?;
+    // `@last` is a loop variable in an expression
+    if LOOP_VARS.contains(name.t()) {
+        return Err(LexError::Next(PError::Expr(DOption::None), Span::from(i)));
+    }
+    let (c, (args, rws)) = do_parse!(c, args: args_list >> rws: end_expr >> ((args, rws)))?;
 
     macro_rules! check_args_len {
         ($len:expr) => {
@@ -490,7 +804,14 @@ fn at_helper(i: Cursor, lws: bool) -> PResult<Node> {
                 Node::AtHelper((lws, rws), AtHelperKind::JsonPretty, args),
             ))
         }
-        _ => Err(LexError::Fail(PError::AtHelperNotExist, name.span())),
+        FLUSH => {
+            check_args_len!(0);
+            Ok((c, Node::AtHelper((lws, rws), AtHelperKind::Flush, args)))
+        }
+        name => Ok((
+            c,
+            Node::AtHelper((lws, rws), AtHelperKind::Defined(name.into()), args),
+        )),
     }
 }
 
@@ -591,13 +912,58 @@ impl MiddleError {
     }
 }
 
+/// Parse syn with the loop variables `@name`
+fn parse_loop_vars<T: Parse>(i: &str) -> syn::Result<T> {
+    if i.contains('@') {
+        parse2(loop_vars(parse_str(i)?))
+    } else {
+        parse_str(i)
+    }
+}
+
+/// Replaces the loop variables `@name` with its reserved identifier
+fn loop_vars(ts: TokenStream) -> TokenStream {
+    let mut out = vec![];
+    let mut iter = ts.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '@' => match iter.peek() {
+                Some(TokenTree::Ident(ident)) if LOOP_VARS.contains(&&*ident.to_string()) => {
+                    let ident = Ident::new(&format!("{LOOP_VAR_PREFIX}{ident}"), ident.span());
+                    out.push(TokenTree::Ident(ident));
+                    iter.next();
+                }
+                _ => out.push(TokenTree::Punct(p)),
+            },
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), loop_vars(g.stream()));
+                group.set_span(g.span());
+                out.push(TokenTree::Group(group));
+            }
+            tt => out.push(tt),
+        }
+    }
+    out.into_iter().collect()
+}
+
 /// Parse syn expression
 fn eat_expr(i: &str) -> Result<Box<crate::Expr>, MiddleError> {
-    parse_str::<Expr>(i)
+    parse_loop_vars::<Expr>(i)
         .map(Box::new)
         .map_err(|e| MiddleError::new(i, e))
 }
 
+/// Parse syn match arm pattern with optional guard
+fn eat_arm(i: &str) -> Result<Box<crate::Arm>, MiddleError> {
+    parse_str::<Arm>(&format!("{} => ()", i))
+        .map(Box::new)
+        .map_err(|e| {
+            let mut e = MiddleError::new(i, e);
+            e.span = (e.span.0.min(i.len()), e.span.1.min(i.len()));
+            e
+        })
+}
+
 /// Parse syn local
 fn eat_local(i: &str) -> Result<Box<crate::Local>, MiddleError> {
     parse_str::<StmtLocal>(i)
@@ -608,7 +974,7 @@ fn eat_local(i: &str) -> Result<Box<crate::Local>, MiddleError> {
 
 /// Parse syn expression comma separated list
 pub(crate) fn eat_expr_list(i: &str) -> Result<Vec<crate::Expr>, MiddleError> {
-    parse_str::<ExprList>(i)
+    parse_loop_vars::<ExprList>(i)
         .map(Into::into)
         .map_err(|e| MiddleError::new(i, e))
 }
@@ -1,7 +1,7 @@
 use syn::parse_str;
 
 use crate::{
-    eat_expr_list, eat_if, hel, if_else,
+    eat_expr_list, eat_if, hel, if_else, match_helper,
     source_map::{Span, S},
     trim, Cursor, Helper,
     Node::*,
@@ -121,11 +121,155 @@ fn test_helpers() {
                         Span { lo: 22, hi: 30 },
                     ),
                 ],
+                None,
             )))
         )
     );
 }
 
+#[test]
+fn test_each_else() {
+    let rest = "each name }}{{this}}{{~ else ~}} foo{{/each}}";
+    assert_eq!(
+        hel(Cursor { rest, off: 0 }, false).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Helper(Box::new(Helper::Each(
+                (WS, WS),
+                S(
+                    Box::new(parse_str::<crate::Expr>("name").unwrap()),
+                    Span { lo: 5, hi: 9 },
+                ),
+                vec![S(
+                    Expr(
+                        WS,
+                        S(
+                            Box::new(parse_str::<crate::Expr>("this").unwrap()),
+                            Span { lo: 14, hi: 18 },
+                        ),
+                    ),
+                    Span { lo: 12, hi: 20 },
+                )],
+                Some((
+                    (true, true),
+                    vec![S(
+                        Lit(" ", S("foo", Span { lo: 33, hi: 36 }), ""),
+                        Span { lo: 32, hi: 36 },
+                    )]
+                )),
+            )))
+        )
+    );
+}
+
+#[test]
+fn test_argument_brace() {
+    let rest = "each { a } ~}}{{this}}{{/each}}";
+    assert_eq!(
+        hel(Cursor { rest, off: 0 }, false).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Helper(Box::new(Helper::Each(
+                ((false, true), WS),
+                S(
+                    Box::new(parse_str::<crate::Expr>("{ a }").unwrap()),
+                    Span { lo: 5, hi: 10 },
+                ),
+                vec![S(
+                    Expr(
+                        WS,
+                        S(
+                            Box::new(parse_str::<crate::Expr>("this").unwrap()),
+                            Span { lo: 16, hi: 20 },
+                        ),
+                    ),
+                    Span { lo: 14, hi: 22 },
+                )],
+                None,
+            )))
+        )
+    );
+}
+
+#[test]
+fn test_inline() {
+    let rest = "*inline \"foo\"~}} bar{{/inline}}";
+    assert_eq!(
+        hel(Cursor { rest, off: 0 }, false).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Inline(
+                ((false, true), WS),
+                S("foo", Span { lo: 8, hi: 13 }),
+                vec![S(
+                    Lit(" ", S("bar", Span { lo: 17, hi: 20 }), ""),
+                    Span { lo: 16, hi: 20 },
+                )],
+            )
+        )
+    );
+}
+
+#[test]
+fn test_extends() {
+    let rest = "extends base }}{{#block title }}foo{{/block }}{{/extends}}";
+    assert_eq!(
+        hel(Cursor { rest, off: 0 }, false).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Extends(
+                (WS, WS),
+                S("base", Span { lo: 8, hi: 12 }),
+                vec![S(
+                    NamedBlock(
+                        (WS, WS),
+                        S("title", Span { lo: 24, hi: 29 }),
+                        vec![S(
+                            Lit("", S("foo", Span { lo: 32, hi: 35 }), ""),
+                            Span { lo: 32, hi: 35 },
+                        )],
+                    ),
+                    Span { lo: 15, hi: 46 },
+                )],
+            )
+        )
+    );
+}
+
+#[test]
+fn test_fragment() {
+    let rest = "fragment \"row\" ~}}foo{{/fragment}}";
+    assert_eq!(
+        hel(Cursor { rest, off: 0 }, false).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Fragment(
+                ((false, true), WS),
+                S("row", Span { lo: 9, hi: 14 }),
+                vec![S(
+                    Lit("", S("foo", Span { lo: 18, hi: 21 }), ""),
+                    Span { lo: 18, hi: 21 },
+                )],
+            )
+        )
+    );
+}
+
 #[test]
 fn test_if_else() {
     let rest = "foo{{/if}}";
@@ -238,6 +382,59 @@ fn test_else_if() {
     );
 }
 
+#[test]
+fn test_match() {
+    let rest = "{{ when Some(_) }}yes{{ when None }}no{{/match}}";
+    let args = S(
+        Box::new(parse_str::<crate::Expr>("bar").unwrap()),
+        Span { lo: 0, hi: 0 },
+    );
+
+    assert_eq!(
+        match_helper(WS, Cursor { rest, off: 0 }, args.clone()).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Helper(Box::new(Helper::Match(
+                (WS, WS),
+                args.clone(),
+                vec![
+                    (
+                        WS,
+                        S(
+                            Box::new(parse_str::<crate::Arm>("Some(_) => ()").unwrap()),
+                            Span { lo: 8, hi: 15 },
+                        ),
+                        vec![S(
+                            Lit("", S("yes", Span { lo: 18, hi: 21 }), ""),
+                            Span { lo: 18, hi: 21 },
+                        )]
+                    ),
+                    (
+                        WS,
+                        S(
+                            Box::new(parse_str::<crate::Arm>("None => ()").unwrap()),
+                            Span { lo: 29, hi: 33 },
+                        ),
+                        vec![S(
+                            Lit("", S("no", Span { lo: 36, hi: 38 }), ""),
+                            Span { lo: 36, hi: 38 },
+                        )]
+                    ),
+                ],
+            )))
+        )
+    );
+
+    let rest = "foo{{ when _ }}bar{{/match}}";
+    assert!(match_helper(WS, Cursor { rest, off: 0 }, args.clone()).is_err());
+
+    let rest = "{{/match}}";
+    assert!(match_helper(WS, Cursor { rest, off: 0 }, args).is_err());
+}
+
 #[test]
 fn test_expr_list() {
     let src = "bar, foo = \"bar\"\n, fuu = 1  , goo = true,    ";